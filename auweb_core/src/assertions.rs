@@ -0,0 +1,207 @@
+use serde_json::{self, Value};
+use regex::Regex;
+use jsonpath;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AssertionResult {
+    pub assertion: String,
+    pub passed: bool,
+    pub message: String,
+}
+
+// Accepts comma separated codes, ranges like 200-299 and classes like 2xx
+pub fn parse_status_list(text: &str) -> Result<Vec<(u16, u16)>, String> {
+    text.split(',').map(|x| x.trim()).filter(|x| !x.is_empty()).map(|item| {
+        let lower = item.to_lowercase();
+        let parse = |x: &str| x.trim().parse::<u16>().map_err(|_| format!("Invalid status {}", item));
+
+        if lower.len() == 3 && lower.ends_with("xx") {
+            parse(&lower[..1]).map(|x| (x * 100, x * 100 + 99))
+        } else if let Some(pos) = lower.find('-') {
+            Ok((parse(&lower[..pos])?, parse(&lower[pos + 1..])?))
+        } else {
+            parse(&lower).map(|x| (x, x))
+        }
+    }).collect()
+}
+
+pub fn status_matches(list: &[(u16, u16)], status: u16) -> bool {
+    list.iter().any(|x| status >= x.0 && status <= x.1)
+}
+
+fn split_word(text: &str) -> (&str, &str) {
+    let text = text.trim();
+
+    match text.find(char::is_whitespace) {
+        Some(pos) => (&text[..pos], text[pos..].trim()),
+        None => (text, "")
+    }
+}
+
+// The path ends at the first space outside quotes and brackets, so $['odd key'] stays whole
+fn split_path(text: &str) -> (&str, &str) {
+    let text = text.trim();
+    let mut quote = None;
+    let mut depth = 0;
+
+    for (pos, c) in text.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => (),
+            None if c == '\'' || c == '"' => quote = Some(c),
+            None if c == '[' => depth += 1,
+            None if c == ']' && depth > 0 => depth -= 1,
+            None if c.is_whitespace() && depth == 0 => return (&text[..pos], text[pos..].trim()),
+            None => ()
+        };
+    }
+
+    (text, "")
+}
+
+fn parse_number(text: &str) -> Result<f64, String> {
+    text.trim().parse::<f64>().map_err(|_| format!("{} is not a number", text.trim()))
+}
+
+// Compares one actual value, returns a description of the mismatch on failure
+fn compare(actual: &str, operator: &str, expected: &str) -> Result<(), String> {
+    let passed = match operator {
+        "==" => actual == expected,
+        "!=" => actual != expected,
+        "contains" => actual.contains(expected),
+        "matches" => Regex::new(expected).map_err(|err| format!("Invalid regex - {}", err))?.is_match(actual),
+        "<" => parse_number(actual)? < parse_number(expected)?,
+        "<=" => parse_number(actual)? <= parse_number(expected)?,
+        ">" => parse_number(actual)? > parse_number(expected)?,
+        ">=" => parse_number(actual)? >= parse_number(expected)?,
+        _ => return Err(format!("Unknown operator {}", operator))
+    };
+
+    if passed {
+        Ok(())
+    } else {
+        Err(format!("Got {}", actual))
+    }
+}
+
+// JSON values compare as JSON, so == 1 matches 1.0 and == "a" matches the string a
+fn compare_json(actual: &Value, operator: &str, expected: &str) -> Result<(), String> {
+    let expected_json = serde_json::from_str::<Value>(expected).ok();
+
+    match (operator, expected_json) {
+        ("==", Some(ref x)) if json_equals(actual, x) => Ok(()),
+        ("==", Some(_)) => Err(format!("Got {}", actual)),
+        ("!=", Some(ref x)) if !json_equals(actual, x) => Ok(()),
+        ("!=", Some(_)) => Err(format!("Got {}", actual)),
+        _ => compare(&jsonpath::value_to_text(actual), operator, expected)
+    }
+}
+
+fn json_equals(left: &Value, right: &Value) -> bool {
+    match (left.as_f64(), right.as_f64()) {
+        (Some(x), Some(y)) => x == y,
+        _ => left == right
+    }
+}
+
+fn check_status(rest: &str, resp: &::Response) -> Result<(), String> {
+    let list = parse_status_list(rest)?;
+
+    if list.is_empty() {
+        Err(String::from("Expected statuses are missing"))
+    } else if status_matches(&list, resp.status) {
+        Ok(())
+    } else {
+        Err(format!("Got {} {}", resp.status, resp.reason))
+    }
+}
+
+fn check_header(rest: &str, resp: &::Response) -> Result<(), String> {
+    let (name, condition) = split_word(rest);
+    let (operator, expected) = split_word(condition);
+
+    if name.is_empty() {
+        return Err(String::from("Header name is missing"));
+    }
+
+    let values: Vec<&str> = resp.headers.get_all(name).iter().filter_map(|x| x.to_str().ok()).collect();
+
+    if values.is_empty() {
+        return Err(format!("There is no {} header", name));
+    }
+
+    if operator.is_empty() {
+        return Ok(());
+    }
+
+    let errors: Vec<String> = values.iter().filter_map(|x| compare(x.trim(), operator, expected).err()).collect();
+
+    if errors.len() < values.len() { Ok(()) } else { Err(errors.join(", ")) }
+}
+
+fn check_jsonpath(rest: &str, resp: &::Response) -> Result<(), String> {
+    let (path, condition) = split_path(rest);
+    let (operator, expected) = split_word(condition);
+    let json: Value = serde_json::from_str(&resp.text).map_err(|_| String::from("Body is not JSON"))?;
+    let found = jsonpath::select(&json, path)?;
+
+    if found.is_empty() {
+        return Err(format!("Nothing matches {}", path));
+    }
+
+    if operator.is_empty() {
+        return Ok(());
+    }
+
+    let errors: Vec<String> = found.iter().filter_map(|x| compare_json(x, operator, expected).err()).collect();
+
+    if errors.len() < found.len() { Ok(()) } else { Err(errors.join(", ")) }
+}
+
+fn check_body(rest: &str, resp: &::Response) -> Result<(), String> {
+    let (operator, expected) = split_word(rest);
+
+    match operator {
+        "contains" if resp.text.contains(expected) => Ok(()),
+        "contains" => Err(format!("Body does not contain {}", expected)),
+        "matches" => compare(&resp.text, operator, expected).map_err(|_| format!("Body does not match {}", expected)),
+        _ => Err(String::from("Body checks use contains or matches"))
+    }
+}
+
+fn check_time(rest: &str, resp: &::Response) -> Result<(), String> {
+    let (operator, expected) = split_word(rest);
+    let total = format!("{:.0}", resp.timings.total_ms());
+
+    match operator {
+        "<" | "<=" | ">" | ">=" => compare(&total, operator, expected).map_err(|_| format!("Took {} ms", total)),
+        _ => Err(String::from("Time checks use <, <=, > or >="))
+    }
+}
+
+// One assertion per line, blank lines and lines starting with # are skipped:
+// status 2xx, status 200,204
+// header Content-Type contains json
+// jsonpath $.items[0].id == 42
+// body matches ^\{
+// time < 500
+pub fn check_response(text: &str, resp: &::Response) -> Vec<AssertionResult> {
+    text.lines().map(|x| x.trim()).filter(|x| !x.is_empty() && !x.starts_with('#')).map(|line| {
+        let (subject, rest) = split_word(line);
+
+        let outcome = match subject.to_lowercase().as_str() {
+            "status" => check_status(rest, resp),
+            "header" => check_header(rest, resp),
+            "jsonpath" => check_jsonpath(rest, resp),
+            "body" => check_body(rest, resp),
+            "time" => check_time(rest, resp),
+            _ => Err(format!("Unknown assertion {}", subject))
+        };
+
+        AssertionResult {
+            assertion: String::from(line),
+            passed: outcome.is_ok(),
+            message: outcome.err().unwrap_or(String::from("Passed")),
+        }
+    }).collect()
+}
@@ -0,0 +1,92 @@
+use super::assertions::{check_response, parse_status_list};
+use super::request::parse_headers;
+use super::timing::Timings;
+use super::Response;
+use mime::APPLICATION_JSON;
+use encoding_rs::UTF_8;
+use std::time::Duration;
+
+fn response(status: u16, headers: &str, body: &str) -> Response {
+    Response {
+        text: String::from(body),
+        body: body.as_bytes().to_vec(),
+        mime_type: APPLICATION_JSON,
+        encoding: UTF_8,
+        force_text: false,
+        extension: "json",
+        highlight: None,
+        headers: parse_headers(headers, &mut |_| {}),
+        status,
+        reason: String::from("OK"),
+        version: String::from("HTTP/1.1"),
+        url: String::from("http://localhost/"),
+        size: body.len(),
+        wire_size: body.len(),
+        content_encoding: String::new(),
+        decode_error: None,
+        raw_encoded: false,
+        timings: Timings { waiting: Duration::from_millis(120), ..Default::default() },
+        assertions: Vec::new(),
+    }
+}
+
+#[test]
+pub fn test_parse_status_list() {
+    assert_eq!(parse_status_list("200, 204").unwrap(), vec![(200, 200), (204, 204)]);
+    assert_eq!(parse_status_list("2xx,300-399").unwrap(), vec![(200, 299), (300, 399)]);
+    assert!(parse_status_list("ok").is_err());
+}
+
+#[test]
+pub fn test_check_response() {
+    let resp = response(201, "Content-Type:application/json\nX-Total:2",
+        r#"{"items": [{"id": 42, "name": "first"}], "count": 1.0, "odd key": true}"#);
+
+    let checks = "# comment\n\
+        status 2xx\n\
+        status 404\n\
+        header content-type contains json\n\
+        header X-Missing\n\
+        header x-total >= 2\n\
+        jsonpath $.items[0].id == 42\n\
+        jsonpath $.items[0].name == first\n\
+        jsonpath $.count == 1\n\
+        jsonpath $.items[*].id != 42\n\
+        jsonpath $.missing\n\
+        jsonpath $['odd key'] == true\n\
+        body matches \"id\":\\s*42\n\
+        body contains nothing here\n\
+        time < 500\n\
+        time > 500\n\
+        speed < 1";
+
+    let results: Vec<(bool, String)> = check_response(checks, &resp).into_iter().
+        map(|x| (x.passed, x.message)).
+        collect();
+
+    assert_eq!(results, vec![
+        (true, String::from("Passed")),
+        (false, String::from("Got 201 OK")),
+        (true, String::from("Passed")),
+        (false, String::from("There is no X-Missing header")),
+        (true, String::from("Passed")),
+        (true, String::from("Passed")),
+        (true, String::from("Passed")),
+        (true, String::from("Passed")),
+        (false, String::from("Got 42")),
+        (false, String::from("Nothing matches $.missing")),
+        (true, String::from("Passed")),
+        (true, String::from("Passed")),
+        (false, String::from("Body does not contain nothing here")),
+        (true, String::from("Passed")),
+        (false, String::from("Took 120 ms")),
+        (false, String::from("Unknown assertion speed")),
+    ]);
+
+    assert_eq!(check_response("jsonpath $.a", &response(200, "", "<a/>"))[0].message, "Body is not JSON");
+
+    let checked = resp.with_assertions("status 201\n\nheader x-total == 2");
+
+    assert_eq!(checked.assertions.len(), 2);
+    assert!(checked.assertions.iter().all(|x| x.passed));
+}
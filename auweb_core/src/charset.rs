@@ -0,0 +1,98 @@
+use encoding_rs::{Encoding, UTF_8};
+use mime::{Mime, CHARSET};
+
+const SNIFF_LIMIT: usize = 1024;
+
+pub const REINTERPRET_ENCODINGS: [&'static str; 14] = [
+    "UTF-8",
+    "UTF-16LE",
+    "UTF-16BE",
+    "windows-1251",
+    "windows-1252",
+    "windows-1250",
+    "KOI8-R",
+    "ISO-8859-2",
+    "ISO-8859-15",
+    "Shift_JIS",
+    "EUC-JP",
+    "GBK",
+    "Big5",
+    "EUC-KR",
+];
+
+fn find_from(haystack: &str, needle: &str, from: usize) -> Option<usize> {
+    haystack[from..].find(needle).map(|x| x + from)
+}
+
+fn read_attr_value(text: &str) -> &str {
+    let text = text.trim_start();
+
+    match text.chars().next() {
+        Some(quote) if quote == '"' || quote == '\'' => {
+            let rest = &text[1..];
+            &rest[..rest.find(quote).unwrap_or(rest.len())]
+        },
+        _ => {
+            let end = text.find(|x: char| x.is_whitespace() || x == ';' || x == '>' || x == '"' || x == '\'' || x == '/').
+                unwrap_or(text.len());
+            &text[..end]
+        }
+    }
+}
+
+fn sniff_head(body: &[u8]) -> String {
+    String::from_utf8_lossy(&body[..body.len().min(SNIFF_LIMIT)]).to_lowercase()
+}
+
+pub fn xml_prolog_encoding(body: &[u8]) -> Option<&'static Encoding> {
+    let head = sniff_head(body);
+    let head = head.trim_start();
+
+    if !head.starts_with("<?xml") {
+        return None;
+    }
+
+    let prolog = &head[..head.find("?>").unwrap_or(head.len())];
+
+    prolog.find("encoding").
+        map(|x| &prolog[x + "encoding".len()..]).
+        and_then(|x| x.trim_start().find('=').map(|y| &x.trim_start()[y + 1..])).
+        map(read_attr_value).
+        and_then(|x| Encoding::for_label(x.as_bytes()))
+}
+
+pub fn html_meta_encoding(body: &[u8]) -> Option<&'static Encoding> {
+    let head = sniff_head(body);
+    let mut from = 0;
+
+    while let Some(start) = find_from(&head, "<meta", from) {
+        let end = find_from(&head, ">", start).unwrap_or(head.len());
+        let tag = &head[start..end];
+
+        let found = tag.find("charset=").
+            map(|x| read_attr_value(&tag[x + "charset=".len()..])).
+            and_then(|x| Encoding::for_label(x.as_bytes()));
+
+        if found.is_some() {
+            return found;
+        }
+
+        from = end;
+    }
+
+    None
+}
+
+// Precedence follows what browsers do: BOM, then the transport level
+// charset, then whatever the document declares about itself
+pub fn detect_encoding(body: &[u8], mime: &Mime) -> &'static Encoding {
+    Encoding::for_bom(body).map(|x| x.0).
+        or_else(|| mime.get_param(CHARSET).and_then(|x| Encoding::for_label(x.as_str().as_bytes()))).
+        or_else(|| xml_prolog_encoding(body)).
+        or_else(|| html_meta_encoding(body)).
+        unwrap_or(UTF_8)
+}
+
+pub fn decode_with(body: &[u8], encoding: &'static Encoding) -> String {
+    encoding.decode_with_bom_removal(body).0.into_owned()
+}
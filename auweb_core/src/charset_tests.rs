@@ -0,0 +1,25 @@
+use super::charset;
+use mime::{Mime, TEXT_PLAIN};
+use encoding_rs::{UTF_8, UTF_16LE, WINDOWS_1251, SHIFT_JIS, WINDOWS_1252};
+
+#[test]
+pub fn test_detect_encoding() {
+    let xml: Mime = "application/xml".parse().unwrap();
+    let html: Mime = "text/html".parse().unwrap();
+    let cp1251: Mime = "text/plain; charset=windows-1251".parse().unwrap();
+
+    assert_eq!(charset::detect_encoding(b"plain", &TEXT_PLAIN), UTF_8);
+    assert_eq!(charset::detect_encoding(b"\xff\xfeh\x00i\x00", &cp1251), UTF_16LE);
+    assert_eq!(charset::detect_encoding(b"text", &cp1251), WINDOWS_1251);
+    assert_eq!(charset::detect_encoding(b"<?xml version=\"1.0\" encoding='Shift_JIS'?><a/>", &xml), SHIFT_JIS);
+    assert_eq!(charset::detect_encoding(b"<html><head><meta charset=\"windows-1251\"></head></html>", &html), WINDOWS_1251);
+    assert_eq!(charset::detect_encoding(
+        b"<html><head><meta http-equiv=\"Content-Type\" content=\"text/html; charset=iso-8859-1\"></head></html>", &html),
+        WINDOWS_1252);
+}
+
+#[test]
+pub fn test_decode_with() {
+    assert_eq!(charset::decode_with(b"\xcf\xf0\xe8\xe2\xe5\xf2", WINDOWS_1251), "Привет");
+    assert_eq!(charset::decode_with(b"\xef\xbb\xbfhello", UTF_8), "hello");
+}
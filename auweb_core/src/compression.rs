@@ -0,0 +1,46 @@
+use std::io::{Read, Cursor};
+use flate2::read::{GzDecoder, ZlibDecoder, DeflateDecoder};
+use brotli_decompressor::Decompressor;
+use zstd;
+
+pub const ACCEPT_ENCODING: &'static str = "gzip, deflate, br, zstd";
+
+fn read_all<R: Read>(mut reader: R) -> Result<Vec<u8>, String> {
+    let mut result = Vec::new();
+
+    reader.read_to_end(&mut result).
+        map(|_| result).
+        map_err(|err| err.to_string())
+}
+
+fn decode_one(body: Vec<u8>, encoding: &str) -> Result<Vec<u8>, String> {
+    match encoding {
+        "" | "identity" => Ok(body),
+        "gzip" | "x-gzip" => read_all(GzDecoder::new(Cursor::new(body))),
+        // Plenty of servers send raw deflate instead of the zlib wrapped one
+        "deflate" => read_all(ZlibDecoder::new(Cursor::new(&body))).
+            or_else(|_| read_all(DeflateDecoder::new(Cursor::new(&body)))),
+        "br" => read_all(Decompressor::new(Cursor::new(body), 4096)),
+        "zstd" => zstd::stream::decode_all(Cursor::new(body)).map_err(|err| err.to_string()),
+        _ => Err(String::from("unsupported content encoding ") + encoding)
+    }
+}
+
+// Content-Encoding: identity, or none at all, means the body is sent as is
+pub fn has_coding(content_encoding: &str) -> bool {
+    content_encoding.split(',').map(|x| x.trim()).any(|x| !x.is_empty() && !x.eq_ignore_ascii_case("identity"))
+}
+
+// Codings are listed in the order they were applied, so they are undone backwards.
+// HEAD, 204 and 304 responses keep the header of the full response but have no body to decode
+pub fn decode_content(body: Vec<u8>, content_encoding: &str) -> Result<Vec<u8>, String> {
+    if body.is_empty() {
+        return Ok(body);
+    }
+
+    let encodings: Vec<String> = content_encoding.split(',').
+        map(|x| x.trim().to_lowercase()).
+        collect();
+
+    encodings.iter().rev().fold(Ok(body), |acc, encoding| acc.and_then(|x| decode_one(x, encoding)))
+}
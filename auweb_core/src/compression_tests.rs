@@ -0,0 +1,22 @@
+use super::compression;
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use std::io::Write;
+
+#[test]
+pub fn test_decode_content() {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(b"{\"hello\": \"world\"}").unwrap();
+    let gzipped = encoder.finish().unwrap();
+
+    assert_eq!(compression::decode_content(gzipped.clone(), "gzip").unwrap(), b"{\"hello\": \"world\"}");
+    assert_eq!(compression::decode_content(gzipped.clone(), "identity, gzip").unwrap(), b"{\"hello\": \"world\"}");
+    assert_eq!(compression::decode_content(b"plain".to_vec(), "").unwrap(), b"plain");
+    assert!(compression::decode_content(b"plain".to_vec(), "compress").is_err());
+    assert!(compression::decode_content(b"not gzip".to_vec(), "gzip").is_err());
+    assert_eq!(compression::decode_content(Vec::new(), "gzip, br").unwrap(), b"");
+
+    assert!(compression::has_coding("identity, gzip"));
+    assert!(!compression::has_coding(" Identity "));
+    assert!(!compression::has_coding(""));
+}
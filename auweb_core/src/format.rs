@@ -0,0 +1,134 @@
+use reqwest::header::HeaderMap;
+use mime::{Mime, TEXT_PLAIN, APPLICATION, JSON, TEXT, XML, HTML, JAVASCRIPT, WWW_FORM_URLENCODED, IMAGE};
+use serde_json;
+use xml;
+use html;
+use compression;
+
+pub const CONTENT_TYPE_JSON: &'static str = "json";
+pub const CONTENT_TYPE_DEFAULT: &'static str = "";
+pub const CONTENT_TYPE_XML: &'static str = "xml";
+pub const CONTENT_TYPE_HTML: &'static str = "html";
+
+pub fn detect_mime_type(headers: &HeaderMap) -> Mime {
+    headers.get("content-type").
+        and_then(|x| x.to_str().ok()).
+        and_then(|x| x.parse::<Mime>().ok()).unwrap_or(TEXT_PLAIN)
+}
+
+pub fn conv_mime_type_to_extension(mime: &Mime) -> &'static str {
+    match (mime.type_(), mime.subtype()) {
+        (APPLICATION, JSON) | (TEXT, JSON) => {
+            CONTENT_TYPE_JSON
+        },
+        (APPLICATION, XML) | (TEXT, XML) => {
+            CONTENT_TYPE_XML
+        },
+        (TEXT, HTML) => {
+            CONTENT_TYPE_HTML
+        }
+        _ => CONTENT_TYPE_DEFAULT
+    }
+}
+
+pub fn is_image_mime(mime: &Mime) -> bool {
+    mime.type_() == IMAGE
+}
+
+pub fn is_text_mime(mime: &Mime) -> bool {
+    match (mime.type_(), mime.subtype(), mime.suffix()) {
+        (TEXT, _, _) => true,
+        (_, JSON, _) | (_, XML, _) | (_, JAVASCRIPT, _) | (_, WWW_FORM_URLENCODED, _) => true,
+        (_, _, Some(JSON)) | (_, _, Some(XML)) => true,
+        _ => false
+    }
+}
+
+const HEX_DUMP_LIMIT: usize = 64 * 1024;
+
+pub fn hex_dump(body: &[u8]) -> String {
+    let mut result = String::new();
+
+    for (i, chunk) in body[..body.len().min(HEX_DUMP_LIMIT)].chunks(16).enumerate() {
+        result += &format!("{:08x}  ", i * 16);
+
+        for j in 0..16 {
+            match chunk.get(j) {
+                Some(x) => result += &format!("{:02x} ", x),
+                None => result += "   "
+            };
+
+            if j == 7 {
+                result += " ";
+            }
+        }
+
+        result += " |";
+        result.extend(chunk.iter().map(|&x| if x >= 0x20 && x < 0x7f { x as char } else { '.' }));
+        result += "|\n";
+    }
+
+    if body.len() > HEX_DUMP_LIMIT {
+        result += &format!("... {} more bytes, use Save body as to get the full content\n", body.len() - HEX_DUMP_LIMIT);
+    }
+
+    result
+}
+
+pub fn beautify_response_text(extension: &'static str, text: &str) -> String {
+    match extension {
+        CONTENT_TYPE_JSON => {
+            let json_result: serde_json::Result<serde_json::Value> = serde_json::from_str(&text);
+            match json_result {
+                Ok(json) => serde_json::ser::to_string_pretty(&json).expect("Cannot stringify JSON"),
+                Err(_) => text.to_owned()
+            }
+        },
+        CONTENT_TYPE_XML => {
+            xml::beautify_xml(&text).unwrap_or(text.to_string())
+        },
+        CONTENT_TYPE_HTML => {
+            html::beautify_html(&text)
+        },
+        _ => text.to_owned()
+    }
+}
+
+pub fn format_headers(headers: &HeaderMap) -> String {
+    let mut headers_text = String::new();
+
+    for ref header in headers {
+        headers_text += header.0.as_str();
+        headers_text += ": ";
+        headers_text += header.1.to_str().unwrap_or("");
+        headers_text += "\n";
+    }
+
+    headers_text
+}
+
+pub fn response_body_text(resp: &::Response) -> String {
+    if resp.is_binary() { hex_dump(&resp.body) } else { beautify_response_text(resp.extension, &resp.text) }
+}
+
+pub fn format_size(size: usize) -> String {
+    if size < 1024 {
+        format!("{} B", size)
+    } else if size < 1024 * 1024 {
+        format!("{:.1} KB", size as f64 / 1024.0)
+    } else {
+        format!("{:.1} MB", size as f64 / (1024.0 * 1024.0))
+    }
+}
+
+pub fn format_transfer_size(resp: &::Response) -> String {
+    if !compression::has_coding(&resp.content_encoding) {
+        return format_size(resp.size);
+    }
+
+    match resp.decode_error {
+        Some(ref err) => format!("{} {} (not decoded: {})", format_size(resp.wire_size), resp.content_encoding, err),
+        None if resp.raw_encoded => format!("{} {} (not decoded)", format_size(resp.wire_size), resp.content_encoding),
+        None => format!("{} {} on wire, {} decoded", format_size(resp.wire_size), resp.content_encoding, format_size(resp.size))
+    }
+}
@@ -0,0 +1,22 @@
+use super::format;
+
+#[test]
+pub fn test_format_size() {
+    assert_eq!(format::format_size(0), "0 B");
+    assert_eq!(format::format_size(1023), "1023 B");
+    assert_eq!(format::format_size(1536), "1.5 KB");
+    assert_eq!(format::format_size(3 * 1024 * 1024), "3.0 MB");
+}
+
+#[test]
+pub fn test_hex_dump() {
+    assert_eq!(format::hex_dump(&[]), "");
+
+    let dump = format::hex_dump(b"Hello, World!\x00\x01\x02\xff");
+    let lines: Vec<&str> = dump.lines().collect();
+
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0], "00000000  48 65 6c 6c 6f 2c 20 57  6f 72 6c 64 21 00 01 02  |Hello, World!...|");
+    assert!(lines[1].starts_with("00000010  ff "));
+    assert!(lines[1].ends_with("|.|"));
+}
@@ -0,0 +1,192 @@
+use serde_json::{self, Value};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Step {
+    Field(String),
+    Index(i64),
+    Wildcard,
+    Descendants,
+}
+
+fn read_name(chars: &[char], start: usize) -> (String, usize) {
+    let mut end = start;
+
+    while end < chars.len() && chars[end] != '.' && chars[end] != '[' {
+        end += 1;
+    }
+
+    (chars[start..end].iter().collect(), end)
+}
+
+fn read_bracket(chars: &[char], start: usize) -> Result<(Step, usize), String> {
+    let end = match chars[start..].iter().position(|&x| x == ']') {
+        Some(x) => start + x,
+        None => return Err(String::from("Missing ] in JSONPath"))
+    };
+
+    let inner: String = chars[start..end].iter().collect();
+    let inner = inner.trim();
+    let quoted = inner.len() >= 2 &&
+        ((inner.starts_with('\'') && inner.ends_with('\'')) || (inner.starts_with('"') && inner.ends_with('"')));
+
+    let step = if inner == "*" {
+        Step::Wildcard
+    } else if quoted {
+        Step::Field(String::from(&inner[1..inner.len() - 1]))
+    } else {
+        inner.parse::<i64>().map(Step::Index).map_err(|_| format!("Invalid JSONPath index [{}]", inner))?
+    };
+
+    Ok((step, end + 1))
+}
+
+// Supports $, .name, ['name'], [index] with negative indexes from the end, [*], .* and ..name
+fn parse_path(path: &str) -> Result<Vec<Step>, String> {
+    let path = path.trim();
+
+    if !path.starts_with('$') {
+        return Err(format!("JSONPath {} must start with $", path));
+    }
+
+    let chars: Vec<char> = path.chars().collect();
+    let mut steps = Vec::new();
+    let mut i = 1;
+
+    while i < chars.len() {
+        if chars[i] == '[' {
+            let (step, next) = read_bracket(&chars, i + 1)?;
+            steps.push(step);
+            i = next;
+        } else if chars[i] == '.' {
+            i += 1;
+
+            if i < chars.len() && chars[i] == '.' {
+                steps.push(Step::Descendants);
+                i += 1;
+            }
+
+            if i < chars.len() && chars[i] == '[' {
+                continue;
+            }
+
+            let (name, next) = read_name(&chars, i);
+
+            if name.is_empty() {
+                return Err(format!("Empty name in JSONPath {}", path));
+            }
+
+            steps.push(if name == "*" { Step::Wildcard } else { Step::Field(name) });
+            i = next;
+        } else {
+            return Err(format!("Unexpected {} in JSONPath {}", chars[i], path));
+        }
+    }
+
+    Ok(steps)
+}
+
+fn add_descendants<'a>(value: &'a Value, result: &mut Vec<&'a Value>) {
+    result.push(value);
+
+    match *value {
+        Value::Array(ref items) => for item in items { add_descendants(item, result); },
+        Value::Object(ref map) => for item in map.values() { add_descendants(item, result); },
+        _ => ()
+    };
+}
+
+fn apply_step<'a>(value: &'a Value, step: &Step, result: &mut Vec<&'a Value>) {
+    match (step, value) {
+        (&Step::Field(ref name), &Value::Object(ref map)) => result.extend(map.get(name)),
+        (&Step::Index(index), &Value::Array(ref items)) => {
+            let index = if index < 0 { items.len() as i64 + index } else { index };
+
+            if index >= 0 {
+                result.extend(items.get(index as usize));
+            }
+        },
+        (&Step::Wildcard, &Value::Array(ref items)) => result.extend(items.iter()),
+        (&Step::Wildcard, &Value::Object(ref map)) => result.extend(map.values()),
+        (&Step::Descendants, _) => add_descendants(value, result),
+        _ => ()
+    };
+}
+
+pub fn select<'a>(root: &'a Value, path: &str) -> Result<Vec<&'a Value>, String> {
+    let mut current = vec![root];
+
+    for step in parse_path(path)? {
+        let mut next = Vec::new();
+
+        for value in current {
+            apply_step(value, &step, &mut next);
+        }
+
+        current = next;
+    }
+
+    Ok(current)
+}
+
+// jq style paths like .items[].id or items[0] are turned into JSONPath
+pub fn normalize_path(expression: &str) -> String {
+    let expression = expression.trim();
+
+    let path = if expression.starts_with('$') {
+        String::from(expression)
+    } else if expression == "." {
+        String::from("$")
+    } else if expression.starts_with('.') || expression.starts_with('[') {
+        format!("${}", expression)
+    } else {
+        format!("$.{}", expression)
+    };
+
+    // Only unquoted [] means every element, inside quotes it is part of a key
+    let mut result = String::new();
+    let mut quote = None;
+    let mut chars = path.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => (),
+            None if c == '\'' || c == '"' => quote = Some(c),
+            None if c == '[' && chars.peek() == Some(&']') => {
+                chars.next();
+                result += "[*]";
+                continue;
+            },
+            None => ()
+        };
+
+        result.push(c);
+    }
+
+    result
+}
+
+// A single match is shown as is and several ones as an array, the number of matches is returned too
+pub fn filter_text(text: &str, expression: &str) -> Result<(String, usize), String> {
+    let json: Value = serde_json::from_str(text).map_err(|_| String::from("Body is not JSON"))?;
+    let found = select(&json, &normalize_path(expression))?;
+    let count = found.len();
+
+    let fragment = if count == 1 {
+        found[0].clone()
+    } else {
+        Value::Array(found.into_iter().cloned().collect())
+    };
+
+    serde_json::to_string_pretty(&fragment).
+        map(|x| (x, count)).
+        map_err(|err| format!("Cannot stringify JSON - {}", err))
+}
+
+// Strings are shown without quotes, everything else as compact JSON
+pub fn value_to_text(value: &Value) -> String {
+    match *value {
+        Value::String(ref x) => x.clone(),
+        _ => value.to_string()
+    }
+}
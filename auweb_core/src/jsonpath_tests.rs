@@ -0,0 +1,46 @@
+use super::jsonpath;
+use serde_json::{self, Value};
+
+#[test]
+pub fn test_select() {
+    let json: Value = serde_json::from_str(
+        r#"{"items": [{"id": 1, "tags": ["a"]}, {"id": 2, "name": "two", "tags": []}], "total": 2, "odd key": true}"#
+    ).unwrap();
+
+    let texts = |path: &str| jsonpath::select(&json, path).unwrap().iter().
+        map(|x| jsonpath::value_to_text(x)).
+        collect::<Vec<_>>();
+
+    assert_eq!(texts("$.total"), vec!["2"]);
+    assert_eq!(texts("$.items[1].name"), vec!["two"]);
+    assert_eq!(texts("$.items[-1].id"), vec!["2"]);
+    assert_eq!(texts("$['odd key']"), vec!["true"]);
+    assert_eq!(texts("$.items[*].id"), vec!["1", "2"]);
+    assert_eq!(texts("$.items.*.tags"), vec!["[\"a\"]", "[]"]);
+    assert_eq!(texts("$..id"), vec!["1", "2"]);
+    assert_eq!(texts("$..tags[0]"), vec!["a"]);
+    assert!(texts("$.missing").is_empty());
+    assert_eq!(texts("$").len(), 1);
+
+    assert!(jsonpath::select(&json, "items").is_err());
+    assert!(jsonpath::select(&json, "$.items[x]").is_err());
+    assert!(jsonpath::select(&json, "$.items[0").is_err());
+}
+
+#[test]
+pub fn test_filter_text() {
+    let text = r#"{"items": [{"id": 1, "name": "one"}, {"id": 2}], "total": 2}"#;
+
+    assert_eq!(jsonpath::normalize_path(".items[].id"), "$.items[*].id");
+    assert_eq!(jsonpath::normalize_path("items[0]"), "$.items[0]");
+    assert_eq!(jsonpath::normalize_path(" . "), "$");
+    assert_eq!(jsonpath::normalize_path(".[0]"), "$.[0]");
+    assert_eq!(jsonpath::normalize_path("$['a[]'][]"), "$['a[]'][*]");
+
+    assert_eq!(jsonpath::filter_text(text, "$.items[0]").unwrap(), (String::from("{\n  \"id\": 1,\n  \"name\": \"one\"\n}"), 1));
+    assert_eq!(jsonpath::filter_text(text, ".items[].id").unwrap(), (String::from("[\n  1,\n  2\n]"), 2));
+    assert_eq!(jsonpath::filter_text(text, "total").unwrap(), (String::from("2"), 1));
+    assert_eq!(jsonpath::filter_text(text, ".missing").unwrap(), (String::from("[]"), 0));
+    assert_eq!(jsonpath::filter_text("<html/>", "$").unwrap_err(), "Body is not JSON");
+    assert!(jsonpath::filter_text(text, ".items[x]").is_err());
+}
@@ -0,0 +1,204 @@
+// Request building, sending and response formatting without GTK, shared by
+// the GUI, the auweb run command and anything else that needs to send requests
+
+extern crate serde_json;
+extern crate reqwest;
+extern crate native_tls;
+extern crate mime;
+extern crate quick_xml;
+extern crate html5ever;
+extern crate encoding_rs;
+extern crate flate2;
+extern crate brotli_decompressor;
+extern crate zstd;
+extern crate regex;
+extern crate rhai;
+extern crate sha2;
+extern crate rand;
+extern crate base64;
+
+use mime::Mime;
+use std::convert::TryFrom;
+
+pub mod xml;
+pub mod html;
+pub mod timing;
+pub mod charset;
+pub mod compression;
+pub mod format;
+pub mod request;
+pub mod jsonpath;
+pub mod assertions;
+pub mod scripting;
+
+pub struct Response
+{
+    pub text: String,
+    pub body: Vec<u8>,
+    pub mime_type: Mime,
+    pub encoding: &'static encoding_rs::Encoding,
+    pub force_text: bool,
+    pub extension: &'static str,
+    pub highlight: Option<String>,
+    pub headers: reqwest::header::HeaderMap,
+    pub status: u16,
+    pub reason: String,
+    pub version: String,
+    pub url: String,
+    pub size: usize,
+    pub wire_size: usize,
+    pub content_encoding: String,
+    pub decode_error: Option<String>,
+    pub raw_encoded: bool,
+    pub timings: timing::Timings,
+    pub assertions: Vec<assertions::AssertionResult>,
+}
+
+pub struct Request
+{
+    pub method: RequestMethod,
+    pub custom_method: String,
+    pub url: String,
+    pub headers: reqwest::header::HeaderMap,
+    pub body_type: BodyType,
+    pub body: String,
+    pub body_file: String,
+    pub decompress: bool,
+}
+
+pub enum RequestMethod {
+    Get = 1,
+    Post = 3,
+    Put = 4,
+    Patch = 5,
+    Delete = 6,
+    Head = 7,
+    Options = 8,
+    Trace = 9,
+    Custom = 10,
+}
+
+pub enum BodyType {
+    Empty = 1,
+    Raw = 2,
+    Json = 3,
+    Xml = 4,
+    Form = 5,
+    Binary = 6,
+    Multipart = 7,
+}
+
+impl<'a> TryFrom<&'a mut reqwest::Response> for Response {
+    type Error = String;
+
+    fn try_from(x: &'a mut reqwest::Response) -> Result<Self, String> {
+        Response::read_from(x, true)
+    }
+}
+
+impl Response {
+    // A body that cannot be read completely fails the whole response instead of showing a truncated one
+    pub fn read_from(x: &mut reqwest::Response, decompress: bool) -> Result<Self, String> {
+        let mut wire_body: Vec<u8> = Vec::new();
+        x.copy_to(&mut wire_body).map_err(|err| format!("Cannot read the response body - {}", err))?;
+
+        Ok(Response::from_wire(x, wire_body, decompress))
+    }
+
+    // For callers that read the body themselves
+    pub fn from_wire(x: &reqwest::Response, wire_body: Vec<u8>, decompress: bool) -> Self {
+        let wire_size = wire_body.len();
+
+        let content_encoding = x.headers().get_all(reqwest::header::CONTENT_ENCODING).iter().
+            filter_map(|y| y.to_str().ok()).
+            collect::<Vec<_>>().
+            join(", ");
+
+        let has_coding = !wire_body.is_empty() && compression::has_coding(&content_encoding);
+
+        let (body, decode_error, raw_encoded) = if decompress {
+            match compression::decode_content(wire_body.clone(), &content_encoding) {
+                Ok(decoded) => (decoded, None, false),
+                Err(err) => (wire_body, Some(err), has_coding)
+            }
+        } else {
+            (wire_body, None, has_coding)
+        };
+
+        let mime: Mime = format::detect_mime_type(x.headers());
+        let extension: &'static str = format::conv_mime_type_to_extension(&mime);
+        let encoding = charset::detect_encoding(&body, &mime);
+        let response_text: String = charset::decode_with(&body, encoding);
+        let size = body.len();
+
+        Response {
+            text: response_text,
+            body,
+            mime_type: mime,
+            encoding,
+            force_text: false,
+            extension,
+            highlight: None,
+            headers: x.headers().clone(),
+            status: x.status().as_u16(),
+            reason: String::from(x.status().canonical_reason().unwrap_or("")),
+            version: format!("{:?}", x.version()),
+            url: x.url().to_string(),
+            size,
+            wire_size,
+            content_encoding,
+            decode_error,
+            raw_encoded,
+            timings: timing::Timings::default(),
+            assertions: Vec::new(),
+        }
+    }
+
+    pub fn is_binary(&self) -> bool {
+        !self.force_text && (self.raw_encoded || !format::is_text_mime(&self.mime_type))
+    }
+
+    pub fn with_highlight_override(self, highlight: Option<String>) -> Self {
+        Response {
+            highlight,
+            ..self
+        }
+    }
+
+    pub fn with_timings(self, timings: timing::Timings) -> Self {
+        Response {
+            timings,
+            ..self
+        }
+    }
+
+    pub fn with_assertions(self, text: &str) -> Self {
+        let assertions = assertions::check_response(text, &self);
+
+        Response {
+            assertions,
+            ..self
+        }
+    }
+}
+
+#[cfg(test)]
+mod request_tests;
+
+#[cfg(test)]
+mod format_tests;
+
+#[cfg(test)]
+mod charset_tests;
+
+#[cfg(test)]
+mod compression_tests;
+
+#[cfg(test)]
+mod jsonpath_tests;
+
+#[cfg(test)]
+mod assertions_tests;
+
+#[cfg(test)]
+mod scripting_tests;
@@ -0,0 +1,373 @@
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE, ACCEPT_ENCODING};
+use std;
+use reqwest;
+use native_tls;
+use std::error::{Error};
+use std::io::{ErrorKind, Read};
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use timing;
+use compression;
+
+// X-AU-* headers only steer this client and are never sent
+pub const CLIENT_HEADER_PREFIX: &'static str = "x-au-";
+
+pub fn is_client_header(name: &HeaderName) -> bool {
+    name.as_str().starts_with(CLIENT_HEADER_PREFIX)
+}
+
+// Seconds until a request gives up, 0 waits as long as it takes
+pub const TIMEOUT_HEADER: &'static str = "X-AU-Timeout";
+pub const DEFAULT_TIMEOUT_SECS: u64 = 30;
+
+const READ_CHUNK_SIZE: usize = 16 * 1024;
+
+pub fn to_pair_if_both<T, U>(t: Option<T>, u: Option<U>) -> Option<(T, U)> {
+    match (t, u) {
+        (Some(x), Some(y)) => Some((x, y)),
+        _ => None
+    }
+}
+
+pub fn parse_headers<F: FnMut(&str)>(text: &str, error_log: &mut F) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+
+    for line in text.lines().filter(|x| x.trim() != "") {
+        let pair = line.find(":").
+            map(|x| line.split_at(x)).
+            map(|p| (p.0, p.1.get(1..).unwrap_or("")));
+
+        let parsed_pair = pair.and_then(|x| {
+            let name = HeaderName::from_bytes(x.0.as_bytes()).ok();
+            let val = x.1.parse::<HeaderValue>().ok();
+
+            to_pair_if_both(name, val)
+        });
+
+        match parsed_pair {
+            Some(p) => { headers.append(p.0, p.1); },
+            None => error_log(&(String::from("Failed to parse header - ") + line))
+        };
+    }
+
+    headers
+}
+
+pub fn create_post_req_data(text: &str) -> Vec<(&str, &str)> {
+    let mut form = Vec::new();
+
+    for line in text.lines() {
+        let tokens = line.splitn(2,'=');
+        let mut key_value: (&str, &str) = ("", "");
+
+        for (i, item) in tokens.enumerate() {
+            match i {
+                0 => key_value.0 = item,
+                1 => key_value.1 = item,
+                _ => panic!("should never happen")
+            };
+        }
+
+        form.push(key_value);
+    }
+
+    form
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MultipartField {
+    pub name: String,
+    pub value: String,
+    pub file_path: Option<String>,
+    pub file_name: Option<String>,
+    pub mime_type: Option<String>,
+}
+
+fn split_multipart_params(text: &str) -> (&str, Vec<(&str, &str)>) {
+    let mut tokens = text.split(';');
+    let head = tokens.next().unwrap_or("");
+    let params = tokens.
+        filter_map(|x| x.find('=').map(|i| (x[..i].trim(), x[i + 1..].trim()))).
+        collect();
+
+    (head, params)
+}
+
+// Uses curl -F syntax, one field per line:
+// name=value, name=value;type=application/json
+// name=@/path/to/file;filename=upload.png;type=image/png
+pub fn create_multipart_fields(text: &str) -> Result<Vec<MultipartField>, String> {
+    let mut fields = Vec::new();
+
+    for line in text.lines().filter(|x| x.trim() != "") {
+        let pair = line.find('=').map(|x| (&line[..x], &line[x + 1..]));
+
+        let (name, value) = match pair {
+            Some(p) => p,
+            None => return Err(String::from("Failed to parse multipart field - ") + line)
+        };
+
+        let field = if value.starts_with('@') {
+            let (path, params) = split_multipart_params(&value[1..]);
+
+            MultipartField {
+                name: String::from(name.trim()),
+                value: String::new(),
+                file_path: Some(String::from(path)),
+                file_name: params.iter().find(|x| x.0 == "filename").map(|x| String::from(x.1)),
+                mime_type: params.iter().find(|x| x.0 == "type").map(|x| String::from(x.1)),
+            }
+        } else {
+            let (text_value, mime_type) = match value.rfind(";type=") {
+                Some(i) => (&value[..i], Some(String::from(&value[i + 6..]))),
+                None => (value, None)
+            };
+
+            MultipartField {
+                name: String::from(name.trim()),
+                value: String::from(text_value),
+                file_path: None,
+                file_name: None,
+                mime_type,
+            }
+        };
+
+        fields.push(field);
+    }
+
+    Ok(fields)
+}
+
+fn create_multipart_form(text: &str) -> Result<reqwest::multipart::Form, String> {
+    let mut form = reqwest::multipart::Form::new();
+
+    for field in create_multipart_fields(text)? {
+        let mut part = match field.file_path {
+            Some(ref path) => reqwest::multipart::Part::file(path).
+                map_err(|err| String::from("Cannot read multipart file ") + path.as_str() + " - " + err.description())?,
+            None => reqwest::multipart::Part::text(field.value.clone())
+        };
+
+        if let Some(file_name) = field.file_name {
+            part = part.file_name(file_name);
+        }
+
+        if let Some(mime_type) = field.mime_type {
+            part = part.mime_str(&mime_type).
+                map_err(|_| String::from("Invalid multipart content type - ") + mime_type.as_str())?;
+        }
+
+        form = form.part(field.name, part);
+    }
+
+    Ok(form)
+}
+
+pub fn conv_to_http_method(request_method: &::RequestMethod, custom_method: &str) -> Result<reqwest::Method, String> {
+    match *request_method {
+        ::RequestMethod::Get => Ok(reqwest::Method::GET),
+        ::RequestMethod::Post => Ok(reqwest::Method::POST),
+        ::RequestMethod::Put => Ok(reqwest::Method::PUT),
+        ::RequestMethod::Patch => Ok(reqwest::Method::PATCH),
+        ::RequestMethod::Delete => Ok(reqwest::Method::DELETE),
+        ::RequestMethod::Head => Ok(reqwest::Method::HEAD),
+        ::RequestMethod::Options => Ok(reqwest::Method::OPTIONS),
+        ::RequestMethod::Trace => Ok(reqwest::Method::TRACE),
+        ::RequestMethod::Custom => {
+            let name = custom_method.trim();
+
+            if name.is_empty() {
+                Err(String::from("Custom method name is empty"))
+            } else {
+                reqwest::Method::from_bytes(name.as_bytes()).
+                    map_err(|_| String::from("Invalid custom method name - ") + name)
+            }
+        }
+    }
+}
+
+pub fn body_type_content_type(body_type: &::BodyType) -> Option<&'static str> {
+    match *body_type {
+        ::BodyType::Empty => None,
+        ::BodyType::Raw => Some("text/plain; charset=utf-8"),
+        ::BodyType::Json => Some("application/json"),
+        ::BodyType::Xml => Some("application/xml"),
+        ::BodyType::Form => Some("application/x-www-form-urlencoded"),
+        ::BodyType::Binary => Some("application/octet-stream"),
+        ::BodyType::Multipart => None,
+    }
+}
+
+// Body type for an already serialized body, so form data stays as raw text
+pub fn raw_body_type(content_type: &str) -> ::BodyType {
+    let content_type = content_type.to_lowercase();
+
+    if content_type.contains("json") {
+        ::BodyType::Json
+    } else if content_type.contains("xml") {
+        ::BodyType::Xml
+    } else {
+        ::BodyType::Raw
+    }
+}
+
+fn with_request_body(builder: reqwest::RequestBuilder, request: &::Request) -> Result<reqwest::RequestBuilder, String> {
+    match request.body_type {
+        ::BodyType::Empty => Ok(builder),
+        ::BodyType::Form => Ok(builder.form(create_post_req_data(&request.body).as_slice())),
+        ::BodyType::Binary => std::fs::read(&request.body_file).
+            map(|x| builder.body(x)).
+            map_err(|err| String::from("Cannot read body file - ") + err.description()),
+        ::BodyType::Multipart => create_multipart_form(&request.body).map(|x| builder.multipart(x)),
+        _ => Ok(builder.body(request.body.clone()))
+    }
+}
+
+pub fn request_timeout(headers: &HeaderMap) -> Result<Option<Duration>, String> {
+    let secs = match headers.get(TIMEOUT_HEADER) {
+        Some(value) => value.to_str().ok().
+            and_then(|x| x.trim().parse::<u64>().ok()).
+            ok_or(format!("{} must be a whole number of seconds", TIMEOUT_HEADER))?,
+        None => DEFAULT_TIMEOUT_SECS
+    };
+
+    Ok(if secs == 0 { None } else { Some(Duration::from_secs(secs)) })
+}
+
+// reqwest keeps its connections to itself, so DNS, connect and TLS are timed on a
+// connection of our own to the same address right before the request is sent.
+// A phase that fails leaves it and the ones after it at zero.
+fn measure_setup(url: &str, timeout: Option<Duration>) -> timing::Timings {
+    let mut timings = timing::Timings::default();
+    let url = match reqwest::Url::parse(url) {
+        Ok(x) => x,
+        Err(_) => return timings
+    };
+
+    let host = url.host_str().unwrap_or("").trim_start_matches('[').trim_end_matches(']');
+    let port = url.port_or_known_default().unwrap_or(80);
+
+    let started = Instant::now();
+    let addresses: Vec<SocketAddr> = match (host, port).to_socket_addrs() {
+        Ok(x) => x.collect(),
+        Err(_) => return timings
+    };
+    timings.dns = started.elapsed();
+
+    // Like the request itself, falls back to the next address when one can't be reached
+    let started = Instant::now();
+    let connected = addresses.iter().filter_map(|address| match timeout {
+        Some(x) => TcpStream::connect_timeout(address, x).ok(),
+        None => TcpStream::connect(address).ok()
+    }).next();
+    let stream = match connected {
+        Some(x) => x,
+        None => return timings
+    };
+    timings.connect = started.elapsed();
+
+    if url.scheme() == "https" {
+        stream.set_read_timeout(timeout).unwrap_or(());
+        stream.set_write_timeout(timeout).unwrap_or(());
+
+        let started = Instant::now();
+        let handshake = native_tls::TlsConnector::new().ok().and_then(|x| x.connect(host, stream).ok());
+
+        if handshake.is_some() {
+            timings.tls = started.elapsed();
+        }
+    }
+
+    timings
+}
+
+// Reads in chunks and stops between them once the request is cancelled, a read
+// that gets no data at all still waits for the timeout
+fn read_body(x: &mut reqwest::Response, cancelled: &AtomicBool) -> Option<Result<Vec<u8>, String>> {
+    let mut body = Vec::new();
+    let mut chunk = [0; READ_CHUNK_SIZE];
+
+    loop {
+        if cancelled.load(Ordering::SeqCst) {
+            return None;
+        }
+
+        match x.read(&mut chunk) {
+            Ok(0) => return Some(Ok(body)),
+            Ok(read) => body.extend_from_slice(&chunk[..read]),
+            Err(ref err) if err.kind() == ErrorKind::Interrupted => (),
+            Err(err) => return Some(Err(format!("Cannot read the response body - {}", err)))
+        };
+    }
+}
+
+// Blocks until the response is read, so GUI callers run it on a worker thread.
+// Returns None when the request was cancelled
+pub fn perform_request(request: &::Request, cancelled: &AtomicBool) -> Option<Result<::Response, String>> {
+    let timeout = match request_timeout(&request.headers) {
+        Ok(x) => x,
+        Err(err) => return Some(Err(err))
+    };
+
+    // Automatic gzip handling is off so that the wire size and encoding stay observable
+    let client_result = reqwest::Client::builder().gzip(false).timeout(timeout).build();
+
+    let req_error_to_string = |err: reqwest::Error| String::from("Request failed: ") + err.description();
+
+    let mut headers: HeaderMap = request.headers.iter().
+        filter(|x| !is_client_header(x.0)).
+        map(|x| (x.0.clone(), x.1.clone())).
+        collect();
+
+    if !headers.contains_key(ACCEPT_ENCODING) {
+        headers.insert(ACCEPT_ENCODING, HeaderValue::from_static(compression::ACCEPT_ENCODING));
+    }
+
+    if !headers.contains_key(CONTENT_TYPE) {
+        body_type_content_type(&request.body_type).
+            map(|x| headers.insert(CONTENT_TYPE, HeaderValue::from_static(x)));
+    }
+
+    let builder_result = client_result.map_err(req_error_to_string).and_then(|client| {
+        conv_to_http_method(&request.method, &request.custom_method).
+            and_then(|method| with_request_body(client.request(method, request.url.as_str()), request))
+    });
+
+    let setup = if builder_result.is_ok() { measure_setup(&request.url, timeout) } else { timing::Timings::default() };
+    let started = Instant::now();
+    let request_result = builder_result.
+        and_then(|builder| builder.headers(headers).send().map_err(req_error_to_string));
+    let time_to_headers = started.elapsed();
+
+    // Sending can't be interrupted, so a request cancelled while waiting for the
+    // headers still runs until they arrive or the timeout hits, then it is dropped
+    if cancelled.load(Ordering::SeqCst) {
+        return None;
+    }
+
+    let mut x = match request_result {
+        Ok(x) => x,
+        Err(err) => return Some(Err(err))
+    };
+
+    let started = Instant::now();
+    let wire_body = match read_body(&mut x, cancelled)? {
+        Ok(body) => body,
+        Err(err) => return Some(Err(err))
+    };
+
+    // The request set up its own connection, which the time to headers includes
+    let timings = timing::Timings {
+        waiting: time_to_headers.checked_sub(setup.dns + setup.connect + setup.tls).unwrap_or(Duration::from_secs(0)),
+        transfer: started.elapsed(),
+        ..setup
+    };
+
+    Some(Ok(::Response::from_wire(&x, wire_body, request.decompress).with_timings(timings)))
+}
+
+// Sends a request without a way to cancel it, for tools and tests
+pub fn send(request: &::Request) -> Result<::Response, String> {
+    perform_request(request, &AtomicBool::new(false)).unwrap_or(Err(String::from("Request was cancelled")))
+}
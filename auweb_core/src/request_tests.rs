@@ -0,0 +1,50 @@
+use super::request;
+
+#[test]
+pub fn it_works() {
+    assert_eq!(4, 2 + 2);
+}
+
+#[test]
+pub fn test_parse_headers() {
+    let empty = request::parse_headers("", &mut |_|{});
+
+    assert_eq!(empty.len(), 0);
+
+    let mut test_vec1: Vec<String> = Vec::new();
+
+    let test1 = request::parse_headers(include_str!("test_data/headers1"), &mut |msg|{
+        test_vec1.push(String::from(msg));
+    });
+
+    assert_eq!(test1.len(), 3);
+    assert!(test1.contains_key("content-type"));
+    assert!(test1.contains_key("cookie"));
+    assert!(!test1.contains_key("connection"));
+    assert_eq!(test1["cookie"], "");
+    assert_eq!(test1["soapaction"], " http://example.com/action");
+    assert_eq!(test1["content-type"], " application/json");
+    assert_eq!(test_vec1.len(), 0);
+}
+
+#[test]
+pub fn test_create_multipart_fields() {
+    let empty = request::create_multipart_fields("\n\n").unwrap();
+
+    assert_eq!(empty.len(), 0);
+
+    let fields = request::create_multipart_fields(include_str!("test_data/multipart1")).unwrap();
+
+    assert_eq!(fields.len(), 3);
+    assert_eq!(fields[0].name, "title");
+    assert_eq!(fields[0].value, "a=b; c");
+    assert_eq!(fields[0].file_path, None);
+    assert_eq!(fields[1].value, "{\"public\":true}");
+    assert_eq!(fields[1].mime_type, Some(String::from("application/json")));
+    assert_eq!(fields[2].name, "avatar");
+    assert_eq!(fields[2].file_path, Some(String::from("/tmp/avatar.png")));
+    assert_eq!(fields[2].file_name, Some(String::from("me.png")));
+    assert_eq!(fields[2].mime_type, Some(String::from("image/png")));
+
+    assert!(request::create_multipart_fields("no separator").is_err());
+}
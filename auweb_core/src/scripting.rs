@@ -0,0 +1,256 @@
+use rhai::{Engine, Scope, Dynamic, Map, Array};
+use rhai::module_resolvers::DummyModuleResolver;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use serde_json::{self, Value};
+use sha2::{Sha256, Digest};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use base64;
+use rand;
+use request;
+
+// Scripts cannot touch files or the network, these limits also stop runaway loops
+const MAX_OPERATIONS: u64 = 1_000_000;
+const MAX_CALL_LEVELS: usize = 32;
+const MAX_STRING_SIZE: usize = 16 * 1024 * 1024;
+const MAX_COLLECTION_SIZE: usize = 100_000;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScriptResult {
+    pub log: Vec<String>,
+    // Only variables the script added or changed
+    pub variables: Vec<(String, String)>,
+    pub error: Option<String>,
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|x| format!("{:02x}", x)).collect()
+}
+
+fn hmac_sha256(key: &[u8], message: &[u8]) -> Vec<u8> {
+    let mut block = [0u8; 64];
+
+    if key.len() > block.len() {
+        let digest = Sha256::digest(key);
+        block[..digest.len()].copy_from_slice(&digest);
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+
+    let inner_pad: Vec<u8> = block.iter().map(|x| x ^ 0x36).collect();
+    let outer_pad: Vec<u8> = block.iter().map(|x| x ^ 0x5c).collect();
+
+    let mut inner = Sha256::new();
+    inner.update(&inner_pad);
+    inner.update(message);
+
+    let mut outer = Sha256::new();
+    outer.update(&outer_pad);
+    outer.update(inner.finalize());
+    outer.finalize().to_vec()
+}
+
+fn url_encode(text: &str) -> String {
+    text.bytes().map(|x| match x {
+        b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (x as char).to_string(),
+        _ => format!("%{:02X}", x)
+    }).collect()
+}
+
+fn unix_time_ms() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).
+        map(|x| x.as_secs() as i64 * 1000 + x.subsec_millis() as i64).
+        unwrap_or(0)
+}
+
+fn uuid_v4() -> String {
+    let mut bytes: [u8; 16] = rand::random();
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex = to_hex(&bytes);
+
+    format!("{}-{}-{}-{}-{}", &hex[..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..])
+}
+
+fn create_engine(log: &Arc<Mutex<Vec<String>>>) -> Engine {
+    let mut engine = Engine::new();
+
+    // The default resolver would let `import` read script files from disk
+    engine.set_module_resolver(DummyModuleResolver::new());
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.set_max_call_levels(MAX_CALL_LEVELS);
+    engine.set_max_string_size(MAX_STRING_SIZE);
+    engine.set_max_array_size(MAX_COLLECTION_SIZE);
+    engine.set_max_map_size(MAX_COLLECTION_SIZE);
+
+    let print_log = log.clone();
+    engine.on_print(move |x| print_log.lock().unwrap().push(String::from(x)));
+
+    let debug_log = log.clone();
+    engine.on_debug(move |x, _, _| debug_log.lock().unwrap().push(String::from(x)));
+
+    let fn_log = log.clone();
+    engine.register_fn("log", move |x: Dynamic| fn_log.lock().unwrap().push(x.to_string()));
+
+    engine.register_fn("sha256", |x: &str| to_hex(&Sha256::digest(x.as_bytes())));
+    engine.register_fn("hmac_sha256", |key: &str, x: &str| to_hex(&hmac_sha256(key.as_bytes(), x.as_bytes())));
+    engine.register_fn("hmac_sha256_base64", |key: &str, x: &str| base64::encode(&hmac_sha256(key.as_bytes(), x.as_bytes())));
+    engine.register_fn("base64_encode", |x: &str| base64::encode(x.as_bytes()));
+    engine.register_fn("url_encode", url_encode);
+    engine.register_fn("timestamp", || unix_time_ms() / 1000);
+    engine.register_fn("timestamp_ms", unix_time_ms);
+    engine.register_fn("nonce", || to_hex(&rand::random::<[u8; 16]>()));
+    engine.register_fn("uuid", uuid_v4);
+
+    engine
+}
+
+pub fn json_to_dynamic(value: &Value) -> Dynamic {
+    match *value {
+        Value::Null => Dynamic::UNIT,
+        Value::Bool(x) => Dynamic::from(x),
+        Value::Number(ref x) => match x.as_i64() {
+            Some(i) => Dynamic::from(i),
+            None => Dynamic::from(x.as_f64().unwrap_or(0.0))
+        },
+        Value::String(ref x) => Dynamic::from(x.clone()),
+        Value::Array(ref items) => Dynamic::from(items.iter().map(json_to_dynamic).collect::<Array>()),
+        Value::Object(ref map) => Dynamic::from(map.iter().
+            map(|(k, v)| (k.as_str().into(), json_to_dynamic(v))).
+            collect::<Map>())
+    }
+}
+
+// Repeated headers are joined with a comma, names are lowercase
+fn headers_to_map(headers: &HeaderMap) -> Map {
+    let mut map = Map::new();
+
+    for name in headers.keys() {
+        let value = headers.get_all(name).iter().
+            filter_map(|x| x.to_str().ok()).
+            map(|x| x.trim()).
+            collect::<Vec<_>>().
+            join(", ");
+
+        map.insert(name.as_str().into(), Dynamic::from(value));
+    }
+
+    map
+}
+
+fn variables_to_map(variables: &[(String, String)]) -> Map {
+    variables.iter().map(|x| (x.0.as_str().into(), Dynamic::from(x.1.clone()))).collect()
+}
+
+fn changed_variables(before: &[(String, String)], after: &Map) -> Vec<(String, String)> {
+    after.iter().
+        map(|(k, v)| (k.to_string(), v.to_string())).
+        filter(|x| before.iter().rev().find(|y| y.0 == x.0).map(|y| y.1 != x.1).unwrap_or(true)).
+        collect()
+}
+
+// Untouched headers keep all their values, changed ones are replaced and removed ones dropped
+fn apply_headers(headers: &mut HeaderMap, before: &Map, after: &Map) -> Result<(), String> {
+    for name in before.keys().filter(|x| !after.contains_key(*x)) {
+        headers.remove(name.as_str());
+    }
+
+    for (name, value) in after {
+        let value = value.to_string();
+
+        if before.get(name).map(|x| x.to_string() == value).unwrap_or(false) {
+            continue;
+        }
+
+        let header_name = HeaderName::from_bytes(name.as_bytes()).
+            map_err(|_| format!("Invalid header name {}", name))?;
+        let header_value = value.parse::<HeaderValue>().
+            map_err(|_| format!("Invalid value of header {}", name))?;
+
+        headers.insert(header_name, header_value);
+    }
+
+    Ok(())
+}
+
+fn finish(log: Arc<Mutex<Vec<String>>>, variables: Vec<(String, String)>, error: Option<String>) -> ScriptResult {
+    let log = log.lock().map(|x| x.clone()).unwrap_or(Vec::new());
+
+    ScriptResult { log, variables, error }
+}
+
+// The script sees request (method, url, headers, body) and vars, changes to url, headers and body are sent
+pub fn run_pre_request(script: &str, request: &mut ::Request, variables: &[(String, String)]) -> ScriptResult {
+    if script.trim().is_empty() {
+        return ScriptResult::default();
+    }
+
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let engine = create_engine(&log);
+    let headers_before = headers_to_map(&request.headers);
+    let method = request::conv_to_http_method(&request.method, &request.custom_method).
+        map(|x| x.to_string()).
+        unwrap_or(request.custom_method.clone());
+
+    let mut request_map = Map::new();
+    request_map.insert("method".into(), Dynamic::from(method));
+    request_map.insert("url".into(), Dynamic::from(request.url.clone()));
+    request_map.insert("headers".into(), Dynamic::from(headers_before.clone()));
+    request_map.insert("body".into(), Dynamic::from(request.body.clone()));
+
+    let mut scope = Scope::new();
+    scope.push("request", request_map);
+    scope.push("vars", variables_to_map(variables));
+
+    if let Err(err) = engine.run_with_scope(&mut scope, script) {
+        return finish(log, Vec::new(), Some(format!("Pre-request script failed - {}", err)));
+    }
+
+    let changed = scope.get_value::<Map>("vars").map(|x| changed_variables(variables, &x)).unwrap_or(Vec::new());
+    let request_map = scope.get_value::<Map>("request").unwrap_or(Map::new());
+    let field = |name: &str| request_map.get(name).map(|x| x.to_string());
+
+    if let Some(url) = field("url") {
+        request.url = url;
+    }
+
+    if let Some(body) = field("body") {
+        request.body = body;
+    }
+
+    let headers_after = request_map.get("headers").and_then(|x| x.clone().try_cast::<Map>()).unwrap_or(Map::new());
+
+    match apply_headers(&mut request.headers, &headers_before, &headers_after) {
+        Ok(_) => finish(log, changed, None),
+        Err(err) => finish(log, changed, Some(format!("Pre-request script failed - {}", err)))
+    }
+}
+
+// The script sees response (status, reason, url, headers, body, json, time) and vars
+pub fn run_post_response(script: &str, resp: &::Response, variables: &[(String, String)]) -> ScriptResult {
+    if script.trim().is_empty() {
+        return ScriptResult::default();
+    }
+
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let engine = create_engine(&log);
+    let json = serde_json::from_str::<Value>(&resp.text).map(|x| json_to_dynamic(&x)).unwrap_or(Dynamic::UNIT);
+
+    let mut response_map = Map::new();
+    response_map.insert("status".into(), Dynamic::from(resp.status as i64));
+    response_map.insert("reason".into(), Dynamic::from(resp.reason.clone()));
+    response_map.insert("url".into(), Dynamic::from(resp.url.clone()));
+    response_map.insert("headers".into(), Dynamic::from(headers_to_map(&resp.headers)));
+    response_map.insert("body".into(), Dynamic::from(resp.text.clone()));
+    response_map.insert("json".into(), json);
+    response_map.insert("time".into(), Dynamic::from(resp.timings.total_ms()));
+
+    let mut scope = Scope::new();
+    scope.push("response", response_map);
+    scope.push("vars", variables_to_map(variables));
+
+    let error = engine.run_with_scope(&mut scope, script).err().map(|err| format!("Post-response script failed - {}", err));
+    let changed = scope.get_value::<Map>("vars").map(|x| changed_variables(variables, &x)).unwrap_or(Vec::new());
+
+    finish(log, changed, error)
+}
@@ -0,0 +1,127 @@
+use super::scripting::{run_pre_request, run_post_response};
+use super::request::parse_headers;
+use super::timing::Timings;
+use super::{Request, RequestMethod, BodyType, Response};
+use mime::APPLICATION_JSON;
+use encoding_rs::UTF_8;
+use std::time::Duration;
+use std::fs;
+use std::env;
+use std::process;
+
+fn request() -> Request {
+    Request {
+        method: RequestMethod::Post,
+        custom_method: String::new(),
+        url: String::from("http://localhost/items"),
+        headers: parse_headers("Accept:application/json\nX-Old:1\nX-Multi:a\nX-Multi:b", &mut |_| {}),
+        body_type: BodyType::Json,
+        body: String::from("{}"),
+        body_file: String::new(),
+        decompress: true,
+    }
+}
+
+fn response(body: &str) -> Response {
+    Response {
+        text: String::from(body),
+        body: body.as_bytes().to_vec(),
+        mime_type: APPLICATION_JSON,
+        encoding: UTF_8,
+        force_text: false,
+        extension: "json",
+        highlight: None,
+        headers: parse_headers("Content-Type:application/json\nX-Token:abc", &mut |_| {}),
+        status: 200,
+        reason: String::from("OK"),
+        version: String::from("HTTP/1.1"),
+        url: String::from("http://localhost/login"),
+        size: body.len(),
+        wire_size: body.len(),
+        content_encoding: String::new(),
+        decode_error: None,
+        raw_encoded: false,
+        timings: Timings { waiting: Duration::from_millis(120), ..Default::default() },
+        assertions: Vec::new(),
+    }
+}
+
+fn vars(list: &[(&str, &str)]) -> Vec<(String, String)> {
+    list.iter().map(|x| (String::from(x.0), String::from(x.1))).collect()
+}
+
+#[test]
+pub fn test_pre_request_script() {
+    let mut req = request();
+    let script = r#"
+        request.url += "?page=" + vars.page;
+        request.headers["x-signature"] = hmac_sha256("key", "The quick brown fox jumps over the lazy dog");
+        request.headers.remove("x-old");
+        request.body = `{"method":"${request.method}"}`;
+        vars.page = "3";
+        vars.token = "t1";
+        print("signed");
+        log(42);
+    "#;
+
+    let result = run_pre_request(script, &mut req, &vars(&[("page", "2"), ("host", "localhost")]));
+
+    assert_eq!(result.error, None);
+    assert_eq!(result.log, vec!["signed", "42"]);
+    assert_eq!(result.variables, vars(&[("page", "3"), ("token", "t1")]));
+    assert_eq!(req.url, "http://localhost/items?page=2");
+    assert_eq!(req.body, r#"{"method":"POST"}"#);
+    assert_eq!(req.headers.get("x-signature").unwrap(), "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8");
+    assert!(req.headers.get("x-old").is_none());
+    assert_eq!(req.headers.get_all("x-multi").iter().count(), 2);
+
+    assert_eq!(run_pre_request("  ", &mut req, &[]), Default::default());
+}
+
+#[test]
+pub fn test_pre_request_script_errors() {
+    let mut req = request();
+
+    let result = run_pre_request("print(\"before\");\nthrow \"stop\";", &mut req, &[]);
+    assert_eq!(result.log, vec!["before"]);
+    assert!(result.error.unwrap().starts_with("Pre-request script failed - "));
+    assert_eq!(req.url, "http://localhost/items");
+
+    let result = run_pre_request("loop { }", &mut req, &[]);
+    assert!(result.error.is_some());
+
+    let result = run_pre_request("request.headers[\"bad name\"] = \"1\";", &mut req, &[]);
+    assert_eq!(result.error, Some(String::from("Pre-request script failed - Invalid header name bad name")));
+
+    // Concurrent test runs each get their own module file
+    let module = env::temp_dir().join(format!("auweb_scripting_import_{}.rhai", process::id()));
+    fs::write(&module, "export const value = 1;").unwrap();
+    let script = format!("import {:?} as m;\nprint(m::value);", module.with_extension("").to_str().unwrap());
+
+    let result = run_pre_request(&script, &mut req, &[]);
+    fs::remove_file(&module).unwrap_or(());
+    assert!(result.log.is_empty());
+    assert!(result.error.unwrap().starts_with("Pre-request script failed - "));
+}
+
+#[test]
+pub fn test_post_response_script() {
+    let resp = response(r#"{"token": "secret", "items": [1, 2]}"#);
+    let script = r#"
+        if response.status == 200 {
+            vars.token = response.json.token;
+            vars.count = response.json.items.len();
+        }
+        log(response.headers["x-token"]);
+        log(response.time > 100.0);
+    "#;
+
+    let result = run_post_response(script, &resp, &vars(&[("count", "2")]));
+
+    assert_eq!(result.error, None);
+    assert_eq!(result.log, vec!["abc", "true"]);
+    assert_eq!(result.variables, vars(&[("token", "secret")]));
+
+    let result = run_post_response("vars.x = response.json.token;", &response("plain"), &[]);
+    assert!(result.error.unwrap().starts_with("Post-response script failed - "));
+}
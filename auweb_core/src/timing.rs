@@ -0,0 +1,44 @@
+use std::time::Duration;
+
+// The setup phases stay zero when they could not be measured, like for requests
+// whose host did not resolve or entries recorded before they were kept
+#[derive(Debug, Clone, Default)]
+pub struct Timings {
+    pub dns: Duration,
+    pub connect: Duration,
+    pub tls: Duration,
+    pub waiting: Duration,
+    pub transfer: Duration,
+}
+
+pub fn duration_to_ms(x: Duration) -> f64 {
+    x.as_secs() as f64 * 1000.0 + x.subsec_nanos() as f64 / 1_000_000.0
+}
+
+impl Timings {
+    pub fn has_setup(&self) -> bool {
+        self.dns + self.connect + self.tls > Duration::from_secs(0)
+    }
+
+    // Without separate setup phases the waiting time also covers DNS, connect and TLS
+    pub fn phases(&self) -> Vec<(&'static str, f64)> {
+        if !self.has_setup() {
+            return vec![
+                ("Time to headers", duration_to_ms(self.waiting)),
+                ("Transfer", duration_to_ms(self.transfer)),
+            ];
+        }
+
+        vec![
+            ("DNS", duration_to_ms(self.dns)),
+            ("Connect", duration_to_ms(self.connect)),
+            ("TLS", duration_to_ms(self.tls)),
+            ("Waiting", duration_to_ms(self.waiting)),
+            ("Transfer", duration_to_ms(self.transfer)),
+        ]
+    }
+
+    pub fn total_ms(&self) -> f64 {
+        self.phases().iter().map(|x| x.1).sum()
+    }
+}
@@ -0,0 +1,206 @@
+extern crate auweb_core;
+
+use auweb_core::{Request, RequestMethod, BodyType};
+use auweb_core::request::{self, parse_headers};
+use auweb_core::format::{beautify_response_text, format_headers};
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+fn read_request<R: Read>(stream: &mut R) -> String {
+    let mut received = Vec::new();
+    let mut buf = [0; 4096];
+
+    loop {
+        let read = stream.read(&mut buf).unwrap_or(0);
+        received.extend_from_slice(&buf[..read]);
+
+        let text = String::from_utf8_lossy(&received).to_string();
+        let length = text.lines().
+            find(|x| x.to_lowercase().starts_with("content-length:")).
+            and_then(|x| x[15..].trim().parse::<usize>().ok()).
+            unwrap_or(0);
+        let complete = text.find("\r\n\r\n").map(|x| received.len() >= x + 4 + length).unwrap_or(false);
+
+        if read == 0 || complete {
+            return text;
+        }
+    }
+}
+
+// The client times its connection setup on a connection of its own, which closes without a request
+fn accept_request(listener: &TcpListener) -> (TcpStream, String) {
+    loop {
+        let (mut stream, _) = listener.accept().unwrap();
+        let received = read_request(&mut stream);
+
+        if !received.is_empty() {
+            return (stream, received);
+        }
+    }
+}
+
+// Answers the first request with the given response and hands back what it received
+fn serve_once(response: &'static str) -> (String, thread::JoinHandle<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/items?id=1", listener.local_addr().unwrap());
+
+    let handle = thread::spawn(move || {
+        let (mut stream, received) = accept_request(&listener);
+        stream.write_all(response.as_bytes()).unwrap();
+        received
+    });
+
+    (url, handle)
+}
+
+// Answers with a chunked body that trickles in over about three seconds, or never answers without a head
+fn serve_slowly(head: &'static str) -> (String, thread::JoinHandle<()>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/", listener.local_addr().unwrap());
+
+    let handle = thread::spawn(move || {
+        let (mut stream, _) = accept_request(&listener);
+
+        if head.is_empty() {
+            thread::sleep(Duration::from_secs(3));
+            return;
+        }
+
+        stream.write_all(head.as_bytes()).unwrap();
+
+        for _ in 0..30 {
+            if stream.write_all(b"1\r\nx\r\n").is_err() {
+                return;
+            }
+
+            thread::sleep(Duration::from_millis(100));
+        }
+
+        stream.write_all(b"0\r\n\r\n").unwrap_or(());
+    });
+
+    (url, handle)
+}
+
+fn get_request(url: String, headers: &str) -> Request {
+    Request {
+        method: RequestMethod::Get,
+        custom_method: String::new(),
+        url,
+        headers: parse_headers(headers, &mut |x| panic!("{}", x)),
+        body_type: BodyType::Empty,
+        body: String::new(),
+        body_file: String::new(),
+        decompress: true,
+    }
+}
+
+#[test]
+pub fn test_send_json_request() {
+    let (url, server) = serve_once("HTTP/1.1 201 Created\r\nContent-Type: application/json\r\n\
+        X-Request-Id: 42\r\nContent-Length: 15\r\nConnection: close\r\n\r\n{\"id\":1,\"ok\":1}");
+
+    let request = Request {
+        method: RequestMethod::Post,
+        custom_method: String::new(),
+        url,
+        headers: parse_headers("Accept:application/json\nX-Token:secret\nX-AU-No-History:1", &mut |x| panic!("{}", x)),
+        body_type: BodyType::Json,
+        body: String::from("{\"name\":\"item\"}"),
+        body_file: String::new(),
+        decompress: true,
+    };
+
+    let response = request::send(&request).unwrap();
+    let received = server.join().unwrap();
+
+    assert!(received.starts_with("POST /items?id=1 HTTP/1.1\r\n"));
+    assert!(received.contains("content-type: application/json\r\n"));
+    assert!(received.contains("x-token: secret\r\n"));
+    assert!(!received.contains("x-au-"));
+    assert!(received.ends_with("{\"name\":\"item\"}"));
+
+    assert_eq!(response.status, 201);
+    assert_eq!(response.reason, "Created");
+    assert_eq!(response.extension, "json");
+    assert!(!response.is_binary());
+    assert!(response.timings.has_setup());
+    assert_eq!(response.timings.phases().len(), 5);
+    assert_eq!(response.timings.tls, Duration::from_secs(0));
+    assert!(format_headers(&response.headers).contains("x-request-id: 42\n"));
+    assert_eq!(beautify_response_text(response.extension, &response.text), "{\n  \"id\": 1,\n  \"ok\": 1\n}");
+}
+
+#[test]
+pub fn test_send_reports_truncated_body() {
+    let (url, server) = serve_once("HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: 100\r\n\
+        Connection: close\r\n\r\nonly part");
+
+    let request = Request {
+        method: RequestMethod::Get,
+        custom_method: String::new(),
+        url,
+        headers: parse_headers("", &mut |_| {}),
+        body_type: BodyType::Empty,
+        body: String::new(),
+        body_file: String::new(),
+        decompress: true,
+    };
+
+    let result = request::send(&request);
+    server.join().unwrap();
+
+    assert!(result.err().unwrap().starts_with("Cannot read the response body - "));
+}
+
+#[test]
+pub fn test_send_reports_errors() {
+    let request = Request {
+        method: RequestMethod::Custom,
+        custom_method: String::new(),
+        url: String::from("http://127.0.0.1:1/"),
+        headers: parse_headers("", &mut |_| {}),
+        body_type: BodyType::Empty,
+        body: String::new(),
+        body_file: String::new(),
+        decompress: true,
+    };
+
+    assert_eq!(request::send(&request).err(), Some(String::from("Custom method name is empty")));
+}
+
+#[test]
+pub fn test_send_times_out() {
+    let (url, server) = serve_slowly("");
+    let started = Instant::now();
+
+    assert!(request::send(&get_request(url.clone(), "X-AU-Timeout: 1")).is_err());
+    assert!(started.elapsed() < Duration::from_secs(3));
+    server.join().unwrap();
+
+    assert_eq!(request::send(&get_request(url, "X-AU-Timeout: soon")).err(),
+        Some(String::from("X-AU-Timeout must be a whole number of seconds")));
+}
+
+#[test]
+pub fn test_cancel_stops_reading_the_body() {
+    let (url, server) = serve_slowly("HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nTransfer-Encoding: chunked\r\n\r\n");
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let canceller = cancelled.clone();
+
+    thread::spawn(move || {
+        thread::sleep(Duration::from_millis(500));
+        canceller.store(true, Ordering::SeqCst);
+    });
+
+    let started = Instant::now();
+    let result = request::perform_request(&get_request(url, "X-AU-Timeout: 0"), &cancelled);
+
+    assert!(result.is_none());
+    assert!(started.elapsed() < Duration::from_secs(3));
+    server.join().unwrap();
+}
@@ -1,184 +1,308 @@
-use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
-use mime::{Mime, TEXT_PLAIN, APPLICATION, JSON, TEXT, XML, HTML};
-use serde_json;
+use gdk_pixbuf::{Pixbuf, PixbufLoader, PixbufLoaderExt};
+use encoding_rs::{Encoding};
 use gtk_ext;
 use gtk_ext::{TextWidget};
 use sourceview::{BufferExt, LanguageManagerExt};
+use gtk::{Cast, LabelExt, StyleContextExt, WidgetExt, Inhibit, ImageExt, StackExt};
+use auweb_core::assertions::AssertionResult;
+use auweb_core::jsonpath;
 use std;
 use reqwest;
 use std::error::{Error};
-use glib;
-use ::xml;
-use ::html;
-
-pub const CONTENT_TYPE_JSON: &'static str = "json";
-pub const CONTENT_TYPE_DEFAULT: &'static str = "";
-pub const CONTENT_TYPE_XML: &'static str = "xml";
-pub const CONTENT_TYPE_HTML: &'static str = "html";
-
-pub fn to_pair_if_both<T, U>(t: Option<T>, u: Option<U>) -> Option<(T, U)> {
-    match (t, u) {
-        (Some(x), Some(y)) => Some((x, y)),
-        _ => None
-    }
-}
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use ::charset;
+use cairo;
+use editor::RequestEditor;
 
-pub fn parse_headers<F: FnMut(&str)>(text: &str, error_log: &mut F) -> HeaderMap {
-    let mut headers = HeaderMap::new();
+pub use auweb_core::format::{CONTENT_TYPE_JSON, CONTENT_TYPE_DEFAULT, CONTENT_TYPE_XML, CONTENT_TYPE_HTML,
+    detect_mime_type, conv_mime_type_to_extension, is_image_mime, is_text_mime, hex_dump, beautify_response_text,
+    format_headers, response_body_text, format_size, format_transfer_size};
+pub use auweb_core::request::{MultipartField, parse_headers, create_post_req_data, create_multipart_fields,
+    conv_to_http_method, body_type_content_type, raw_body_type, perform_request, is_client_header};
 
-    for line in text.lines().filter(|x| x.trim() != "") {
-        let pair = line.find(":").
-            map(|x| line.split_at(x)).
-            map(|p| (p.0, p.1.get(1..).unwrap_or("")));
+pub fn output_to_sourceview(target: &RequestEditor, resp: &::Response) {
+    let highlight_override = resp.highlight.as_ref().map(String::as_str);
+    let mime_str = resp.mime_type.to_string();
+    let binary = resp.is_binary();
+    let text = response_body_text(resp);
+    let extension = match highlight_override {Some(x) => x, _ if binary => CONTENT_TYPE_DEFAULT, _ => resp.extension};
+    let content_type = match highlight_override {Some(_) => None, _ if binary => None, _ => Some(mime_str.as_str())};
 
-        let parsed_pair = pair.and_then(|x| {
-            let name = HeaderName::from_bytes(x.0.as_bytes()).ok();
-            let val = x.1.parse::<HeaderValue>().ok();
+    target.set_rs_body(&text);
+    target.resp_headers_mtx.replace_all_text(&format_headers(&resp.headers));
 
-            to_pair_if_both(name, val)
-        });
+    update_status_bar(target, resp);
+    update_image_preview(target, resp);
+    update_assertion_badges(target, &resp.assertions);
 
-        match parsed_pair {
-            Some(p) => { headers.append(p.0, p.1); },
-            None => error_log(&(String::from("Failed to parse header - ") + line))
-        };
+    {
+        let mut state = target.state.borrow_mut();
+        state.current_extension = Some(String::from(extension));
+        state.current_mime = content_type.map(|x| String::from(x));
     }
 
-    headers
+    update_resp_body_highlighting(target);
 }
 
-pub fn detect_mime_type(headers: &HeaderMap) -> Mime {
-    headers.get("content-type").
-        and_then(|x| x.to_str().ok()).
-        and_then(|x| x.parse::<Mime>().ok()).unwrap_or(TEXT_PLAIN)
+pub fn show_response(target: &RequestEditor, resp: ::Response) {
+    output_to_sourceview(target, &resp);
+    target.state.borrow_mut().response = Some(resp);
+    target.timings_area.queue_draw();
 }
 
-pub fn conv_mime_type_to_extension(mime: &Mime) -> &'static str {
-    match (mime.type_(), mime.subtype()) {
-        (APPLICATION, JSON) | (TEXT, JSON) => {
-            CONTENT_TYPE_JSON
-        },
-        (APPLICATION, XML) | (TEXT, XML) => {
-            CONTENT_TYPE_XML
+pub fn reset_response_view(target: &RequestEditor) {
+    target.state.borrow_mut().response = None;
+
+    target.resp_img.clear();
+    target.resp_img_info.set_text("");
+    target.resp_stack_switcher.hide();
+    target.resp_stack.set_visible_child_name("text");
+    target.status_lbl.set_text("");
+    target.status_bar.get_style_context().map(|context| clear_status_classes(&context));
+    target.timings_area.queue_draw();
+    update_assertion_badges(target, &[]);
+}
+
+// Filters always start from the whole body, which is kept aside until the original is shown again
+pub fn apply_response_filter(target: &RequestEditor) {
+    let expression = target.resp_filter_inp.upcast_ref::<gtk::Entry>().get_all_text();
+
+    if expression.trim().is_empty() {
+        show_original_body(target);
+        return;
+    }
+
+    let original = target.get_rs_body();
+
+    match jsonpath::filter_text(&original, &expression) {
+        Ok((fragment, count)) => {
+            target.state.borrow_mut().unfiltered_body = Some(original);
+            target.resp_mtx.replace_all_text(&fragment);
+            target.resp_filter_lbl.set_text(&format!("{} {}", count, if count == 1 { "match" } else { "matches" }));
+            target.resp_original_btn.set_sensitive(true);
         },
-        (TEXT, HTML) => {
-            CONTENT_TYPE_HTML
-        }
-        _ => CONTENT_TYPE_DEFAULT
+        Err(err) => target.resp_filter_lbl.set_text(&err)
+    };
+}
+
+// A filter typed for an earlier body is applied to the new one as well
+pub fn replace_response_body(target: &RequestEditor, text: &str) {
+    forget_response_filter(target);
+    target.resp_mtx.replace_all_text(text);
+
+    if !target.resp_filter_inp.upcast_ref::<gtk::Entry>().get_all_text().trim().is_empty() {
+        apply_response_filter(target);
     }
 }
 
-pub fn beautify_response_text(extension: &'static str, text: &str) -> String {
-    match extension {
-        CONTENT_TYPE_JSON => {
-            let json_result: serde_json::Result<serde_json::Value> = serde_json::from_str(&text);
-            match json_result {
-                Ok(json) => serde_json::ser::to_string_pretty(&json).expect("Cannot stringify JSON"),
-                Err(_) => text.to_owned()
-            }
-        },
-        CONTENT_TYPE_XML => {
-            xml::beautify_xml(&text).unwrap_or(text.to_string())
-        },
-        CONTENT_TYPE_HTML => {
-            html::beautify_html(&text)
-        },
-        _ => text.to_owned()
+pub fn show_original_body(target: &RequestEditor) {
+    let original = target.state.borrow_mut().unfiltered_body.take();
+
+    if let Some(text) = original {
+        target.resp_mtx.replace_all_text(&text);
     }
+
+    forget_response_filter(target);
 }
 
-pub fn output_to_sourceview(target: &::MainWindow, resp: &::Response) {
-    let highlight_override = resp.highlight.as_ref().map(String::as_str);
-    let mime_str = resp.mime_type.to_string();
-    let text = beautify_response_text(resp.extension, &resp.text);
-    let extension = match highlight_override {Some(x) => x, _ => resp.extension};
-    let content_type = match highlight_override {Some(_) => None, _ => Some(mime_str.as_str())};
+// The body shown is replaced, so the one kept aside is dropped without restoring it
+fn forget_response_filter(target: &RequestEditor) {
+    target.state.borrow_mut().unfiltered_body = None;
+    target.resp_filter_lbl.set_text("");
+    target.resp_original_btn.set_sensitive(false);
+}
+
+fn escape_markup(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;").replace('\'', "&apos;")
+}
 
-    target.resp_mtx.replace_all_text(&text);
+// Each check becomes a colored badge, failed ones also say what was received
+pub fn assertion_markup(results: &[AssertionResult]) -> String {
+    results.iter().map(|x| {
+        let (color, mark, text) = if x.passed {
+            ("#2E6B30", "\u{2714}", x.assertion.clone())
+        } else {
+            ("#8A2B2B", "\u{2718}", format!("{}: {}", x.assertion, x.message))
+        };
 
-    let mut headers_text = String::new();
+        format!("<span background=\"{}\" foreground=\"#FFFFFF\"> {} {} </span>", color, mark, escape_markup(&text))
+    }).collect::<Vec<_>>().join("  ")
+}
 
-    for ref header in &resp.headers {
-        headers_text += header.0.as_str();
-        headers_text += ": ";
-        headers_text += header.1.to_str().unwrap();
-        headers_text += "\n";
+pub fn update_assertion_badges(target: &RequestEditor, results: &[AssertionResult]) {
+    if results.is_empty() {
+        target.assertions_lbl.set_markup("");
+        target.assertions_lbl.hide();
+        return;
     }
 
-    target.resp_headers_mtx.replace_all_text(&headers_text);
+    let passed = results.iter().filter(|x| x.passed).count();
 
-    ::CONFIG.with(|conf| {
-        let mut state = conf.borrow_mut();
-        state.current_extension = Some(String::from(extension));
-        state.current_mime = content_type.map(|x| String::from(x));
-    });
+    target.assertions_lbl.set_markup(&assertion_markup(results));
+    target.assertions_lbl.set_tooltip_text(format!("{} of {} checks passed", passed, results.len()).as_str());
+    target.assertions_lbl.show();
+}
 
-    update_resp_body_highlighting(target);
+pub fn load_pixbuf(body: &[u8]) -> Option<(Pixbuf, String)> {
+    let loader = PixbufLoader::new();
+
+    if loader.write(body).is_err() || loader.close().is_err() {
+        return None;
+    }
+
+    let format = loader.get_format().
+        and_then(|x| x.get_name()).
+        map(|x| x.to_uppercase()).
+        unwrap_or(String::from("Unknown"));
+
+    loader.get_pixbuf().map(|x| (x, format))
+}
+
+pub fn update_image_preview(target: &RequestEditor, resp: &::Response) {
+    let preview = if is_image_mime(&resp.mime_type) { load_pixbuf(&resp.body) } else { None };
+
+    match preview {
+        Some((pixbuf, format)) => {
+            target.resp_img_info.set_text(&format!("{} image, {} x {} px, {}",
+                format, pixbuf.get_width(), pixbuf.get_height(), format_size(resp.body.len())));
+            target.resp_img.set_from_pixbuf(Some(&pixbuf));
+            target.resp_stack_switcher.show();
+            target.resp_stack.set_visible_child_name("image");
+        },
+        None => {
+            target.resp_img.clear();
+            target.resp_img_info.set_text("");
+            target.resp_stack_switcher.hide();
+            target.resp_stack.set_visible_child_name("text");
+        }
+    };
+}
+
+pub fn status_css_class(status: u16) -> &'static str {
+    match status {
+        100..=199 => "au-status-info",
+        200..=299 => "au-status-success",
+        300..=399 => "au-status-redirect",
+        400..=499 => "au-status-client-error",
+        _ => "au-status-server-error",
+    }
 }
 
-pub fn update_resp_body_highlighting(target: &::MainWindow) {
-    ::CONFIG.with(|conf| {
-        let state = conf.borrow();
+fn clear_status_classes(context: &gtk::StyleContext) {
+    for class in &["au-status-info", "au-status-success", "au-status-redirect",
+        "au-status-client-error", "au-status-server-error"] {
+        context.remove_class(class);
+    }
+}
 
-        let extension = state.current_extension.as_ref().map(|x| x.as_str()).unwrap_or("text/plain");
-        let mime_str = state.current_mime.as_ref().map(|x| x.as_str());
+pub fn update_status_bar(target: &RequestEditor, resp: &::Response) {
+    let encoding = if resp.is_binary() { "binary" } else { resp.encoding.name() };
 
-        target.lang_manager.
-            guess_language(Some((String::from("dummy.") + extension).as_str()), mime_str).
-            map(|lang| gtk_ext::apply_to_src_buf(&target.resp_mtx, &|x| x.set_language(&lang)));
+    target.status_lbl.set_text(&format!("{} {}    {}    {}    {}    {:.0} ms    {}",
+        resp.status, resp.reason, resp.version, format_transfer_size(resp), encoding, resp.timings.total_ms(), resp.url));
+
+    target.status_bar.get_style_context().map(|context| {
+        clear_status_classes(&context);
+        context.add_class(status_css_class(resp.status));
     });
 }
 
-pub fn create_post_req_data(text: &str) -> Vec<(&str, &str)> {
-    let mut form = Vec::new();
+const TIMING_COLORS: [(f64, f64, f64); 5] = [
+    (0.40, 0.80, 0.80),
+    (0.98, 0.57, 0.34),
+    (0.80, 0.60, 0.80),
+    (0.60, 0.80, 0.60),
+    (0.40, 0.60, 0.80),
+];
+
+pub fn draw_timings(resp: Option<&::Response>, area: &gtk::DrawingArea, cr: &cairo::Context) -> Inhibit {
+    if let Some(resp) = resp {
+        let label_width = 150.0;
+        let row_height = 12.0;
+        let bar_width = (area.get_allocated_width() as f64 - label_width - 8.0).max(1.0);
+        let total = resp.timings.total_ms();
+        let scale = if total > 0.0 { bar_width / total } else { 0.0 };
+        let mut offset = 0.0;
 
-    for line in text.lines() {
-        let tokens = line.splitn(2,'=');                    
-        let mut key_value: (&str, &str) = ("", "");
+        cr.set_font_size(10.0);
 
-        for (i, item) in tokens.enumerate() {
-            match i {
-                0 => key_value.0 = item,
-                1 => key_value.1 = item,
-                _ => panic!("should never happen")
-            };
+        for (i, phase) in resp.timings.phases().iter().enumerate() {
+            let y = 2.0 + i as f64 * row_height;
+            let color = TIMING_COLORS[i % TIMING_COLORS.len()];
+
+            cr.set_source_rgb(0.83, 0.84, 0.81);
+            cr.move_to(4.0, y + row_height - 3.0);
+            cr.show_text(&format!("{} {:.1} ms", phase.0, phase.1));
+
+            cr.set_source_rgb(color.0, color.1, color.2);
+            cr.rectangle(label_width + offset * scale, y + 2.0, (phase.1 * scale).max(1.0), row_height - 4.0);
+            cr.fill();
+
+            offset += phase.1;
         }
+    }
+
+    Inhibit(false)
+}
+
+pub fn reinterpret_response(target: &RequestEditor, encoding: Option<&'static Encoding>) {
+    let response = target.state.borrow_mut().response.take();
 
-        form.push(key_value);
+    if let Some(mut resp) = response {
+        resp.encoding = encoding.unwrap_or_else(|| charset::detect_encoding(&resp.body, &resp.mime_type));
+        resp.text = charset::decode_with(&resp.body, resp.encoding);
+        resp.force_text = encoding.is_some();
+        output_to_sourceview(target, &resp);
+        target.state.borrow_mut().response = Some(resp);
     }
+}
 
-    form
+fn suggest_file_name(url: &str) -> String {
+    reqwest::Url::parse(url).ok().
+        and_then(|x| x.path_segments().and_then(|y| y.last().map(|z| String::from(z)))).
+        and_then(|x| if x.is_empty() { None } else { Some(x) }).
+        unwrap_or(String::from("response.bin"))
 }
 
-pub fn http_worker(
-    request_method: ::RequestMethod, 
-    url: &str, 
-    req: String, 
-    highlight_override: Option<String>,
-    headers: HeaderMap,
-    tx: std::sync::mpsc::Sender<std::result::Result<::Response, std::string::String>>)
-{
-    let client = reqwest::Client::new();
-            
-    let req_error_to_string = |err: reqwest::Error| Err(String::from("Request failed: ") + err.description());
+pub fn save_response_body(target: &::MainWindow) {
+    // Copied out so that a response arriving while the dialog is open can replace it
+    let last_body = target.editor().state.borrow().response.as_ref().
+        map(|x| (x.body.clone(), suggest_file_name(&x.url)));
 
-    let request_result = match request_method {
-        ::RequestMethod::GetWithUri => {
-            client.get(url).headers(headers).send()
-        },
-        ::RequestMethod::PostWithForm => {
-            client.post(url).headers(headers).form(create_post_req_data(&req).as_slice()).send()
+    match last_body {
+        Some((body, file_name)) => {
+            let path = gtk_ext::choose_file_to_save(&target.window, "Save body as", &file_name);
+
+            path.map(|x| match std::fs::write(&x, &body) {
+                Ok(_) => (),
+                Err(err) => gtk_ext::show_message(&(String::from("Cannot save body - ") + err.description()), &target.window)
+            });
         },
-        ::RequestMethod::PostRaw => {
-            client.post(url).headers(headers).body(req).send()
-        }
+        None => gtk_ext::show_message("There is no response to save yet", &target.window)
     };
+}
 
-    let result = request_result.or_else(req_error_to_string).map(|mut x| {
-        ::Response::from(&mut x).with_highlight_override(highlight_override)
-    });
+pub fn update_resp_body_highlighting(target: &RequestEditor) {
+    let state = target.state.borrow();
+
+    let extension = state.current_extension.as_ref().map(|x| x.as_str()).unwrap_or("text/plain");
+    let mime_str = state.current_mime.as_ref().map(|x| x.as_str());
+
+    target.lang_manager.
+        guess_language(Some((String::from("dummy.") + extension).as_str()), mime_str).
+        map(|lang| gtk_ext::apply_to_src_buf(&target.resp_mtx, &|x| x.set_language(&lang)));
+}
 
-    tx.send(result).unwrap();
-    
-    glib::idle_add(::receive);
+pub fn http_worker(
+    request: ::Request,
+    highlight_override: Option<String>,
+    assertions: String,
+    cancelled: Arc<AtomicBool>,
+    tx: std::sync::mpsc::Sender<std::result::Result<::Response, std::string::String>>)
+{
+    if let Some(result) = perform_request(&request, &cancelled) {
+        let result = result.map(|x| x.with_highlight_override(highlight_override).with_assertions(&assertions));
+        tx.send(result).unwrap_or(());
+    }
 }
@@ -0,0 +1,370 @@
+use std::io::{self, Write};
+use config;
+use actions;
+use environments;
+use collections::{self, SavedRequest};
+use auweb_core::request::send;
+use auweb_core::assertions::{parse_status_list, status_matches};
+use auweb_core::scripting::{self, ScriptResult};
+
+pub const RUN_COMMAND: &'static str = "run";
+
+const EXIT_TRANSPORT_ERROR: i32 = 1;
+const EXIT_USAGE_ERROR: i32 = 2;
+const EXIT_UNEXPECTED_STATUS: i32 = 3;
+const EXIT_ASSERTION_FAILED: i32 = 4;
+const EXIT_SCRIPT_FAILED: i32 = 5;
+
+const VALUE_OPTIONS: [&'static str; 15] = ["-X", "--request", "-u", "--url", "-H", "--header", "-d", "--data",
+    "--body-type", "--body-file", "-e", "--env", "--var", "-s", "--expect-status"];
+
+const USAGE: &'static str = "Usage: auweb run [OPTIONS] [SAVED_REQUEST]
+
+Sends a saved request, chosen by id or name, or one described by the options.
+Options given together with a saved request override its parts.
+
+Options:
+  -X, --request METHOD         HTTP method
+  -u, --url URL                Request URL
+  -H, --header 'NAME: VALUE'   Adds a header, may be repeated
+  -d, --data BODY              Request body
+      --body-type TYPE         empty, raw, json, xml, form, binary or multipart
+      --body-file PATH         File sent as a binary body
+  -e, --env NAME               Environment for {{variables}}, the active one by default
+      --var NAME=VALUE         Adds a variable, may be repeated
+  -s, --expect-status LIST     Accepted statuses like 200,204 or 2xx or 200-299, 2xx by default
+  -i, --include                Prints the status line and headers before the body
+      --raw                    Prints the body as received, without beautifying
+      --no-decompress          Keeps the body content-encoded
+  -h, --help                   Shows this help
+
+Scripts stored with a saved request run before sending and after the response arrives,
+their log goes to stderr. Checks stored with a saved request are evaluated after the expected status.
+Exit status is 1 when the request fails, 2 on invalid arguments, 3 on an unexpected status,
+4 when a check fails and 5 when a script fails.";
+
+#[derive(Debug, Clone, Default)]
+pub struct RunOptions {
+    pub saved_request: Option<String>,
+    pub method: Option<String>,
+    pub url: Option<String>,
+    pub headers: Vec<String>,
+    pub body: Option<String>,
+    pub body_type: Option<i32>,
+    pub body_file: Option<String>,
+    pub environment: Option<String>,
+    pub variables: Vec<(String, String)>,
+    pub expected_status: Vec<(u16, u16)>,
+    pub include: bool,
+    pub raw: bool,
+    pub decompress: bool,
+    pub help: bool,
+}
+
+pub fn parse_body_type(name: &str) -> Result<i32, String> {
+    let body_type = match name.to_lowercase().as_str() {
+        "empty" => ::BodyType::Empty,
+        "raw" => ::BodyType::Raw,
+        "json" => ::BodyType::Json,
+        "xml" => ::BodyType::Xml,
+        "form" => ::BodyType::Form,
+        "binary" => ::BodyType::Binary,
+        "multipart" => ::BodyType::Multipart,
+        _ => return Err(format!("Unknown body type {}", name))
+    };
+
+    Ok(body_type as i32)
+}
+
+pub fn parse_args(args: &[String]) -> Result<RunOptions, String> {
+    let mut options = RunOptions { decompress: true, ..Default::default() };
+    let mut i = 0;
+
+    while i < args.len() {
+        // --name=value is the same as --name value
+        let (name, inline_value) = match args[i].find('=') {
+            Some(pos) if args[i].starts_with("--") => (&args[i][..pos], Some(args[i][pos + 1..].to_string())),
+            _ => (args[i].as_str(), None)
+        };
+
+        let value = match inline_value {
+            Some(x) => x,
+            None if VALUE_OPTIONS.contains(&name) => {
+                i += 1;
+                args.get(i).cloned().ok_or(format!("Option {} needs a value", name))?
+            },
+            None => String::new()
+        };
+
+        match name {
+            "-X" | "--request" => options.method = Some(value),
+            "-u" | "--url" => options.url = Some(value),
+            "-H" | "--header" => options.headers.push(value),
+            "-d" | "--data" => options.body = Some(value),
+            "--body-type" => options.body_type = Some(parse_body_type(&value)?),
+            "--body-file" => options.body_file = Some(value),
+            "-e" | "--env" => options.environment = Some(value),
+            "--var" => match value.find('=') {
+                Some(pos) => options.variables.push((String::from(value[..pos].trim()), String::from(&value[pos + 1..]))),
+                None => return Err(format!("Variable {} needs a value, like name=value", value))
+            },
+            "-s" | "--expect-status" => options.expected_status.extend(parse_status_list(&value)?),
+            "-i" | "--include" => options.include = true,
+            "--raw" => options.raw = true,
+            "--no-decompress" => options.decompress = false,
+            "-h" | "--help" => options.help = true,
+            x if x.starts_with('-') && x.len() > 1 => return Err(format!("Unknown option {}", x)),
+            x => {
+                if options.saved_request.is_some() {
+                    return Err(format!("Unexpected argument {}", x));
+                }
+
+                options.saved_request = Some(String::from(x));
+            }
+        };
+
+        i += 1;
+    }
+
+    if options.expected_status.is_empty() {
+        options.expected_status.push((200, 299));
+    }
+
+    Ok(options)
+}
+
+// Saved requests are looked up by id first, then by name ignoring case
+pub fn find_request(requests: &[SavedRequest], key: &str) -> Result<SavedRequest, String> {
+    if let Ok(id) = key.parse::<i64>() {
+        if let Some(request) = requests.iter().find(|x| x.id == Some(id)) {
+            return Ok(request.clone());
+        }
+    }
+
+    let found: Vec<&SavedRequest> = requests.iter().filter(|x| x.name.to_lowercase() == key.to_lowercase()).collect();
+
+    match found.len() {
+        0 => Err(format!("There is no saved request {}", key)),
+        1 => Ok(found[0].clone()),
+        _ => {
+            let ids = found.iter().map(|x| x.id.unwrap_or(0).to_string()).collect::<Vec<_>>().join(", ");
+            Err(format!("Several saved requests are named {}, use one of the ids {}", key, ids))
+        }
+    }
+}
+
+pub fn build_request(options: &RunOptions, saved: Option<SavedRequest>) -> SavedRequest {
+    let mut request = saved.unwrap_or_else(|| {
+        let mut request = SavedRequest::default();
+        request.set_method_name("GET");
+        request.body_type = ::BodyType::Empty as i32;
+        request
+    });
+
+    if let Some(ref method) = options.method {
+        request.set_method_name(method);
+    }
+
+    if let Some(ref url) = options.url {
+        request.url = url.clone();
+    }
+
+    for header in &options.headers {
+        if !request.req_headers.is_empty() && !request.req_headers.ends_with('\n') {
+            request.req_headers.push('\n');
+        }
+
+        request.req_headers += &format!("{}\n", header);
+    }
+
+    if let Some(ref body) = options.body {
+        request.req_body = body.clone();
+
+        let has_text_body = request.body_type != ::BodyType::Empty as i32 && request.body_type != ::BodyType::Binary as i32;
+
+        if !has_text_body {
+            request.body_type = ::BodyType::Raw as i32;
+        }
+    }
+
+    if let Some(ref body_file) = options.body_file {
+        request.body_file = body_file.clone();
+        request.body_type = ::BodyType::Binary as i32;
+    }
+
+    if let Some(body_type) = options.body_type {
+        request.body_type = body_type;
+    }
+
+    request
+}
+
+fn variables_for(options: &RunOptions) -> Result<Vec<(String, String)>, String> {
+    let mut variables = match options.environment {
+        Some(ref name) => environments::list_environments(&config::connect_to_state()).into_iter().
+            find(|x| x.name.to_lowercase() == name.to_lowercase()).
+            map(|x| environments::parse_variables(&x.variables)).
+            ok_or(format!("There is no environment {}", name))?,
+        None => {
+            let state = config::WindowState::read_from_db(&config::connect_to_state());
+            environments::active_variables(state.active_environment)
+        }
+    };
+
+    variables.extend(options.variables.iter().cloned());
+    Ok(variables)
+}
+
+fn to_request(options: &RunOptions, request: &SavedRequest, variables: &[(String, String)]) -> Result<::Request, String> {
+    let mut unresolved = Vec::new();
+    let url = environments::substitute(&request.url, variables, &mut unresolved);
+    let headers = environments::substitute(&request.req_headers, variables, &mut unresolved);
+    let body = environments::substitute(&request.req_body, variables, &mut unresolved);
+    let body_file = environments::substitute(&request.body_file, variables, &mut unresolved);
+
+    if !unresolved.is_empty() {
+        return Err(environments::unresolved_message(&unresolved));
+    }
+
+    if url.trim().is_empty() {
+        return Err(String::from("The request has no URL"));
+    }
+
+    let mut errors = Vec::new();
+    let headers = actions::parse_headers(&headers, &mut |x| errors.push(String::from(x)));
+
+    if !errors.is_empty() {
+        return Err(errors.join("\n"));
+    }
+
+    Ok(::Request {
+        method: config::WindowState::conv_to_req_method(request.request_method),
+        custom_method: request.custom_method.clone(),
+        url,
+        headers,
+        body_type: config::WindowState::conv_to_body_type(request.body_type),
+        body,
+        body_file,
+        decompress: options.decompress && request.decompress,
+    })
+}
+
+fn print_response(options: &RunOptions, resp: &::Response) -> io::Result<()> {
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+
+    if options.include {
+        write!(out, "{} {} {}\n{}\n", resp.version, resp.status, resp.reason, actions::format_headers(&resp.headers))?;
+    }
+
+    if options.raw || resp.is_binary() {
+        out.write_all(&resp.body)?;
+    } else {
+        writeln!(out, "{}", actions::beautify_response_text(resp.extension, &resp.text))?;
+    }
+
+    out.flush()
+}
+
+fn print_script_result(result: &ScriptResult) {
+    for line in &result.log {
+        eprintln!("{}", line);
+    }
+
+    if let Some(ref err) = result.error {
+        eprintln!("{}", err);
+    }
+}
+
+// Runs without touching GTK, so it also works without a display
+pub fn run(args: &[String]) -> i32 {
+    let options = match parse_args(args) {
+        Ok(ref x) if x.help => {
+            println!("{}", USAGE);
+            return 0;
+        },
+        Ok(x) => x,
+        Err(err) => {
+            eprintln!("{}\n\n{}", err, USAGE);
+            return EXIT_USAGE_ERROR;
+        }
+    };
+
+    let saved = match options.saved_request {
+        Some(ref key) => match find_request(&collections::list_requests(&config::connect_to_state()), key) {
+            Ok(x) => Some(x),
+            Err(err) => {
+                eprintln!("{}", err);
+                return EXIT_USAGE_ERROR;
+            }
+        },
+        None => None
+    };
+
+    let saved_request = build_request(&options, saved);
+
+    let mut variables = match variables_for(&options) {
+        Ok(x) => x,
+        Err(err) => {
+            eprintln!("{}", err);
+            return EXIT_USAGE_ERROR;
+        }
+    };
+
+    let mut request = match to_request(&options, &saved_request, &variables) {
+        Ok(x) => x,
+        Err(err) => {
+            eprintln!("{}", err);
+            return EXIT_USAGE_ERROR;
+        }
+    };
+
+    let pre_result = scripting::run_pre_request(&saved_request.pre_script, &mut request, &variables);
+    print_script_result(&pre_result);
+
+    if pre_result.error.is_some() {
+        return EXIT_SCRIPT_FAILED;
+    }
+
+    variables.extend(pre_result.variables);
+
+    match send(&request) {
+        Ok(resp) => {
+            if let Some(ref err) = resp.decode_error {
+                eprintln!("{}", err);
+            }
+
+            if let Err(err) = print_response(&options, &resp) {
+                eprintln!("Cannot print the response - {}", err);
+            }
+
+            let post_result = scripting::run_post_response(&saved_request.post_script, &resp, &variables);
+            print_script_result(&post_result);
+
+            if !status_matches(&options.expected_status, resp.status) {
+                eprintln!("Unexpected status {} {}", resp.status, resp.reason);
+                return EXIT_UNEXPECTED_STATUS;
+            }
+
+            let failed: Vec<_> = resp.with_assertions(&saved_request.assertions).assertions.into_iter().
+                filter(|x| !x.passed).
+                collect();
+
+            for result in &failed {
+                eprintln!("Check failed: {} - {}", result.assertion, result.message);
+            }
+
+            if !failed.is_empty() {
+                EXIT_ASSERTION_FAILED
+            } else if post_result.error.is_some() {
+                EXIT_SCRIPT_FAILED
+            } else {
+                0
+            }
+        },
+        Err(err) => {
+            eprintln!("{}", err);
+            EXIT_TRANSPORT_ERROR
+        }
+    }
+}
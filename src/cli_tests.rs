@@ -0,0 +1,60 @@
+use super::cli::{parse_args, find_request, build_request};
+use super::collections::SavedRequest;
+
+fn to_args(text: &str) -> Vec<String> {
+    text.split_whitespace().map(String::from).collect()
+}
+
+#[test]
+pub fn test_parse_args() {
+    let options = parse_args(&to_args("-X POST --url=http://localhost/a -H X-A:1 -H X-B:2 --var id=7 -i users")).unwrap();
+
+    assert_eq!(options.method, Some(String::from("POST")));
+    assert_eq!(options.url, Some(String::from("http://localhost/a")));
+    assert_eq!(options.headers, vec!["X-A:1", "X-B:2"]);
+    assert_eq!(options.variables, vec![(String::from("id"), String::from("7"))]);
+    assert_eq!(options.saved_request, Some(String::from("users")));
+    assert_eq!(options.expected_status, vec![(200, 299)]);
+    assert!(options.include);
+    assert!(options.decompress);
+
+    let options = parse_args(&to_args("--no-decompress -s 404 --body-type json")).unwrap();
+    assert!(!options.decompress);
+    assert_eq!(options.expected_status, vec![(404, 404)]);
+    assert_eq!(options.body_type, Some(::BodyType::Json as i32));
+
+    assert!(parse_args(&to_args("--url")).is_err());
+    assert!(parse_args(&to_args("--bogus")).is_err());
+    assert!(parse_args(&to_args("--var novalue")).is_err());
+    assert!(parse_args(&to_args("one two")).is_err());
+}
+
+#[test]
+pub fn test_find_and_build_request() {
+    let saved = vec![
+        SavedRequest { id: Some(3), name: String::from("Users"), ..Default::default() },
+        SavedRequest { id: Some(5), name: String::from("login"), ..Default::default() },
+        SavedRequest { id: Some(8), name: String::from("Login"), ..Default::default() },
+    ];
+
+    assert_eq!(find_request(&saved, "users").unwrap().id, Some(3));
+    assert_eq!(find_request(&saved, "8").unwrap().id, Some(8));
+    assert!(find_request(&saved, "LOGIN").unwrap_err().contains("5, 8"));
+    assert!(find_request(&saved, "missing").is_err());
+
+    let template = SavedRequest {
+        url: String::from("http://localhost/users"),
+        req_headers: String::from("Accept: */*"),
+        body_type: ::BodyType::Empty as i32,
+        ..Default::default()
+    };
+
+    let options = parse_args(&to_args("-X PUT -H X-A:1 -d {}")).unwrap();
+    let request = build_request(&options, Some(template));
+
+    assert_eq!(request.request_method, ::RequestMethod::Put as i32);
+    assert_eq!(request.url, "http://localhost/users");
+    assert_eq!(request.req_headers, "Accept: */*\nX-A:1\n");
+    assert_eq!(request.body_type, ::BodyType::Raw as i32);
+    assert_eq!(request.req_body, "{}");
+}
@@ -0,0 +1,540 @@
+use rusqlite::{Connection, Row};
+use gtk;
+use gtk::prelude::*;
+use gtk_ext;
+use config;
+use actions;
+use editor::RequestEditor;
+use http_file;
+
+pub const KIND_FOLDER: i32 = 0;
+pub const KIND_REQUEST: i32 = 1;
+
+const REQUEST_COLUMNS: &'static str = "id, folder_id, name, tags, request_method, custom_method, url, \
+    req_headers, body_type, req_body, body_file, assertions, pre_script, post_script, decompress";
+
+// A folder tree produced by the importers, not yet written to the database
+#[derive(Debug, Clone, Default)]
+pub struct ImportedFolder {
+    pub name: String,
+    pub folders: Vec<ImportedFolder>,
+    pub requests: Vec<SavedRequest>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Folder {
+    pub id: i64,
+    pub parent_id: Option<i64>,
+    pub name: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct SavedRequest {
+    pub id: Option<i64>,
+    pub folder_id: Option<i64>,
+    pub name: String,
+    pub tags: String,
+    pub request_method: i32,
+    pub custom_method: String,
+    pub url: String,
+    pub req_headers: String,
+    pub body_type: i32,
+    pub req_body: String,
+    pub body_file: String,
+    pub assertions: String,
+    pub pre_script: String,
+    pub post_script: String,
+    pub decompress: bool,
+}
+
+// Responses are decompressed unless a request says otherwise
+impl Default for SavedRequest {
+    fn default() -> Self {
+        SavedRequest {
+            id: None,
+            folder_id: None,
+            name: String::new(),
+            tags: String::new(),
+            request_method: 0,
+            custom_method: String::new(),
+            url: String::new(),
+            req_headers: String::new(),
+            body_type: 0,
+            req_body: String::new(),
+            body_file: String::new(),
+            assertions: String::new(),
+            pre_script: String::new(),
+            post_script: String::new(),
+            decompress: true,
+        }
+    }
+}
+
+#[allow(unused_must_use)]
+pub fn create_tables(connection: &Connection) {
+    connection.execute("CREATE TABLE folders (
+        id INTEGER PRIMARY KEY,
+        parent_id INTEGER,
+        name TEXT NOT NULL
+        )", &[]);
+
+    connection.execute("CREATE TABLE saved_requests (
+        id INTEGER PRIMARY KEY,
+        folder_id INTEGER,
+        name TEXT NOT NULL,
+        tags TEXT NOT NULL,
+        request_method INTEGER NOT NULL,
+        custom_method TEXT NOT NULL,
+        url TEXT NOT NULL,
+        req_headers TEXT NOT NULL,
+        body_type INTEGER NOT NULL,
+        req_body TEXT NOT NULL,
+        body_file TEXT NOT NULL,
+        assertions TEXT NOT NULL DEFAULT '',
+        pre_script TEXT NOT NULL DEFAULT '',
+        post_script TEXT NOT NULL DEFAULT '',
+        decompress INTEGER NOT NULL DEFAULT 1
+        )", &[]);
+
+    // Databases created before checks existed lack the column, elsewhere this fails harmlessly
+    connection.execute("ALTER TABLE saved_requests ADD COLUMN assertions TEXT NOT NULL DEFAULT ''", &[]);
+    connection.execute("ALTER TABLE saved_requests ADD COLUMN pre_script TEXT NOT NULL DEFAULT ''", &[]);
+    connection.execute("ALTER TABLE saved_requests ADD COLUMN post_script TEXT NOT NULL DEFAULT ''", &[]);
+    connection.execute("ALTER TABLE saved_requests ADD COLUMN decompress INTEGER NOT NULL DEFAULT 1", &[]);
+}
+
+impl SavedRequest {
+    pub fn tag_list(&self) -> Vec<String> {
+        self.tags.split(',').map(|x| x.trim().to_lowercase()).filter(|x| !x.is_empty()).collect()
+    }
+
+    pub fn matches(&self, filter: &str) -> bool {
+        let filter = filter.trim().to_lowercase();
+
+        filter.is_empty() ||
+            self.name.to_lowercase().contains(&filter) ||
+            self.tag_list().iter().any(|x| x.contains(&filter))
+    }
+
+    pub fn method_name(&self) -> String {
+        if self.request_method == ::RequestMethod::Custom as i32 {
+            return self.custom_method.clone();
+        }
+
+        actions::conv_to_http_method(&config::WindowState::conv_to_req_method(self.request_method), "").
+            map(|x| String::from(x.as_str())).
+            unwrap_or(String::new())
+    }
+
+    pub fn set_method_name(&mut self, name: &str) {
+        let name = name.trim();
+
+        let method = match name.to_uppercase().as_str() {
+            "GET" => ::RequestMethod::Get,
+            "POST" => ::RequestMethod::Post,
+            "PUT" => ::RequestMethod::Put,
+            "PATCH" => ::RequestMethod::Patch,
+            "DELETE" => ::RequestMethod::Delete,
+            "HEAD" => ::RequestMethod::Head,
+            "OPTIONS" => ::RequestMethod::Options,
+            "TRACE" => ::RequestMethod::Trace,
+            _ => ::RequestMethod::Custom,
+        };
+
+        self.custom_method = match method {
+            ::RequestMethod::Custom => String::from(name),
+            _ => String::new()
+        };
+        self.request_method = method as i32;
+    }
+
+    // Names, tags and folders are left out, they are not edited in the tab
+    pub fn same_content(&self, other: &SavedRequest) -> bool {
+        self.request_method == other.request_method &&
+            self.custom_method == other.custom_method &&
+            self.url == other.url &&
+            self.req_headers == other.req_headers &&
+            self.body_type == other.body_type &&
+            self.req_body == other.req_body &&
+            self.body_file == other.body_file &&
+            self.assertions == other.assertions &&
+            self.pre_script == other.pre_script &&
+            self.post_script == other.post_script &&
+            self.decompress == other.decompress
+    }
+
+    pub fn update_from_editor(&mut self, editor: &RequestEditor) {
+        self.request_method = editor.get_request_method() as i32;
+        self.custom_method = editor.get_custom_method();
+        self.url = editor.get_url();
+        self.req_headers = editor.get_req_headers();
+        self.body_type = editor.get_body_type() as i32;
+        self.req_body = editor.get_req_body();
+        self.body_file = editor.get_body_file();
+        self.assertions = editor.get_assertions();
+        self.pre_script = editor.get_pre_script();
+        self.post_script = editor.get_post_script();
+        self.decompress = editor.get_decompress();
+    }
+
+    pub fn update_to_editor(&self, editor: &RequestEditor) {
+        editor.set_request_method(config::WindowState::conv_to_req_method(self.request_method));
+        editor.set_custom_method(&self.custom_method);
+        editor.set_url(&self.url);
+        editor.set_req_headers(&self.req_headers);
+        editor.set_body_type(config::WindowState::conv_to_body_type(self.body_type));
+        editor.set_req_body(&self.req_body);
+        editor.set_body_file(&self.body_file);
+        editor.set_assertions(&self.assertions);
+        editor.set_pre_script(&self.pre_script);
+        editor.set_post_script(&self.post_script);
+        editor.set_decompress(self.decompress);
+    }
+}
+
+fn read_request(row: &Row) -> SavedRequest {
+    SavedRequest {
+        id: row.get(0),
+        folder_id: row.get(1),
+        name: row.get(2),
+        tags: row.get(3),
+        request_method: row.get(4),
+        custom_method: row.get(5),
+        url: row.get(6),
+        req_headers: row.get(7),
+        body_type: row.get(8),
+        req_body: row.get(9),
+        body_file: row.get(10),
+        assertions: row.get(11),
+        pre_script: row.get(12),
+        post_script: row.get(13),
+        decompress: row.get::<_, i32>(14) != 0,
+    }
+}
+
+pub fn list_folders(connection: &Connection) -> Vec<Folder> {
+    let mut stmt = connection.prepare("SELECT id, parent_id, name FROM folders ORDER BY name").unwrap();
+    let folder_iter = stmt.query_map(&[], |row| {
+        Folder {
+            id: row.get(0),
+            parent_id: row.get(1),
+            name: row.get(2),
+        }
+    }).unwrap();
+
+    folder_iter.filter_map(|x| x.ok()).collect()
+}
+
+pub fn list_requests(connection: &Connection) -> Vec<SavedRequest> {
+    let q = format!("SELECT {} FROM saved_requests ORDER BY name", REQUEST_COLUMNS);
+    let mut stmt = connection.prepare(&q).unwrap();
+    let request_iter = stmt.query_map(&[], read_request).unwrap();
+
+    request_iter.filter_map(|x| x.ok()).collect()
+}
+
+pub fn load_request(connection: &Connection, id: i64) -> Option<SavedRequest> {
+    let q = format!("SELECT {} FROM saved_requests WHERE id = ?1", REQUEST_COLUMNS);
+
+    connection.query_row(&q, &[&id], read_request).ok()
+}
+
+pub fn insert_folder(connection: &Connection, parent_id: Option<i64>, name: &str) -> Option<i64> {
+    connection.execute("INSERT INTO folders (parent_id, name) VALUES (?1, ?2)", &[&parent_id, &name]).
+        ok().
+        map(|_| connection.last_insert_rowid())
+}
+
+pub fn save_request(connection: &Connection, request: &SavedRequest) -> Option<i64> {
+    match request.id {
+        Some(id) => connection.execute("UPDATE saved_requests SET folder_id = ?1, name = ?2, tags = ?3,
+            request_method = ?4, custom_method = ?5, url = ?6, req_headers = ?7, body_type = ?8,
+            req_body = ?9, body_file = ?10, assertions = ?11, pre_script = ?12, post_script = ?13,
+            decompress = ?14 WHERE id = ?15", &[
+                &request.folder_id, &request.name, &request.tags, &request.request_method,
+                &request.custom_method, &request.url, &request.req_headers, &request.body_type,
+                &request.req_body, &request.body_file, &request.assertions, &request.pre_script,
+                &request.post_script, &(request.decompress as i32), &id
+            ]).ok().map(|_| id),
+        None => connection.execute("INSERT INTO saved_requests (folder_id, name, tags, request_method,
+            custom_method, url, req_headers, body_type, req_body, body_file, assertions, pre_script, post_script,
+            decompress) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)", &[
+                &request.folder_id, &request.name, &request.tags, &request.request_method,
+                &request.custom_method, &request.url, &request.req_headers, &request.body_type,
+                &request.req_body, &request.body_file, &request.assertions, &request.pre_script,
+                &request.post_script, &(request.decompress as i32)
+            ]).ok().map(|_| connection.last_insert_rowid())
+    }
+}
+
+// Returns the number of requests written
+pub fn store_imported(connection: &Connection, parent_id: Option<i64>, folder: &ImportedFolder) -> usize {
+    let folder_id = insert_folder(connection, parent_id, &folder.name);
+    let mut count = 0;
+
+    for request in &folder.requests {
+        let request = SavedRequest { id: None, folder_id, ..request.clone() };
+
+        if save_request(connection, &request).is_some() {
+            count += 1;
+        }
+    }
+
+    for child in &folder.folders {
+        count += store_imported(connection, folder_id, child);
+    }
+
+    count
+}
+
+#[allow(unused_must_use)]
+pub fn delete_request(connection: &Connection, id: i64) {
+    connection.execute("DELETE FROM saved_requests WHERE id = ?1", &[&id]);
+}
+
+fn collect_folder_ids(folders: &[Folder], id: i64, result: &mut Vec<i64>) {
+    result.push(id);
+
+    for folder in folders.iter().filter(|x| x.parent_id == Some(id)) {
+        collect_folder_ids(folders, folder.id, result);
+    }
+}
+
+#[allow(unused_must_use)]
+pub fn delete_folder(connection: &Connection, id: i64) {
+    let mut ids = Vec::new();
+    collect_folder_ids(&list_folders(connection), id, &mut ids);
+
+    for folder_id in ids {
+        connection.execute("DELETE FROM saved_requests WHERE folder_id = ?1", &[&folder_id]);
+        connection.execute("DELETE FROM folders WHERE id = ?1", &[&folder_id]);
+    }
+}
+
+fn add_tree_level(
+    store: &gtk::TreeStore,
+    parent_iter: Option<&gtk::TreeIter>,
+    parent_id: Option<i64>,
+    folders: &[Folder],
+    requests: &[SavedRequest],
+    keep_empty: bool) -> bool
+{
+    let mut added = false;
+
+    for folder in folders.iter().filter(|x| x.parent_id == parent_id) {
+        let iter = store.insert_with_values(parent_iter, None, &[0, 1, 2, 3],
+            &[&folder.name, &KIND_FOLDER, &folder.id, &String::new()]);
+
+        if add_tree_level(store, Some(&iter), Some(folder.id), folders, requests, keep_empty) || keep_empty {
+            added = true;
+        } else {
+            store.remove(&iter);
+        }
+    }
+
+    for request in requests.iter().filter(|x| x.folder_id == parent_id) {
+        store.insert_with_values(parent_iter, None, &[0, 1, 2, 3],
+            &[&request.name, &KIND_REQUEST, &request.id.unwrap_or(0), &request.tags]);
+        added = true;
+    }
+
+    added
+}
+
+pub fn refresh_tree(m_win: &::MainWindow) {
+    let connection = config::connect_to_state();
+    let filter = m_win.collections_search_inp.get_text().unwrap_or(String::new());
+    let requests: Vec<SavedRequest> = list_requests(&connection).into_iter().
+        filter(|x| x.matches(&filter)).
+        collect();
+
+    m_win.collections_store.clear();
+    add_tree_level(&m_win.collections_store, None, None, &list_folders(&connection), &requests, filter.trim().is_empty());
+
+    if !filter.trim().is_empty() {
+        m_win.collections_view.expand_all();
+    }
+}
+
+pub fn get_selected(view: &gtk::TreeView) -> Option<(i32, i64)> {
+    view.get_selection().get_selected().map(|(model, iter)| {
+        let kind = model.get_value(&iter, 1).get::<i32>().unwrap_or(KIND_FOLDER);
+        let id = model.get_value(&iter, 2).get::<i64>().unwrap_or(0);
+
+        (kind, id)
+    })
+}
+
+// New folders and requests go into the selected folder, or next to the selected request
+fn get_target_folder(m_win: &::MainWindow, connection: &Connection) -> Option<i64> {
+    match get_selected(&m_win.collections_view) {
+        Some((KIND_FOLDER, id)) => Some(id),
+        Some((_, id)) => load_request(connection, id).and_then(|x| x.folder_id),
+        None => None
+    }
+}
+
+fn get_current_request_id(m_win: &::MainWindow) -> Option<i64> {
+    m_win.editor().get_saved_request()
+}
+
+pub fn set_current_request_id(m_win: &::MainWindow, id: Option<i64>) {
+    m_win.editor().set_saved_request(id);
+}
+
+pub fn save_current_as(m_win: &::MainWindow) {
+    let connection = config::connect_to_state();
+    let current = get_current_request_id(m_win).and_then(|x| load_request(&connection, x));
+    let (name, tags) = current.as_ref().
+        map(|x| (x.name.clone(), x.tags.clone())).
+        unwrap_or((m_win.editor().get_url(), String::new()));
+
+    let values = gtk_ext::prompt_values(&m_win.window, "Save request",
+        &[("Name", name.as_str()), ("Tags", tags.as_str())]);
+
+    if let Some(values) = values {
+        if values[0].trim().is_empty() {
+            gtk_ext::show_message("Request name cannot be empty", &m_win.window);
+            return;
+        }
+
+        let mut request = SavedRequest {
+            folder_id: get_target_folder(m_win, &connection),
+            name: String::from(values[0].trim()),
+            tags: String::from(values[1].trim()),
+            ..Default::default()
+        };
+
+        request.update_from_editor(&m_win.editor());
+        set_current_request_id(m_win, save_request(&connection, &request));
+        refresh_tree(m_win);
+    }
+}
+
+pub fn save_current(m_win: &::MainWindow) {
+    let connection = config::connect_to_state();
+
+    match get_current_request_id(m_win).and_then(|x| load_request(&connection, x)) {
+        Some(mut request) => {
+            request.update_from_editor(&m_win.editor());
+            save_request(&connection, &request);
+        },
+        None => save_current_as(m_win)
+    };
+}
+
+// An unsaved request counts as edited once it has a URL
+pub(crate) fn has_unsaved_edits(m_win: &::MainWindow, connection: &Connection) -> bool {
+    let editor = m_win.editor();
+    let mut current = SavedRequest::default();
+    current.update_from_editor(&editor);
+
+    let stored = match (editor.get_saved_request(), editor.get_http_block()) {
+        (Some(id), _) => load_request(connection, id),
+        (None, Some(index)) => http_file::block_request(index),
+        (None, None) => None
+    };
+
+    match stored {
+        Some(stored) => !stored.same_content(&current),
+        None => !current.url.trim().is_empty()
+    }
+}
+
+pub fn load_selected(m_win: &::MainWindow) {
+    if let Some((KIND_REQUEST, id)) = get_selected(&m_win.collections_view) {
+        let connection = config::connect_to_state();
+
+        if has_unsaved_edits(m_win, &connection) &&
+            !gtk_ext::confirm("Discard the unsaved changes in this tab?", &m_win.window) {
+            return;
+        }
+
+        load_request(&connection, id).map(|request| {
+            request.update_to_editor(&m_win.editor());
+            set_current_request_id(m_win, request.id);
+        });
+    }
+}
+
+pub fn create_folder(m_win: &::MainWindow) {
+    let values = gtk_ext::prompt_values(&m_win.window, "New folder", &[("Name", "")]);
+
+    if let Some(values) = values {
+        if !values[0].trim().is_empty() {
+            let connection = config::connect_to_state();
+            insert_folder(&connection, get_target_folder(m_win, &connection), values[0].trim());
+            refresh_tree(m_win);
+        }
+    }
+}
+
+pub fn delete_selected(m_win: &::MainWindow) {
+    let selected = get_selected(&m_win.collections_view);
+
+    let question = match selected {
+        Some((KIND_FOLDER, _)) => "Delete the selected folder with everything inside it?",
+        Some(_) => "Delete the selected request?",
+        None => return
+    };
+
+    if !gtk_ext::confirm(question, &m_win.window) {
+        return;
+    }
+
+    let connection = config::connect_to_state();
+
+    match selected {
+        Some((KIND_FOLDER, id)) => delete_folder(&connection, id),
+        Some((_, id)) => delete_request(&connection, id),
+        None => ()
+    };
+
+    // Tabs that were showing a deleted request keep it as an unsaved one
+    for tab in m_win.tabs.borrow().items.iter() {
+        let deleted = tab.editor.get_saved_request().map(|x| load_request(&connection, x).is_none()).unwrap_or(false);
+
+        if deleted {
+            tab.editor.set_saved_request(None);
+        }
+    }
+
+    refresh_tree(m_win);
+}
+
+pub fn bind_to_window(m_win: &::MainWindow) {
+    let name_column = gtk::TreeViewColumn::new();
+    let name_cell = gtk::CellRendererText::new();
+    name_column.pack_start(&name_cell, true);
+    name_column.add_attribute(&name_cell, "text", 0);
+    m_win.collections_view.append_column(&name_column);
+
+    let tags_column = gtk::TreeViewColumn::new();
+    let tags_cell = gtk::CellRendererText::new();
+    tags_column.pack_start(&tags_cell, true);
+    tags_column.add_attribute(&tags_cell, "text", 3);
+    m_win.collections_view.append_column(&tags_column);
+
+    m_win.collections_view.set_model(Some(&m_win.collections_store));
+
+    m_win.collections_search_inp.connect_search_changed(gtk_clone!(m_win => move |_| {
+        refresh_tree(&m_win);
+    }));
+
+    // Selecting only picks the target folder, a request is opened by activating its row
+    m_win.collections_view.connect_row_activated(gtk_clone!(m_win => move |_, _, _| {
+        load_selected(&m_win);
+    }));
+
+    let save_btn: gtk::Button = gtk_ext::get_gtk_obj_by_id(&m_win.builder, "saveRequestBtn");
+    let save_as_btn: gtk::Button = gtk_ext::get_gtk_obj_by_id(&m_win.builder, "saveRequestAsBtn");
+    let new_folder_btn: gtk::Button = gtk_ext::get_gtk_obj_by_id(&m_win.builder, "newFolderBtn");
+    let delete_btn: gtk::Button = gtk_ext::get_gtk_obj_by_id(&m_win.builder, "deleteSavedBtn");
+
+    save_btn.connect_clicked(gtk_clone!(m_win => move |_| save_current(&m_win)));
+    save_as_btn.connect_clicked(gtk_clone!(m_win => move |_| save_current_as(&m_win)));
+    new_folder_btn.connect_clicked(gtk_clone!(m_win => move |_| create_folder(&m_win)));
+    delete_btn.connect_clicked(gtk_clone!(m_win => move |_| delete_selected(&m_win)));
+
+    refresh_tree(m_win);
+}
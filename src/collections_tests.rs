@@ -0,0 +1,32 @@
+use super::collections::SavedRequest;
+
+#[test]
+pub fn test_saved_request_matches() {
+    let request = SavedRequest {
+        name: String::from("Upload avatar"),
+        tags: String::from("users, Files,"),
+        ..Default::default()
+    };
+
+    assert_eq!(request.tag_list(), vec!["users", "files"]);
+    assert!(request.matches(""));
+    assert!(request.matches("avatar"));
+    assert!(request.matches("FILES"));
+    assert!(!request.matches("orders"));
+}
+
+#[test]
+pub fn test_saved_request_same_content() {
+    let stored = SavedRequest {
+        id: Some(3),
+        name: String::from("List users"),
+        url: String::from("https://api.example.com/users"),
+        ..Default::default()
+    };
+    let edited = SavedRequest { url: String::from("https://api.example.com/users"), ..Default::default() };
+
+    assert!(stored.decompress);
+    assert!(stored.same_content(&edited));
+    assert!(!stored.same_content(&SavedRequest { decompress: false, ..edited.clone() }));
+    assert!(!stored.same_content(&SavedRequest { req_body: String::from("{}"), ..edited }));
+}
@@ -4,7 +4,10 @@ use std;
 use rusqlite::Connection;
 use std::collections::HashMap;
 use gtk::{WidgetExt};
-use actions;
+use collections;
+use history;
+use tabs;
+use environments;
 
 fn get_state_path() -> PathBuf {
     let mut path: PathBuf = dirs::home_dir().expect("Cannot get user home directory location");
@@ -22,6 +25,11 @@ pub fn connect_to_state() -> Connection {
         option_value TEXT NOT NULL
         )", &[]);
 
+    collections::create_tables(&connection);
+    history::create_tables(&connection);
+    tabs::create_tables(&connection);
+    environments::create_tables(&connection);
+
     connection
 }
 
@@ -30,6 +38,7 @@ pub struct WindowState {
     pub top_left_offset: i32,
     pub top_right_offset: i32,
     pub vertical_offset: i32,
+    pub sidebar_offset: i32,
     pub window_height: i32,
     pub window_width: i32,
     pub req_headers: String,
@@ -40,6 +49,15 @@ pub struct WindowState {
     pub current_extension: Option<String>,
     pub current_mime: Option<String>,
     pub request_method: i32,
+    pub custom_method: String,
+    pub body_type: i32,
+    pub body_file: String,
+    pub decompress: bool,
+    pub current_saved_request: Option<i64>,
+    pub history_limit: i64,
+    pub history_days: i64,
+    pub active_tab: i32,
+    pub active_environment: Option<i64>,
 }
 
 #[derive(Debug, Clone)]
@@ -51,6 +69,7 @@ struct StateOption {
 pub const TOP_LEFT_OFFSET: &'static str = "top_left_offset";
 pub const TOP_RIGHT_OFFSET: &'static str = "top_right_offset";
 pub const VERTICAL_OFFSET: &'static str = "vertical_offset";
+pub const SIDEBAR_OFFSET: &'static str = "sidebar_offset";
 pub const WINDOW_HEIGHT: &'static str = "window_height";
 pub const WINDOW_WIDTH: &'static str = "window_width";
 pub const REQ_HEADERS: &'static str = "req_headers";
@@ -61,6 +80,15 @@ pub const CURRENT_URL: &'static str = "current_url";
 pub const CURRENT_EXTENSION: &'static str = "current_extension";
 pub const CURRENT_MIME: &'static str = "current_mime";
 pub const REQUEST_METHOD: &'static str = "request_method";
+pub const CUSTOM_METHOD: &'static str = "custom_method";
+pub const BODY_TYPE: &'static str = "body_type";
+pub const BODY_FILE: &'static str = "body_file";
+pub const DECOMPRESS: &'static str = "decompress";
+pub const CURRENT_SAVED_REQUEST: &'static str = "current_saved_request";
+pub const HISTORY_LIMIT: &'static str = "history_limit";
+pub const HISTORY_DAYS: &'static str = "history_days";
+pub const ACTIVE_TAB: &'static str = "active_tab";
+pub const ACTIVE_ENVIRONMENT: &'static str = "active_environment";
 
 impl WindowState {
     pub fn read_from_db(connection: &Connection) -> Self {
@@ -81,10 +109,13 @@ impl WindowState {
             };
         }
 
+        let request_method = WindowState::parse_option(&dict, REQUEST_METHOD, ::RequestMethod::Get as i32);
+
         WindowState {
             top_left_offset: WindowState::parse_option(&dict, TOP_LEFT_OFFSET, 200),
             top_right_offset: WindowState::parse_option(&dict, TOP_RIGHT_OFFSET, 200),
             vertical_offset: WindowState::parse_option(&dict, VERTICAL_OFFSET, 300),
+            sidebar_offset: WindowState::parse_option(&dict, SIDEBAR_OFFSET, 250),
             window_height: WindowState::parse_option(&dict, WINDOW_HEIGHT, 600),
             window_width: WindowState::parse_option(&dict, WINDOW_WIDTH, 1024),
             req_headers: WindowState::parse_str(&dict, REQ_HEADERS),
@@ -94,43 +125,71 @@ impl WindowState {
             current_url: WindowState::parse_str_or(&dict, CURRENT_URL, "https://api.github.com/users/kykc/repos"),
             current_extension: WindowState::parse_option_str(&dict, CURRENT_EXTENSION),
             current_mime: WindowState::parse_option_str(&dict, CURRENT_MIME),
-            request_method: WindowState::parse_option(&dict, REQUEST_METHOD, ::RequestMethod::GetWithUri as i32),
+            request_method,
+            custom_method: WindowState::parse_str(&dict, CUSTOM_METHOD),
+            body_type: WindowState::parse_option(&dict, BODY_TYPE, WindowState::legacy_body_type(request_method) as i32),
+            body_file: WindowState::parse_str(&dict, BODY_FILE),
+            decompress: WindowState::parse_option(&dict, DECOMPRESS, 1) != 0,
+            current_saved_request: WindowState::parse_option_str(&dict, CURRENT_SAVED_REQUEST).
+                and_then(|x| x.parse::<i64>().ok()),
+            history_limit: WindowState::parse_option(&dict, HISTORY_LIMIT, 500),
+            history_days: WindowState::parse_option(&dict, HISTORY_DAYS, 30),
+            active_tab: WindowState::parse_option(&dict, ACTIVE_TAB, 0),
+            active_environment: WindowState::parse_option_str(&dict, ACTIVE_ENVIRONMENT).
+                and_then(|x| x.parse::<i64>().ok()),
         }
     }
 
+    // The request fields are only read, to restore the request of databases written before tabs existed
     pub fn update_from_window(&mut self, m_win: &::MainWindow) {
-        self.top_left_offset = m_win.get_paned_top_left();
-        self.top_right_offset = m_win.get_paned_top_right();
-        self.vertical_offset = m_win.get_vertical_offset();
+        let editor = m_win.editor();
+
+        self.top_left_offset = editor.get_paned_top_left();
+        self.top_right_offset = editor.get_paned_top_right();
+        self.vertical_offset = editor.get_vertical_offset();
+        self.sidebar_offset = m_win.get_sidebar_offset();
         self.window_height = m_win.window.get_allocated_height();
         self.window_width = m_win.window.get_allocated_width();
-        self.req_headers = m_win.get_req_headers();
-        self.req_body = m_win.get_req_body();
-        self.rs_headers = m_win.get_rs_headers();
-        self.rs_body = m_win.get_rs_body();
-        self.current_url = m_win.get_url();
-        self.request_method = m_win.get_request_method() as i32;
     }
 
     pub fn update_to_window(&self, m_win: &::MainWindow) {
-        m_win.set_paned_top_left(self.top_left_offset);
-        m_win.set_paned_top_right(self.top_right_offset);
-        m_win.set_vertical_offset(self.vertical_offset);
+        m_win.set_sidebar_offset(self.sidebar_offset);
         m_win.set_window_size(self.window_width, self.window_height);
-        m_win.set_req_headers(&self.req_headers);
-        m_win.set_req_body(&self.req_body);
-        m_win.set_rs_headers(&self.rs_headers);
-        m_win.set_rs_body(&self.rs_body);
-        m_win.set_url(&self.current_url);
-        m_win.set_request_method(WindowState::conv_to_req_method(self.request_method));
-        actions::update_resp_body_highlighting(&m_win);
     }
 
     pub fn conv_to_req_method(i: i32) -> ::RequestMethod {
         match i {
-            2 => ::RequestMethod::PostWithForm,
-            3 => ::RequestMethod::PostRaw,
-            _ => ::RequestMethod::GetWithUri,
+            2 | 3 => ::RequestMethod::Post,
+            4 => ::RequestMethod::Put,
+            5 => ::RequestMethod::Patch,
+            6 => ::RequestMethod::Delete,
+            7 => ::RequestMethod::Head,
+            8 => ::RequestMethod::Options,
+            9 => ::RequestMethod::Trace,
+            10 => ::RequestMethod::Custom,
+            _ => ::RequestMethod::Get,
+        }
+    }
+
+    pub fn conv_to_body_type(i: i32) -> ::BodyType {
+        match i {
+            2 => ::BodyType::Raw,
+            3 => ::BodyType::Json,
+            4 => ::BodyType::Xml,
+            5 => ::BodyType::Form,
+            6 => ::BodyType::Binary,
+            7 => ::BodyType::Multipart,
+            _ => ::BodyType::Empty,
+        }
+    }
+
+    // Before body type got its own selector, it was implied by the method:
+    // 2 was POST with form data and 3 was POST with raw body
+    fn legacy_body_type(request_method: i32) -> ::BodyType {
+        match request_method {
+            2 => ::BodyType::Form,
+            3 => ::BodyType::Raw,
+            _ => ::BodyType::Empty,
         }
     }
 
@@ -142,6 +201,7 @@ impl WindowState {
         connection.execute(q, &[&TOP_LEFT_OFFSET, &self.top_left_offset]);
         connection.execute(q, &[&TOP_RIGHT_OFFSET, &self.top_right_offset]);
         connection.execute(q, &[&VERTICAL_OFFSET, &self.vertical_offset]);
+        connection.execute(q, &[&SIDEBAR_OFFSET, &self.sidebar_offset]);
         connection.execute(q, &[&WINDOW_HEIGHT, &self.window_height]);
         connection.execute(q, &[&WINDOW_WIDTH, &self.window_width]);
         connection.execute(q, &[&REQ_HEADERS, &self.req_headers.as_str()]);
@@ -152,6 +212,17 @@ impl WindowState {
         connection.execute(q, &[&CURRENT_EXTENSION, &WindowState::optional_string_to_db(&self.current_extension)]);
         connection.execute(q, &[&CURRENT_MIME, &WindowState::optional_string_to_db(&self.current_mime)]);
         connection.execute(q, &[&REQUEST_METHOD, &self.request_method]);
+        connection.execute(q, &[&CUSTOM_METHOD, &self.custom_method.as_str()]);
+        connection.execute(q, &[&BODY_TYPE, &self.body_type]);
+        connection.execute(q, &[&BODY_FILE, &self.body_file.as_str()]);
+        connection.execute(q, &[&DECOMPRESS, &(self.decompress as i32)]);
+        connection.execute(q, &[&CURRENT_SAVED_REQUEST,
+            &WindowState::optional_string_to_db(&self.current_saved_request.map(|x| x.to_string()))]);
+        connection.execute(q, &[&HISTORY_LIMIT, &self.history_limit]);
+        connection.execute(q, &[&HISTORY_DAYS, &self.history_days]);
+        connection.execute(q, &[&ACTIVE_TAB, &self.active_tab]);
+        connection.execute(q, &[&ACTIVE_ENVIRONMENT,
+            &WindowState::optional_string_to_db(&self.active_environment.map(|x| x.to_string()))]);
     }
 
     fn optional_string_to_db(opt: &Option<String>) -> String {
@@ -0,0 +1,492 @@
+use std::char;
+use serde_json;
+use serde_json::Value;
+use base64;
+use gdk;
+use glib::ObjectExt;
+use gtk;
+use gtk::prelude::*;
+use gtk_ext;
+use actions;
+use collections::{self, SavedRequest};
+use editor::RequestEditor;
+
+#[derive(Debug, Clone, Default)]
+pub struct ParsedCommand {
+    pub request: SavedRequest,
+    pub ignored: Vec<String>,
+}
+
+const SHORT_VALUE_OPTIONS: &'static str = "XHdFuAebTomxwEKrcCzY";
+
+const IGNORED_FLAGS: [&'static str; 19] = [
+    "-s", "--silent", "-S", "--show-error", "-L", "--location", "-k", "--insecure", "-v", "--verbose",
+    "-i", "--include", "--compressed", "-g", "--globoff", "-f", "--fail", "-N", "--no-buffer",
+];
+
+const IGNORED_VALUE_OPTIONS: [&'static str; 10] = [
+    "-o", "--output", "-m", "--max-time", "--connect-timeout", "--retry", "-w", "--write-out",
+    "-r", "--range",
+];
+
+fn read_hex(chars: &mut ::std::iter::Peekable<::std::str::Chars>, max_len: usize) -> Option<char> {
+    let mut digits = String::new();
+
+    while digits.len() < max_len && chars.peek().map(|x| x.is_digit(16)).unwrap_or(false) {
+        digits.push(chars.next().unwrap());
+    }
+
+    u32::from_str_radix(&digits, 16).ok().and_then(char::from_u32)
+}
+
+// Splits a POSIX shell command line, honouring quotes, $'...' strings and line continuations
+pub fn split_shell_words(text: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            ' ' | '\t' | '\r' | '\n' => {
+                if in_word {
+                    words.push(word.clone());
+                    word.clear();
+                    in_word = false;
+                }
+            },
+            '\\' => {
+                match chars.next() {
+                    Some('\n') => (),
+                    Some('\r') => { chars.next(); },
+                    Some(x) => { word.push(x); in_word = true; },
+                    None => ()
+                };
+            },
+            '\'' => {
+                in_word = true;
+
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(x) => word.push(x),
+                        None => return Err(String::from("Unterminated single quote"))
+                    };
+                }
+            },
+            '"' => {
+                in_word = true;
+
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => {
+                            match chars.next() {
+                                Some('\n') => (),
+                                Some(x) if "$`\"\\".contains(x) => word.push(x),
+                                Some(x) => { word.push('\\'); word.push(x); },
+                                None => return Err(String::from("Unterminated double quote"))
+                            };
+                        },
+                        Some(x) => word.push(x),
+                        None => return Err(String::from("Unterminated double quote"))
+                    };
+                }
+            },
+            '$' if chars.peek() == Some(&'\'') => {
+                chars.next();
+                in_word = true;
+
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some('\\') => {
+                            let escaped = match chars.next() {
+                                Some('n') => Some('\n'),
+                                Some('t') => Some('\t'),
+                                Some('r') => Some('\r'),
+                                Some('x') => read_hex(&mut chars, 2),
+                                Some('u') => read_hex(&mut chars, 4),
+                                Some('U') => read_hex(&mut chars, 8),
+                                Some(x) => Some(x),
+                                None => return Err(String::from("Unterminated $'...' string"))
+                            };
+
+                            escaped.map(|x| word.push(x));
+                        },
+                        Some(x) => word.push(x),
+                        None => return Err(String::from("Unterminated $'...' string"))
+                    };
+                }
+            },
+            x => {
+                word.push(x);
+                in_word = true;
+            }
+        };
+    }
+
+    if in_word {
+        words.push(word);
+    }
+
+    Ok(words)
+}
+
+pub fn percent_encode(text: &str) -> String {
+    let mut result = String::new();
+
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => result.push(byte as char),
+            _ => result += &format!("%{:02X}", byte)
+        };
+    }
+
+    result
+}
+
+// --data-urlencode accepts "content", "=content" and "name=content"
+fn urlencode_data(value: &str) -> String {
+    match value.find('=') {
+        Some(0) => percent_encode(&value[1..]),
+        Some(x) => format!("{}={}", &value[..x], percent_encode(&value[x + 1..])),
+        None => percent_encode(value)
+    }
+}
+
+fn find_header<'a>(headers: &'a [String], name: &str) -> Option<&'a str> {
+    headers.iter().
+        filter_map(|x| x.find(':').map(|pos| (x[..pos].trim(), x[pos + 1..].trim()))).
+        find(|x| x.0.eq_ignore_ascii_case(name)).
+        map(|x| x.1)
+}
+
+// Splits "-XPOST" into "-X" and "POST", other words are returned as is
+fn split_option(word: &str) -> (String, Option<String>) {
+    let mut chars = word.chars();
+
+    if word.len() > 2 && !word.starts_with("--") && chars.next() == Some('-') {
+        let flag = chars.next().unwrap();
+
+        if SHORT_VALUE_OPTIONS.contains(flag) {
+            return (format!("-{}", flag), Some(String::from(&word[2..])));
+        }
+    }
+
+    (String::from(word), None)
+}
+
+// Bundled boolean flags like -sSL are split into separate ones
+fn expand_words(words: &[String]) -> Vec<String> {
+    let mut result = Vec::new();
+
+    for word in words {
+        let is_bundle = word.len() > 2 && word.starts_with('-') && !word.starts_with("--") &&
+            word.chars().skip(1).all(|x| x.is_ascii_alphabetic()) &&
+            !SHORT_VALUE_OPTIONS.contains(word.chars().nth(1).unwrap());
+
+        if is_bundle {
+            let chars = word.chars().skip(1).collect::<Vec<_>>();
+
+            for (i, flag) in chars.iter().enumerate() {
+                if SHORT_VALUE_OPTIONS.contains(*flag) {
+                    result.push(format!("-{}", chars[i..].iter().collect::<String>()));
+                    break;
+                }
+
+                result.push(format!("-{}", flag));
+            }
+        } else {
+            result.push(word.clone());
+        }
+    }
+
+    result
+}
+
+pub fn parse_curl(text: &str) -> Result<ParsedCommand, String> {
+    let words = expand_words(&split_shell_words(text)?);
+    let mut iter = words.iter();
+
+    match iter.next() {
+        Some(x) if x == "curl" || x.ends_with("/curl") || x.ends_with("curl.exe") => (),
+        _ => return Err(String::from("Not a curl command"))
+    };
+
+    let mut method: Option<String> = None;
+    let mut url: Option<String> = None;
+    let mut headers: Vec<String> = Vec::new();
+    let mut data: Vec<String> = Vec::new();
+    let mut forms: Vec<String> = Vec::new();
+    let mut body_file: Option<String> = None;
+    let mut upload = false;
+    let mut head = false;
+    let mut get = false;
+    let mut ignored: Vec<String> = Vec::new();
+
+    while let Some(word) = iter.next() {
+        if !word.starts_with('-') || word == "-" {
+            match url {
+                None => url = Some(word.clone()),
+                Some(_) => ignored.push(word.clone())
+            };
+            continue;
+        }
+
+        let (name, attached) = split_option(word);
+
+        if IGNORED_FLAGS.contains(&name.as_str()) {
+            continue;
+        }
+
+        let takes_value = name.starts_with("--data") || IGNORED_VALUE_OPTIONS.contains(&name.as_str()) ||
+            ["--request", "--header", "--form", "--form-string", "--user", "--user-agent", "--referer",
+            "--cookie", "--url", "--upload-file", "--proxy"].contains(&name.as_str()) ||
+            (name.len() == 2 && SHORT_VALUE_OPTIONS.contains(&name[1..]));
+
+        if !takes_value {
+            match name.as_str() {
+                "-I" | "--head" => head = true,
+                "-G" | "--get" => get = true,
+                _ => ignored.push(name)
+            };
+            continue;
+        }
+
+        let value = match attached.or_else(|| iter.next().cloned()) {
+            Some(x) => x,
+            None => return Err(format!("Missing value for {}", name))
+        };
+
+        match name.as_str() {
+            "-X" | "--request" => method = Some(value),
+            "-H" | "--header" => headers.push(value),
+            "-d" | "--data" | "--data-ascii" | "--data-binary" if value.starts_with('@') => {
+                body_file = Some(String::from(&value[1..]));
+            },
+            "-d" | "--data" | "--data-ascii" | "--data-binary" | "--data-raw" => data.push(value),
+            "--data-urlencode" => data.push(urlencode_data(&value)),
+            "-F" | "--form" | "--form-string" => forms.push(value),
+            "-u" | "--user" => headers.push(format!("Authorization: Basic {}", base64::encode(value.as_bytes()))),
+            "-A" | "--user-agent" => headers.push(format!("User-Agent: {}", value)),
+            "-e" | "--referer" => headers.push(format!("Referer: {}", value)),
+            "-b" | "--cookie" if value.contains('=') => headers.push(format!("Cookie: {}", value)),
+            "--url" => url = Some(value),
+            "-T" | "--upload-file" => {
+                body_file = Some(value);
+                upload = true;
+            },
+            x if IGNORED_VALUE_OPTIONS.contains(&x) => (),
+            _ => ignored.push(format!("{} {}", name, value))
+        };
+    }
+
+    let mut url = url.ok_or(String::from("No URL found in the curl command"))?;
+
+    if get && !data.is_empty() {
+        url += if url.contains('?') { "&" } else { "?" };
+        url += &data.join("&");
+        data.clear();
+    }
+
+    let has_body = !data.is_empty() || !forms.is_empty() || body_file.is_some();
+    let default_method = if head {
+        "HEAD"
+    } else if upload {
+        "PUT"
+    } else if has_body {
+        "POST"
+    } else {
+        "GET"
+    };
+
+    let mut request = SavedRequest::default();
+    request.set_method_name(&method.unwrap_or(String::from(default_method)));
+    request.url = url;
+
+    let content_type = find_header(&headers, "Content-Type").map(String::from);
+
+    let body_type = if !forms.is_empty() {
+        request.req_body = forms.join("\n");
+        ::BodyType::Multipart
+    } else if let Some(file) = body_file {
+        request.body_file = file;
+        ::BodyType::Binary
+    } else if !data.is_empty() {
+        request.req_body = data.join("&");
+
+        match content_type {
+            Some(ref x) => actions::raw_body_type(x),
+            None => {
+                // curl sends -d data as a form unless told otherwise
+                headers.push(String::from("Content-Type: application/x-www-form-urlencoded"));
+                ::BodyType::Raw
+            }
+        }
+    } else {
+        ::BodyType::Empty
+    };
+
+    request.body_type = body_type as i32;
+    request.req_headers = headers.iter().map(|x| format!("{}\n", x)).collect();
+
+    Ok(ParsedCommand { request, ignored })
+}
+
+// Reads a JavaScript string literal and returns it with the rest of the text
+fn read_js_string(text: &str) -> Result<(String, &str), String> {
+    let mut chars = text.char_indices().peekable();
+
+    let quote = match chars.next() {
+        Some((_, x)) if x == '"' || x == '\'' || x == '`' => x,
+        _ => return Err(String::from("Expected a string literal"))
+    };
+
+    let mut result = String::new();
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            x if x == quote => return Ok((result, &text[i + 1..])),
+            '\\' => {
+                match chars.next().map(|x| x.1) {
+                    Some('n') => result.push('\n'),
+                    Some('t') => result.push('\t'),
+                    Some('r') => result.push('\r'),
+                    Some('u') => {
+                        let mut digits = String::new();
+
+                        while digits.len() < 4 && chars.peek().map(|x| x.1.is_digit(16)).unwrap_or(false) {
+                            digits.push(chars.next().unwrap().1);
+                        }
+
+                        u32::from_str_radix(&digits, 16).ok().and_then(char::from_u32).map(|x| result.push(x));
+                    },
+                    Some(x) => result.push(x),
+                    None => ()
+                };
+            },
+            x => result.push(x)
+        };
+    }
+
+    Err(String::from("Unterminated string literal"))
+}
+
+fn json_to_string(value: &Value) -> String {
+    match *value {
+        Value::String(ref x) => x.clone(),
+        ref x => x.to_string()
+    }
+}
+
+// Accepts browser devtools "Copy as fetch" and "Copy as fetch (Node.js)" snippets
+pub fn parse_fetch(text: &str) -> Result<ParsedCommand, String> {
+    let start = text.find("fetch(").ok_or(String::from("Not a fetch call"))? + "fetch(".len();
+    let (url, rest) = read_js_string(text[start..].trim_start())?;
+    let rest = rest.trim_start();
+
+    let options = if rest.starts_with(',') {
+        serde_json::Deserializer::from_str(&rest[1..]).into_iter::<Value>().next().
+            unwrap_or(Ok(Value::Null)).
+            map_err(|err| format!("Cannot parse fetch options - {}", err))?
+    } else {
+        Value::Null
+    };
+
+    let mut request = SavedRequest::default();
+    let mut headers: Vec<String> = Vec::new();
+    let mut ignored: Vec<String> = Vec::new();
+    let mut method = String::from("GET");
+    request.url = url;
+
+    if let Value::Object(ref map) = options {
+        for (key, value) in map {
+            match (key.as_str(), value) {
+                ("method", &Value::String(ref x)) => method = x.clone(),
+                ("headers", &Value::Object(ref items)) => {
+                    for (name, value) in items {
+                        headers.push(format!("{}: {}", name, json_to_string(value)));
+                    }
+                },
+                ("headers", &Value::Array(ref items)) => {
+                    for item in items {
+                        if let Some(pair) = item.as_array().filter(|x| x.len() == 2) {
+                            headers.push(format!("{}: {}", json_to_string(&pair[0]), json_to_string(&pair[1])));
+                        }
+                    }
+                },
+                ("body", &Value::String(ref x)) => request.req_body = x.clone(),
+                ("body", &Value::Null) => (),
+                ("referrer", &Value::String(ref x)) => headers.push(format!("Referer: {}", x)),
+                // Browser only settings that have no meaning outside of a page
+                ("mode", _) | ("credentials", _) | ("referrerPolicy", _) | ("cache", _) |
+                ("redirect", _) | ("integrity", _) | ("keepalive", _) => (),
+                (x, _) => ignored.push(String::from(x))
+            };
+        }
+    }
+
+    request.set_method_name(&method);
+
+    let body_type = if request.req_body.is_empty() {
+        ::BodyType::Empty
+    } else {
+        find_header(&headers, "Content-Type").map(actions::raw_body_type).unwrap_or(::BodyType::Raw)
+    };
+
+    request.body_type = body_type as i32;
+    request.req_headers = headers.iter().map(|x| format!("{}\n", x)).collect();
+
+    Ok(ParsedCommand { request, ignored })
+}
+
+pub fn looks_like_command(text: &str) -> bool {
+    let text = text.trim_start();
+
+    text.starts_with("curl ") || text.starts_with("fetch(") || text.starts_with("await fetch(")
+}
+
+pub fn parse_command(text: &str) -> Result<ParsedCommand, String> {
+    if text.trim_start().starts_with("curl") {
+        parse_curl(text)
+    } else {
+        parse_fetch(text)
+    }
+}
+
+pub fn import_command(m_win: &::MainWindow, text: &str) {
+    match parse_command(text) {
+        Ok(parsed) => {
+            parsed.request.update_to_editor(&m_win.editor());
+            collections::set_current_request_id(m_win, None);
+
+            if !parsed.ignored.is_empty() {
+                gtk_ext::show_message(&format!("Imported, but these options were ignored: {}",
+                    parsed.ignored.join(", ")), &m_win.window);
+            }
+        },
+        Err(err) => gtk_ext::show_message(&err, &m_win.window)
+    };
+}
+
+fn clipboard_text() -> Option<String> {
+    gtk::Clipboard::get(&gdk::SELECTION_CLIPBOARD).wait_for_text()
+}
+
+pub fn paste_command(m_win: &::MainWindow) {
+    match clipboard_text() {
+        Some(text) => import_command(m_win, &text),
+        None => gtk_ext::show_message("Clipboard does not contain text", &m_win.window)
+    };
+}
+
+pub fn bind_to_editor(m_win: &::MainWindow, editor: &RequestEditor) {
+    // Commands pasted into the URL field are imported instead of being pasted as text
+    editor.url_inp.connect_paste_clipboard(gtk_clone!(m_win => move |entry| {
+        if let Some(text) = clipboard_text().filter(|x| looks_like_command(x)) {
+            entry.stop_signal_emission("paste-clipboard");
+            import_command(&m_win, &text);
+        }
+    }));
+}
@@ -0,0 +1,74 @@
+use super::curl::{split_shell_words, parse_curl, parse_fetch, looks_like_command};
+
+#[test]
+pub fn test_split_shell_words() {
+    let words = split_shell_words("curl 'a b' \"c \\\"d\\\" \\$e\" f\\ g \\\n  $'h\\ni\\x41' \"\\w\"").unwrap();
+
+    assert_eq!(words, vec!["curl", "a b", "c \"d\" $e", "f g", "h\niA", "\\w"]);
+    assert!(split_shell_words("curl 'unterminated").is_err());
+}
+
+#[test]
+pub fn test_parse_curl_post() {
+    let text = include_str!("test_data/curl1");
+    let parsed = parse_curl(text).unwrap();
+
+    assert_eq!(parsed.request.request_method, ::RequestMethod::Post as i32);
+    assert_eq!(parsed.request.url, "https://api.example.com/v1/users?page=2");
+    assert_eq!(parsed.request.body_type, ::BodyType::Json as i32);
+    assert_eq!(parsed.request.req_body, "{\"name\": \"O'Brien\"}");
+    assert_eq!(parsed.request.req_headers,
+        "Content-Type: application/json\nAccept: */*\nAuthorization: Basic dXNlcjpzZWNyZXQ=\nUser-Agent: tests/1.0\n");
+    assert_eq!(parsed.ignored, vec!["--proxy http://localhost:3128"]);
+}
+
+#[test]
+pub fn test_parse_curl_forms() {
+    let parsed = parse_curl("curl -sSL -XPUT -d a=1 --data-urlencode 'q=x y&z' http://localhost/").unwrap();
+
+    assert_eq!(parsed.request.request_method, ::RequestMethod::Put as i32);
+    assert_eq!(parsed.request.body_type, ::BodyType::Raw as i32);
+    assert_eq!(parsed.request.req_body, "a=1&q=x%20y%26z");
+    assert_eq!(parsed.request.req_headers, "Content-Type: application/x-www-form-urlencoded\n");
+    assert!(parsed.ignored.is_empty());
+
+    let parsed = parse_curl("curl -F 'file=@photo.png;type=image/png' -F name=x https://example.com").unwrap();
+
+    assert_eq!(parsed.request.request_method, ::RequestMethod::Post as i32);
+    assert_eq!(parsed.request.body_type, ::BodyType::Multipart as i32);
+    assert_eq!(parsed.request.req_body, "file=@photo.png;type=image/png\nname=x");
+
+    let parsed = parse_curl("curl -G -d q=1 -X PROPFIND https://example.com/search").unwrap();
+
+    assert_eq!(parsed.request.request_method, ::RequestMethod::Custom as i32);
+    assert_eq!(parsed.request.custom_method, "PROPFIND");
+    assert_eq!(parsed.request.url, "https://example.com/search?q=1");
+    assert_eq!(parsed.request.body_type, ::BodyType::Empty as i32);
+
+    let parsed = parse_curl("curl -T report.pdf https://example.com/upload").unwrap();
+
+    assert_eq!(parsed.request.request_method, ::RequestMethod::Put as i32);
+    assert_eq!(parsed.request.body_type, ::BodyType::Binary as i32);
+    assert_eq!(parsed.request.body_file, "report.pdf");
+
+    assert!(parse_curl("curl -s").is_err());
+    assert!(parse_curl("wget https://example.com").is_err());
+}
+
+#[test]
+pub fn test_parse_fetch() {
+    let text = include_str!("test_data/fetch1");
+    let parsed = parse_fetch(text).unwrap();
+
+    assert_eq!(parsed.request.request_method, ::RequestMethod::Post as i32);
+    assert_eq!(parsed.request.url, "https://example.com/api/items?id=\"7\"");
+    assert_eq!(parsed.request.body_type, ::BodyType::Json as i32);
+    assert_eq!(parsed.request.req_body, "{\"title\":\"milk\"}");
+    assert_eq!(parsed.request.req_headers,
+        "accept: application/json\ncontent-type: application/json\nReferer: https://example.com/\n");
+    assert_eq!(parsed.ignored, vec!["signal"]);
+
+    assert!(looks_like_command(text));
+    assert!(looks_like_command("  curl https://example.com"));
+    assert!(!looks_like_command("https://example.com/curl"));
+}
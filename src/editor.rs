@@ -0,0 +1,447 @@
+use gtk;
+use gtk::prelude::*;
+use gtk::{Builder, Button, Entry, ComboBoxText};
+use sourceview;
+use sourceview::{BufferExt, LanguageManagerExt};
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use gtk_ext;
+use gtk_ext::{TextWidget};
+use actions;
+use config;
+use environments;
+use ::{Request, Response, RequestMethod, BodyType};
+
+// Everything a tab keeps next to its widgets
+#[derive(Default)]
+pub struct EditorState {
+    // Where the request was loaded from, at most one is set and saving writes back there
+    pub saved_request: Option<i64>,
+    pub http_block: Option<usize>,
+    pub current_extension: Option<String>,
+    pub current_mime: Option<String>,
+    pub response: Option<Response>,
+    pub unfiltered_body: Option<String>,
+    pub running: Option<Arc<AtomicBool>>,
+}
+
+// The request editors and response panes of one tab
+#[derive(Clone)]
+pub struct RequestEditor {
+    pub builder: Builder,
+    pub page: gtk::Box,
+    pub perform_btn: Button,
+    pub cancel_btn: Button,
+    pub url_inp: Entry,
+    pub resp_mtx: sourceview::View,
+    pub headers_mtx: sourceview::View,
+    pub method_sel: ComboBoxText,
+    pub custom_method_inp: Entry,
+    pub body_type_sel: ComboBoxText,
+    pub decompress_chk: gtk::CheckButton,
+    pub body_file_btn: gtk::FileChooserButton,
+    pub req_mtx: sourceview::View,
+    pub resp_headers_mtx: sourceview::View,
+    pub lang_manager: sourceview::LanguageManager,
+    pub status_bar: gtk::Box,
+    pub status_lbl: gtk::Label,
+    pub timings_area: gtk::DrawingArea,
+    pub resp_stack: gtk::Stack,
+    pub resp_stack_switcher: gtk::StackSwitcher,
+    pub resp_img: gtk::Image,
+    pub resp_img_info: gtk::Label,
+    pub assertions_mtx: sourceview::View,
+    pub assertions_lbl: gtk::Label,
+    pub pre_script_mtx: sourceview::View,
+    pub post_script_mtx: sourceview::View,
+    pub resp_filter_inp: gtk::SearchEntry,
+    pub resp_filter_lbl: gtk::Label,
+    pub resp_original_btn: Button,
+    pub search_bar: gtk::SearchBar,
+    pub state: Rc<RefCell<EditorState>>,
+}
+
+impl RequestEditor {
+    pub fn new(lang_manager: &sourceview::LanguageManager, scheme: Option<&sourceview::StyleScheme>) -> RequestEditor {
+        let builder = Builder::new_from_string(include_str!("request_page.glade"));
+
+        let page: gtk::Box = gtk_ext::get_gtk_obj_by_id(&builder, "requestPage");
+        let perform_btn: Button = gtk_ext::get_gtk_obj_by_id(&builder, "performBtn");
+        let cancel_btn: Button = gtk_ext::get_gtk_obj_by_id(&builder, "cancelBtn");
+        let url_inp: Entry = gtk_ext::get_gtk_obj_by_id(&builder, "urlInp");
+        let resp_mtx: sourceview::View = gtk_ext::get_gtk_obj_by_id(&builder, "respMtx");
+        let req_mtx: sourceview::View = gtk_ext::get_gtk_obj_by_id(&builder, "reqMtx");
+        let headers_mtx: sourceview::View = gtk_ext::get_gtk_obj_by_id(&builder, "headersMtx");
+        let resp_headers_mtx: sourceview::View = gtk_ext::get_gtk_obj_by_id(&builder, "respHeadersMtx");
+        let method_sel: ComboBoxText = gtk_ext::get_gtk_obj_by_id(&builder, "methodSel");
+        let custom_method_inp: Entry = gtk_ext::get_gtk_obj_by_id(&builder, "customMethodInp");
+        let body_type_sel: ComboBoxText = gtk_ext::get_gtk_obj_by_id(&builder, "bodyTypeSel");
+        let body_file_btn: gtk::FileChooserButton = gtk_ext::get_gtk_obj_by_id(&builder, "bodyFileBtn");
+        let decompress_chk: gtk::CheckButton = gtk_ext::get_gtk_obj_by_id(&builder, "decompressChk");
+        let search_bar: gtk::SearchBar = gtk_ext::get_gtk_obj_by_id(&builder, "searchBar");
+        let search_inp: gtk::SearchEntry = gtk_ext::get_gtk_obj_by_id(&builder, "searchInp");
+        let status_bar: gtk::Box = gtk_ext::get_gtk_obj_by_id(&builder, "statusBar");
+        let status_lbl: gtk::Label = gtk_ext::get_gtk_obj_by_id(&builder, "statusLbl");
+        let timings_area: gtk::DrawingArea = gtk_ext::get_gtk_obj_by_id(&builder, "timingsArea");
+        let resp_stack: gtk::Stack = gtk_ext::get_gtk_obj_by_id(&builder, "respStack");
+        let resp_stack_switcher: gtk::StackSwitcher = gtk_ext::get_gtk_obj_by_id(&builder, "respStackSwitcher");
+        let resp_img: gtk::Image = gtk_ext::get_gtk_obj_by_id(&builder, "respImg");
+        let resp_img_info: gtk::Label = gtk_ext::get_gtk_obj_by_id(&builder, "respImgInfo");
+        let assertions_mtx: sourceview::View = gtk_ext::get_gtk_obj_by_id(&builder, "assertionsMtx");
+        let assertions_lbl: gtk::Label = gtk_ext::get_gtk_obj_by_id(&builder, "assertionsLbl");
+        let pre_script_mtx: sourceview::View = gtk_ext::get_gtk_obj_by_id(&builder, "preScriptMtx");
+        let post_script_mtx: sourceview::View = gtk_ext::get_gtk_obj_by_id(&builder, "postScriptMtx");
+        let resp_filter_inp: gtk::SearchEntry = gtk_ext::get_gtk_obj_by_id(&builder, "respFilterInp");
+        let resp_filter_lbl: gtk::Label = gtk_ext::get_gtk_obj_by_id(&builder, "respFilterLbl");
+        let resp_original_btn: Button = gtk_ext::get_gtk_obj_by_id(&builder, "respOriginalBtn");
+
+        ::MainWindow::apply_css(page.upcast_ref());
+
+        search_inp.connect_activate(move |search_inp| {
+            search_inp.emit_next_match();
+        });
+
+        search_inp.connect_next_match(gtk_clone!(resp_mtx => move |search_inp| {
+            let pattern = search_inp.upcast_ref::<gtk::Entry>().get_all_text();
+            let buffer = resp_mtx.get_buffer().unwrap();
+            let cursor = buffer.get_insert().unwrap();
+            let mut cursor_iter = buffer.get_iter_at_mark(&cursor);
+
+            if cursor_iter == buffer.get_end_iter() {
+                buffer.place_cursor(&buffer.get_start_iter());
+                cursor_iter = buffer.get_iter_at_mark(&cursor);
+            }
+
+            let found = cursor_iter.forward_search(&pattern, gtk::TextSearchFlags::CASE_INSENSITIVE, None);
+
+            match found {
+                Some(pair) => {
+                    buffer.select_range(&pair.1, &pair.0);
+                    resp_mtx.scroll_mark_onscreen(&cursor);
+                },
+                None => buffer.place_cursor(&buffer.get_end_iter())
+            };
+        }));
+
+        scheme.map(|theme| {
+            gtk_ext::apply_to_src_buf(&resp_mtx, &|x| x.set_style_scheme(theme));
+            gtk_ext::apply_to_src_buf(&headers_mtx, &|x| x.set_style_scheme(theme));
+            gtk_ext::apply_to_src_buf(&req_mtx, &|x| x.set_style_scheme(theme));
+            gtk_ext::apply_to_src_buf(&resp_headers_mtx, &|x| x.set_style_scheme(theme));
+            gtk_ext::apply_to_src_buf(&assertions_mtx, &|x| x.set_style_scheme(theme));
+            gtk_ext::apply_to_src_buf(&pre_script_mtx, &|x| x.set_style_scheme(theme));
+            gtk_ext::apply_to_src_buf(&post_script_mtx, &|x| x.set_style_scheme(theme));
+        });
+
+        lang_manager.
+            guess_language(Some("headers.ini"), None).
+            map(|lang| {
+                gtk_ext::apply_to_src_buf(&headers_mtx, &|x| x.set_language(&lang));
+                gtk_ext::apply_to_src_buf(&resp_headers_mtx, &|x| x.set_language(&lang));
+            });
+
+        // Rhai has no language file, JavaScript highlighting is close enough
+        lang_manager.
+            guess_language(Some("script.js"), None).
+            map(|lang| {
+                gtk_ext::apply_to_src_buf(&pre_script_mtx, &|x| x.set_language(&lang));
+                gtk_ext::apply_to_src_buf(&post_script_mtx, &|x| x.set_language(&lang));
+            });
+
+        let result = RequestEditor {
+            builder,
+            page,
+            perform_btn,
+            cancel_btn,
+            url_inp,
+            resp_mtx,
+            headers_mtx,
+            method_sel,
+            custom_method_inp,
+            body_type_sel,
+            decompress_chk,
+            body_file_btn,
+            req_mtx,
+            resp_headers_mtx,
+            lang_manager: lang_manager.clone(),
+            status_bar,
+            status_lbl,
+            timings_area,
+            resp_stack,
+            resp_stack_switcher,
+            resp_img,
+            resp_img_info,
+            assertions_mtx,
+            assertions_lbl,
+            pre_script_mtx,
+            post_script_mtx,
+            resp_filter_inp,
+            resp_filter_lbl,
+            resp_original_btn,
+            search_bar,
+            state: Rc::new(RefCell::new(EditorState::default())),
+        };
+
+        result.timings_area.connect_draw(gtk_clone!(result => move |area, cr| {
+            actions::draw_timings(result.state.borrow().response.as_ref(), area, cr)
+        }));
+
+        // Each tab has its own Perform button, so Enter cannot rely on the window default
+        result.url_inp.connect_activate(gtk_clone!(result => move |_| {
+            if result.perform_btn.is_sensitive() {
+                result.perform_btn.clicked();
+            }
+        }));
+
+        result.method_sel.connect_changed(gtk_clone!(result => move |_| {
+            result.update_custom_method_visibility();
+        }));
+
+        result.body_type_sel.connect_changed(gtk_clone!(result => move |_| {
+            result.update_body_file_visibility();
+        }));
+
+        result.resp_filter_inp.connect_activate(gtk_clone!(result => move |_| {
+            actions::apply_response_filter(&result);
+        }));
+
+        result.resp_filter_inp.connect_search_changed(gtk_clone!(result => move |x| {
+            if x.upcast_ref::<gtk::Entry>().get_all_text().trim().is_empty() {
+                actions::show_original_body(&result);
+            }
+        }));
+
+        result.resp_original_btn.connect_clicked(gtk_clone!(result => move |_| {
+            result.resp_filter_inp.upcast_ref::<gtk::Entry>().clear_all_text();
+            actions::show_original_body(&result);
+        }));
+
+        result.set_request_running(false);
+        result.update_custom_method_visibility();
+        result.update_body_file_visibility();
+        result
+    }
+
+    pub fn get_request_method(&self) -> RequestMethod {
+        config::WindowState::conv_to_req_method(RequestEditor::get_sel_int_id(&self.method_sel, 1))
+    }
+
+    fn get_sel_int_id(sel: &gtk::ComboBoxText, def: i32) -> i32 {
+        sel.get_active_id().unwrap_or("".to_owned()).parse::<i32>().unwrap_or(def)
+    }
+
+    pub fn set_request_method(&self, method: RequestMethod) {
+        self.method_sel.set_active_id((method as i32).to_string().as_str());
+    }
+
+    pub fn get_custom_method(&self) -> String {
+        self.custom_method_inp.get_all_text()
+    }
+
+    pub fn set_custom_method(&self, x: &str) {
+        self.custom_method_inp.replace_all_text(x);
+    }
+
+    fn update_custom_method_visibility(&self) {
+        match self.get_request_method() {
+            RequestMethod::Custom => self.custom_method_inp.show(),
+            _ => self.custom_method_inp.hide(),
+        };
+    }
+
+    pub fn get_body_type(&self) -> BodyType {
+        config::WindowState::conv_to_body_type(RequestEditor::get_sel_int_id(&self.body_type_sel, 1))
+    }
+
+    pub fn set_body_type(&self, body_type: BodyType) {
+        self.body_type_sel.set_active_id((body_type as i32).to_string().as_str());
+    }
+
+    pub fn get_body_file(&self) -> String {
+        self.body_file_btn.get_filename().
+            and_then(|x| x.to_str().map(|y| String::from(y))).
+            unwrap_or(String::new())
+    }
+
+    pub fn set_body_file(&self, x: &str) {
+        if x.is_empty() {
+            self.body_file_btn.unselect_all();
+        } else {
+            self.body_file_btn.set_filename(x);
+        }
+    }
+
+    fn update_body_file_visibility(&self) {
+        match self.get_body_type() {
+            BodyType::Binary => self.body_file_btn.show(),
+            _ => self.body_file_btn.hide(),
+        };
+    }
+
+    pub fn get_decompress(&self) -> bool {
+        self.decompress_chk.get_active()
+    }
+
+    pub fn set_decompress(&self, x: bool) {
+        self.decompress_chk.set_active(x);
+    }
+
+    // Placeholders are substituted from the given variables, unknown ones fail the request
+    pub fn get_request(&self, variables: &[(String, String)], window: &gtk::ApplicationWindow) -> Result<Request, String> {
+        let mut unresolved = Vec::new();
+        let url = environments::substitute(&self.get_url(), variables, &mut unresolved);
+        let headers = environments::substitute(&self.get_req_headers(), variables, &mut unresolved);
+        let body = environments::substitute(&self.get_req_body(), variables, &mut unresolved);
+        let body_file = environments::substitute(&self.get_body_file(), variables, &mut unresolved);
+
+        if !unresolved.is_empty() {
+            return Err(environments::unresolved_message(&unresolved));
+        }
+
+        Ok(Request {
+            method: self.get_request_method(),
+            custom_method: self.get_custom_method(),
+            url,
+            headers: actions::parse_headers(&headers,
+                &mut |x| gtk_ext::show_message(x, window)),
+            body_type: self.get_body_type(),
+            body,
+            body_file,
+            decompress: self.get_decompress(),
+        })
+    }
+
+    fn get_paned(&self, id: &str) -> gtk::Paned {
+        gtk_ext::get_gtk_obj_by_id(&self.builder, id)
+    }
+
+    pub fn set_vertical_offset(&self, x: i32) {
+        gtk::PanedExt::set_position(&self.get_paned("panedParent"), x);
+    }
+
+    pub fn get_vertical_offset(&self) -> i32 {
+        gtk::PanedExt::get_position(&self.get_paned("panedParent"))
+    }
+
+    pub fn set_paned_top_left(&self, x: i32) {
+        gtk::PanedExt::set_position(&self.get_paned("panedTopLeft"), x);
+    }
+
+    pub fn get_paned_top_left(&self) -> i32 {
+        gtk::PanedExt::get_position(&self.get_paned("panedTopLeft"))
+    }
+
+    pub fn set_paned_top_right(&self, x: i32) {
+        gtk::PanedExt::set_position(&self.get_paned("panedTopRight"), x);
+    }
+
+    pub fn get_paned_top_right(&self) -> i32 {
+        gtk::PanedExt::get_position(&self.get_paned("panedTopRight"))
+    }
+
+    pub fn get_req_headers(&self) -> String {
+        self.headers_mtx.get_all_text()
+    }
+
+    pub fn get_req_body(&self) -> String {
+        self.req_mtx.get_all_text()
+    }
+
+    pub fn get_assertions(&self) -> String {
+        self.assertions_mtx.get_all_text()
+    }
+
+    pub fn get_pre_script(&self) -> String {
+        self.pre_script_mtx.get_all_text()
+    }
+
+    pub fn get_post_script(&self) -> String {
+        self.post_script_mtx.get_all_text()
+    }
+
+    pub fn get_rs_headers(&self) -> String {
+        self.resp_headers_mtx.get_all_text()
+    }
+
+    // While a filter is applied the whole body is the one kept aside
+    pub fn get_rs_body(&self) -> String {
+        self.state.borrow().unfiltered_body.clone().unwrap_or_else(|| self.resp_mtx.get_all_text())
+    }
+
+    pub fn set_req_headers(&self, x: &str) {
+        self.headers_mtx.replace_all_text(x);
+    }
+
+    pub fn set_req_body(&self, x: &str) {
+        self.req_mtx.replace_all_text(x);
+    }
+
+    pub fn set_assertions(&self, x: &str) {
+        self.assertions_mtx.replace_all_text(x);
+    }
+
+    pub fn set_pre_script(&self, x: &str) {
+        self.pre_script_mtx.replace_all_text(x);
+    }
+
+    pub fn set_post_script(&self, x: &str) {
+        self.post_script_mtx.replace_all_text(x);
+    }
+
+    pub fn set_rs_headers(&self, x: &str) {
+        self.resp_headers_mtx.replace_all_text(x);
+    }
+
+    pub fn set_rs_body(&self, x: &str) {
+        actions::replace_response_body(self, x);
+    }
+
+    pub fn get_url(&self) -> String {
+        self.url_inp.get_all_text()
+    }
+
+    pub fn set_url(&self, x: &str) {
+        self.url_inp.replace_all_text(x);
+    }
+
+    pub fn get_saved_request(&self) -> Option<i64> {
+        self.state.borrow().saved_request
+    }
+
+    pub fn set_saved_request(&self, id: Option<i64>) {
+        let mut state = self.state.borrow_mut();
+        state.saved_request = id;
+        state.http_block = None;
+    }
+
+    pub fn get_http_block(&self) -> Option<usize> {
+        self.state.borrow().http_block
+    }
+
+    pub fn set_http_block(&self, index: Option<usize>) {
+        let mut state = self.state.borrow_mut();
+        state.http_block = index;
+        state.saved_request = None;
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.state.borrow().running.is_some()
+    }
+
+    pub fn set_request_running(&self, running: bool) {
+        self.perform_btn.set_sensitive(!running);
+        self.cancel_btn.set_sensitive(running);
+    }
+
+    // The worker stops reading the body at its next chunk, but a request still waiting
+    // for its headers runs in its thread until they arrive or it times out
+    pub fn cancel(&self) {
+        if let Some(cancelled) = self.state.borrow_mut().running.take() {
+            cancelled.store(true, Ordering::SeqCst);
+        }
+
+        self.set_request_running(false);
+    }
+}
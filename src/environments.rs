@@ -0,0 +1,244 @@
+use rusqlite::{Connection, Row};
+use gtk;
+use gtk::prelude::*;
+use gtk_ext;
+use gtk_ext::{TextWidget};
+use config;
+
+const NO_ENVIRONMENT_ID: &'static str = "0";
+
+#[derive(Debug, Clone, Default)]
+pub struct Environment {
+    pub id: Option<i64>,
+    pub name: String,
+    pub variables: String,
+}
+
+#[allow(unused_must_use)]
+pub fn create_tables(connection: &Connection) {
+    connection.execute("CREATE TABLE environments (
+        id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL,
+        variables TEXT NOT NULL
+        )", &[]);
+}
+
+pub fn parse_variables(text: &str) -> Vec<(String, String)> {
+    text.lines().
+        map(|x| x.trim()).
+        filter(|x| !x.is_empty() && !x.starts_with('#')).
+        filter_map(|x| x.find('=').map(|pos| (x[..pos].trim(), x[pos + 1..].trim()))).
+        filter(|x| !x.0.is_empty()).
+        map(|(name, value)| (String::from(name), String::from(value))).
+        collect()
+}
+
+// Replaces {{name}} placeholders, later definitions of a variable win.
+// Names without a value are left as is and added to unresolved.
+// A literal {{ is written as \{{ and loses the backslash.
+pub fn substitute(text: &str, variables: &[(String, String)], unresolved: &mut Vec<String>) -> String {
+    let mut result = String::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find("{{") {
+        if rest[..start].ends_with('\\') {
+            result += &rest[..start - 1];
+            result += "{{";
+            rest = &rest[start + 2..];
+            continue;
+        }
+
+        let end = match rest[start + 2..].find("}}") {
+            Some(x) => start + 2 + x,
+            None => break
+        };
+
+        let name = rest[start + 2..end].trim();
+        result += &rest[..start];
+
+        match variables.iter().rev().find(|x| x.0 == name) {
+            Some(variable) => result += &variable.1,
+            None => {
+                if !unresolved.iter().any(|x| x == name) {
+                    unresolved.push(String::from(name));
+                }
+
+                result += &rest[start..end + 2];
+            }
+        };
+
+        rest = &rest[end + 2..];
+    }
+
+    result + rest
+}
+
+pub fn unresolved_message(unresolved: &[String]) -> String {
+    let names = unresolved.iter().map(|x| format!("{{{{{}}}}}", x)).collect::<Vec<_>>().join(", ");
+
+    format!("Unresolved variables: {}", names)
+}
+
+fn read_environment(row: &Row) -> Environment {
+    Environment {
+        id: row.get(0),
+        name: row.get(1),
+        variables: row.get(2),
+    }
+}
+
+pub fn list_environments(connection: &Connection) -> Vec<Environment> {
+    let mut stmt = connection.prepare("SELECT id, name, variables FROM environments ORDER BY name").unwrap();
+    let environment_iter = stmt.query_map(&[], read_environment).unwrap();
+
+    environment_iter.filter_map(|x| x.ok()).collect()
+}
+
+pub fn load_environment(connection: &Connection, id: i64) -> Option<Environment> {
+    connection.query_row("SELECT id, name, variables FROM environments WHERE id = ?1", &[&id], read_environment).ok()
+}
+
+pub fn save_environment(connection: &Connection, environment: &Environment) -> Option<i64> {
+    match environment.id {
+        Some(id) => connection.execute("UPDATE environments SET name = ?1, variables = ?2 WHERE id = ?3",
+            &[&environment.name, &environment.variables, &id]).ok().map(|_| id),
+        None => connection.execute("INSERT INTO environments (name, variables) VALUES (?1, ?2)",
+            &[&environment.name, &environment.variables]).ok().map(|_| connection.last_insert_rowid())
+    }
+}
+
+#[allow(unused_must_use)]
+pub fn delete_environment(connection: &Connection, id: i64) {
+    connection.execute("DELETE FROM environments WHERE id = ?1", &[&id]);
+}
+
+fn get_active_id(m_win: &::MainWindow) -> Option<i64> {
+    m_win.config.borrow().active_environment
+}
+
+fn set_active_id(m_win: &::MainWindow, id: Option<i64>) {
+    m_win.config.borrow_mut().active_environment = id;
+}
+
+pub fn active_variables(active: Option<i64>) -> Vec<(String, String)> {
+    active.
+        and_then(|x| load_environment(&config::connect_to_state(), x)).
+        map(|x| parse_variables(&x.variables)).
+        unwrap_or(Vec::new())
+}
+
+fn get_selected(m_win: &::MainWindow) -> Option<i64> {
+    m_win.environments_view.get_selection().get_selected().
+        and_then(|(model, iter)| model.get_value(&iter, 1).get::<i64>())
+}
+
+pub fn refresh(m_win: &::MainWindow) {
+    let environments = list_environments(&config::connect_to_state());
+    let active = get_active_id(m_win).filter(|x| environments.iter().any(|y| y.id == Some(*x)));
+
+    m_win.environments_store.clear();
+    m_win.environment_sel.remove_all();
+    m_win.environment_sel.append(NO_ENVIRONMENT_ID, "No environment");
+
+    for environment in environments {
+        let id = environment.id.unwrap_or(0);
+        m_win.environments_store.insert_with_values(None, &[0, 1], &[&environment.name, &id]);
+        m_win.environment_sel.append(id.to_string().as_str(), &environment.name);
+    }
+
+    set_active_id(m_win, active);
+    m_win.environment_sel.set_active_id(active.map(|x| x.to_string()).unwrap_or(String::from(NO_ENVIRONMENT_ID)).as_str());
+}
+
+fn load_selected(m_win: &::MainWindow) {
+    let variables = get_selected(m_win).
+        and_then(|x| load_environment(&config::connect_to_state(), x)).
+        map(|x| x.variables).
+        unwrap_or(String::new());
+
+    m_win.environment_vars_mtx.replace_all_text(&variables);
+}
+
+fn save_selected(m_win: &::MainWindow) {
+    let connection = config::connect_to_state();
+
+    match get_selected(m_win).and_then(|x| load_environment(&connection, x)) {
+        Some(mut environment) => {
+            environment.variables = m_win.environment_vars_mtx.get_all_text();
+            save_environment(&connection, &environment);
+        },
+        None => gtk_ext::show_message("Select an environment to save its variables", &m_win.window)
+    };
+}
+
+fn create(m_win: &::MainWindow) {
+    let values = gtk_ext::prompt_values(&m_win.window, "New environment", &[("Name", "")]);
+
+    if let Some(name) = values.as_ref().map(|x| x[0].trim()).filter(|x| !x.is_empty()) {
+        let environment = Environment {
+            id: None,
+            name: String::from(name),
+            variables: m_win.environment_vars_mtx.get_all_text(),
+        };
+
+        save_environment(&config::connect_to_state(), &environment);
+        refresh(m_win);
+    }
+}
+
+fn rename_selected(m_win: &::MainWindow) {
+    let connection = config::connect_to_state();
+
+    if let Some(mut environment) = get_selected(m_win).and_then(|x| load_environment(&connection, x)) {
+        let values = gtk_ext::prompt_values(&m_win.window, "Rename environment", &[("Name", &environment.name)]);
+
+        if let Some(name) = values.as_ref().map(|x| x[0].trim()).filter(|x| !x.is_empty()) {
+            environment.name = String::from(name);
+            save_environment(&connection, &environment);
+            refresh(m_win);
+        }
+    }
+}
+
+fn delete_selected(m_win: &::MainWindow) {
+    let connection = config::connect_to_state();
+
+    if let Some(environment) = get_selected(m_win).and_then(|x| load_environment(&connection, x)) {
+        if gtk_ext::confirm(&format!("Delete environment \"{}\"?", environment.name), &m_win.window) {
+            delete_environment(&connection, environment.id.unwrap_or(0));
+            refresh(m_win);
+        }
+    }
+}
+
+pub fn bind_to_window(m_win: &::MainWindow) {
+    let column = gtk::TreeViewColumn::new();
+    let cell = gtk::CellRendererText::new();
+    column.pack_start(&cell, true);
+    column.add_attribute(&cell, "text", 0);
+    m_win.environments_view.append_column(&column);
+    m_win.environments_view.set_model(Some(&m_win.environments_store));
+
+    m_win.environments_view.get_selection().connect_changed(gtk_clone!(m_win => move |_| {
+        load_selected(&m_win);
+    }));
+
+    // Clearing the switcher on refresh leaves it without an active item, that is not a choice
+    m_win.environment_sel.connect_changed(gtk_clone!(m_win => move |sel| {
+        if let Some(id) = sel.get_active_id() {
+            set_active_id(&m_win, id.parse::<i64>().ok().filter(|x| *x != 0));
+        }
+    }));
+
+    let save_btn: gtk::Button = gtk_ext::get_gtk_obj_by_id(&m_win.builder, "saveEnvironmentBtn");
+    let new_btn: gtk::Button = gtk_ext::get_gtk_obj_by_id(&m_win.builder, "newEnvironmentBtn");
+    let rename_btn: gtk::Button = gtk_ext::get_gtk_obj_by_id(&m_win.builder, "renameEnvironmentBtn");
+    let delete_btn: gtk::Button = gtk_ext::get_gtk_obj_by_id(&m_win.builder, "deleteEnvironmentBtn");
+
+    save_btn.connect_clicked(gtk_clone!(m_win => move |_| save_selected(&m_win)));
+    new_btn.connect_clicked(gtk_clone!(m_win => move |_| create(&m_win)));
+    rename_btn.connect_clicked(gtk_clone!(m_win => move |_| rename_selected(&m_win)));
+    delete_btn.connect_clicked(gtk_clone!(m_win => move |_| delete_selected(&m_win)));
+
+    refresh(m_win);
+}
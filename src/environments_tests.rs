@@ -0,0 +1,32 @@
+use super::environments::{parse_variables, substitute, unresolved_message};
+
+fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+    pairs.iter().map(|x| (String::from(x.0), String::from(x.1))).collect()
+}
+
+#[test]
+pub fn test_parse_variables() {
+    let text = "# staging\nhost = staging.example.com\n\ntoken=abc=def\nbroken line\n=nameless\n";
+
+    assert_eq!(parse_variables(text), vars(&[("host", "staging.example.com"), ("token", "abc=def")]));
+}
+
+#[test]
+pub fn test_substitute() {
+    let variables = vars(&[("host", "old.example.com"), ("token", "abc"), ("host", "api.example.com")]);
+    let mut unresolved = Vec::new();
+
+    assert_eq!(substitute("https://{{host}}/users?t={{ token }}", &variables, &mut unresolved),
+        "https://api.example.com/users?t=abc");
+    assert!(unresolved.is_empty());
+
+    assert_eq!(substitute("{{user}}:{{password}} {{user}} {{unclosed", &variables, &mut unresolved),
+        "{{user}}:{{password}} {{user}} {{unclosed");
+    assert_eq!(unresolved, vec!["user", "password"]);
+    assert_eq!(unresolved_message(&unresolved), "Unresolved variables: {{user}}, {{password}}");
+
+    let mut unresolved = Vec::new();
+    assert_eq!(substitute(r"\{{user}} {{host}} \{{ {{token}}", &variables, &mut unresolved),
+        "{{user}} api.example.com {{ abc");
+    assert!(unresolved.is_empty());
+}
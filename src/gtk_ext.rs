@@ -1,4 +1,6 @@
-use gtk::{TextView, Entry, TextViewExt, EntryExt, TextBufferExt, DialogExt, WidgetExt, ContainerExt, Cast};
+use gtk::{TextView, Entry, TextViewExt, EntryExt, TextBufferExt, DialogExt, WidgetExt, ContainerExt, Cast, FileChooserExt,
+    GridExt, LabelExt};
+use std::path::PathBuf;
 use gtk;
 use sourceview;
 
@@ -104,6 +106,97 @@ pub fn show_message<T: gtk::prelude::IsA<gtk::Window>>(msg: &str, window: &T) {
     dialog.run();
 }
 
+pub fn confirm<T: gtk::prelude::IsA<gtk::Window>>(msg: &str, window: &T) -> bool {
+    let dialog = gtk::MessageDialog::new(
+        Some(window),
+        gtk::DialogFlags::MODAL,
+        gtk::MessageType::Question,
+        gtk::ButtonsType::YesNo, msg
+    );
+
+    let result = dialog.run() == gtk::ResponseType::Yes.into();
+    dialog.destroy();
+
+    result
+}
+
+pub fn prompt_values<T: gtk::prelude::IsA<gtk::Window>>(window: &T, title: &str, fields: &[(&str, &str)]) -> Option<Vec<String>> {
+    let dialog = gtk::Dialog::new_with_buttons(
+        Some(title),
+        Some(window),
+        gtk::DialogFlags::MODAL,
+        &[("_Cancel", gtk::ResponseType::Cancel), ("_OK", gtk::ResponseType::Ok)]
+    );
+
+    dialog.set_default_response(gtk::ResponseType::Ok.into());
+
+    let grid = gtk::Grid::new();
+    grid.set_row_spacing(6);
+    grid.set_column_spacing(6);
+    grid.set_border_width(8);
+
+    let entries: Vec<Entry> = fields.iter().enumerate().map(|(i, field)| {
+        let label = gtk::Label::new(Some(field.0));
+        let entry = Entry::new();
+
+        label.set_xalign(0.0);
+        entry.set_text(field.1);
+        entry.set_activates_default(true);
+        entry.set_hexpand(true);
+
+        grid.attach(&label, 0, i as i32, 1, 1);
+        grid.attach(&entry, 1, i as i32, 1, 1);
+
+        entry
+    }).collect();
+
+    dialog.get_content_area().add(&grid);
+    dialog.show_all();
+
+    let result = if dialog.run() == gtk::ResponseType::Ok.into() {
+        Some(entries.iter().map(|x| x.get_all_text()).collect())
+    } else {
+        None
+    };
+
+    dialog.destroy();
+
+    result
+}
+
+pub fn choose_file_to_save<T: gtk::prelude::IsA<gtk::Window>>(window: &T, title: &str, suggested_name: &str) -> Option<PathBuf> {
+    let dialog = gtk::FileChooserDialog::with_buttons(
+        Some(title),
+        Some(window),
+        gtk::FileChooserAction::Save,
+        &[("_Cancel", gtk::ResponseType::Cancel), ("_Save", gtk::ResponseType::Accept)]
+    );
+
+    dialog.set_do_overwrite_confirmation(true);
+    dialog.set_current_name(suggested_name);
+
+    let result = if dialog.run() == gtk::ResponseType::Accept.into() { dialog.get_filename() } else { None };
+    dialog.destroy();
+
+    result
+}
+
+pub fn choose_files_to_open<T: gtk::prelude::IsA<gtk::Window>>(window: &T, title: &str) -> Vec<PathBuf> {
+    let dialog = gtk::FileChooserDialog::with_buttons(
+        Some(title),
+        Some(window),
+        gtk::FileChooserAction::Open,
+        &[("_Cancel", gtk::ResponseType::Cancel), ("_Open", gtk::ResponseType::Accept)]
+    );
+
+    dialog.set_select_multiple(true);
+
+    let result = if dialog.run() == gtk::ResponseType::Accept.into() { dialog.get_filenames() } else { Vec::new() };
+    dialog.destroy();
+
+    result
+}
+
 pub fn traverse_gtk_container(container: &gtk::Container, worker: &Fn(&gtk::Container)) {
     worker(&container);
     
@@ -0,0 +1,421 @@
+use serde_json;
+use serde_json::Value;
+use base64;
+use std::fs;
+use std::path::Path;
+use reqwest;
+use gtk_ext;
+use config;
+use actions;
+use curl;
+use json_ext::{str_field, json_object};
+use timing::{self, Timings};
+use history::{self, HistoryEntry, ms_to_duration};
+use collections::{self, ImportedFolder, SavedRequest};
+
+#[derive(Debug, Clone, Default)]
+pub struct HarImport {
+    pub entries: Vec<HistoryEntry>,
+    pub warnings: Vec<String>,
+}
+
+// HAR uses -1 for phases that did not happen, missing ones are treated the same
+fn num_field(value: &Value, key: &str) -> f64 {
+    value.get(key).and_then(|x| x.as_f64()).unwrap_or(-1.0)
+}
+
+fn to_iso_timestamp(timestamp: i64) -> String {
+    history::format_timestamp(timestamp).replace(' ', "T") + ".000Z"
+}
+
+fn header_list(text: &str) -> Value {
+    let headers = actions::parse_headers(text, &mut |_| {});
+
+    Value::Array(headers.iter().
+        map(|(name, value)| json_object(vec![
+            ("name", Value::from(name.as_str())),
+            ("value", Value::from(value.to_str().unwrap_or("").trim())),
+        ])).
+        collect())
+}
+
+fn header_value(text: &str, name: &str) -> Option<String> {
+    actions::parse_headers(text, &mut |_| {}).get_all(name).iter().
+        filter_map(|x| x.to_str().ok()).
+        map(|x| String::from(x.trim())).
+        next()
+}
+
+fn export_post_data(request: &SavedRequest) -> Option<Value> {
+    let body_type = config::WindowState::conv_to_body_type(request.body_type);
+    let mime_type = header_value(&request.req_headers, "content-type").
+        or(actions::body_type_content_type(&body_type).map(String::from)).
+        unwrap_or(String::new());
+
+    let (text, params) = match body_type {
+        ::BodyType::Empty => return None,
+        ::BodyType::Binary => {
+            return Some(json_object(vec![
+                ("mimeType", Value::from(mime_type)),
+                ("text", Value::from("")),
+                ("_bodyFile", Value::from(request.body_file.as_str())),
+            ]));
+        },
+        ::BodyType::Form => {
+            let fields = actions::create_post_req_data(&request.req_body);
+            let text = fields.iter().
+                map(|x| format!("{}={}", curl::percent_encode(x.0), curl::percent_encode(x.1))).
+                collect::<Vec<_>>().
+                join("&");
+            let params = fields.iter().
+                map(|x| json_object(vec![("name", Value::from(x.0)), ("value", Value::from(x.1))])).
+                collect();
+
+            (text, Some(params))
+        },
+        ::BodyType::Multipart => {
+            let fields = actions::create_multipart_fields(&request.req_body).unwrap_or(Vec::new());
+            let params = fields.into_iter().map(|field| {
+                let mut param = vec![("name", Value::from(field.name))];
+
+                match field.file_path {
+                    Some(path) => {
+                        let file_name = field.file_name.unwrap_or_else(|| {
+                            Path::new(&path).file_name().map(|x| x.to_string_lossy().into_owned()).unwrap_or(String::new())
+                        });
+
+                        param.push(("fileName", Value::from(file_name)));
+                        param.push(("_filePath", Value::from(path)));
+                    },
+                    None => param.push(("value", Value::from(field.value)))
+                };
+
+                if let Some(mime_type) = field.mime_type {
+                    param.push(("contentType", Value::from(mime_type)));
+                }
+
+                json_object(param)
+            }).collect();
+
+            (String::new(), Some(params))
+        },
+        _ => (request.req_body.clone(), None)
+    };
+
+    let mut post_data = vec![("mimeType", Value::from(mime_type)), ("text", Value::from(text))];
+
+    if let Some(params) = params {
+        post_data.push(("params", Value::Array(params)));
+    }
+
+    Some(json_object(post_data))
+}
+
+fn setup_ms(timings: &Timings, ms: f64) -> Value {
+    if timings.has_setup() { Value::from(ms) } else { Value::from(-1) }
+}
+
+// reqwest encodes forms the way a URL query is encoded
+fn encoded_form_size(fields: &[(&str, &str)]) -> i64 {
+    let mut url = reqwest::Url::parse("http://localhost/").unwrap();
+    url.query_pairs_mut().extend_pairs(fields);
+
+    url.query().map(|x| x.len() as i64).unwrap_or(0)
+}
+
+// Multipart boundaries are only picked when sending, so that size is unknown
+fn body_size(request: &SavedRequest) -> i64 {
+    match config::WindowState::conv_to_body_type(request.body_type) {
+        ::BodyType::Empty => 0,
+        ::BodyType::Binary => fs::metadata(&request.body_file).map(|x| x.len() as i64).unwrap_or(-1),
+        ::BodyType::Form => encoded_form_size(&actions::create_post_req_data(&request.req_body)),
+        ::BodyType::Multipart => -1,
+        _ => request.req_body.len() as i64
+    }
+}
+
+fn export_entry(entry: &HistoryEntry) -> Value {
+    let request = entry.sent_request();
+    let query = reqwest::Url::parse(&request.url).ok().map(|url| {
+        url.query_pairs().
+            map(|(name, value)| json_object(vec![("name", Value::from(&*name)), ("value", Value::from(&*value))])).
+            collect::<Vec<_>>()
+    }).unwrap_or(Vec::new());
+
+    let mut har_request = vec![
+        ("method", Value::from(request.method_name())),
+        ("url", Value::from(request.url.as_str())),
+        ("httpVersion", Value::from(entry.version.as_str())),
+        ("cookies", Value::Array(Vec::new())),
+        ("headers", header_list(&request.req_headers)),
+        ("queryString", Value::Array(query)),
+        ("headersSize", Value::from(-1)),
+        ("bodySize", Value::from(body_size(request))),
+    ];
+
+    if let Some(post_data) = export_post_data(request) {
+        har_request.push(("postData", post_data));
+    }
+
+    let mut content = vec![
+        ("size", Value::from(entry.rs_body.len() as i64)),
+        ("mimeType", Value::from(header_value(&entry.rs_headers, "content-type").unwrap_or(String::new()))),
+    ];
+
+    match String::from_utf8(entry.rs_body.clone()) {
+        Ok(text) => content.push(("text", Value::from(text))),
+        Err(_) => {
+            content.push(("text", Value::from(base64::encode(&entry.rs_body))));
+            content.push(("encoding", Value::from("base64")));
+        }
+    };
+
+    let mut har_response = vec![
+        ("status", Value::from(entry.status)),
+        ("statusText", Value::from(entry.reason.as_str())),
+        ("httpVersion", Value::from(entry.version.as_str())),
+        ("cookies", Value::Array(Vec::new())),
+        ("headers", header_list(&entry.rs_headers)),
+        ("content", json_object(content)),
+        ("redirectURL", Value::from(header_value(&entry.rs_headers, "location").unwrap_or(String::new()))),
+        ("headersSize", Value::from(-1)),
+        ("bodySize", Value::from(entry.wire_size)),
+        ("_finalUrl", Value::from(entry.final_url.as_str())),
+    ];
+
+    // Browsers mark requests that got no response the same way
+    if !entry.error.is_empty() {
+        har_response.push(("_error", Value::from(entry.error.as_str())));
+    }
+
+    let timings = &entry.timings;
+
+    json_object(vec![
+        ("startedDateTime", Value::from(to_iso_timestamp(entry.created_at))),
+        ("time", Value::from(timings.total_ms())),
+        ("request", json_object(har_request)),
+        ("response", json_object(har_response)),
+        ("cache", json_object(Vec::new())),
+        // HAR counts TLS as part of connect and uses -1 for phases that were not measured
+        ("timings", json_object(vec![
+            ("dns", setup_ms(timings, timing::duration_to_ms(timings.dns))),
+            ("connect", setup_ms(timings, timing::duration_to_ms(timings.connect) + timing::duration_to_ms(timings.tls))),
+            ("ssl", setup_ms(timings, timing::duration_to_ms(timings.tls))),
+            ("send", Value::from(0)),
+            ("wait", Value::from(timing::duration_to_ms(timings.waiting))),
+            ("receive", Value::from(timing::duration_to_ms(timings.transfer))),
+        ])),
+    ])
+}
+
+pub fn export_har(entries: &[HistoryEntry]) -> String {
+    let har = json_object(vec![("log", json_object(vec![
+        ("version", Value::from("1.2")),
+        ("creator", json_object(vec![
+            ("name", Value::from(env!("CARGO_PKG_NAME"))),
+            ("version", Value::from(env!("CARGO_PKG_VERSION"))),
+        ])),
+        ("entries", Value::Array(entries.iter().map(export_entry).collect())),
+    ]))]);
+
+    serde_json::to_string_pretty(&har).unwrap_or(String::new())
+}
+
+fn header_lines(headers: Option<&Value>, skip: &Fn(&str) -> bool) -> String {
+    headers.and_then(|x| x.as_array()).map(|items| items.iter().
+        map(|x| (str_field(x, "name"), str_field(x, "value"))).
+        filter(|x| !x.0.is_empty() && !skip(&x.0)).
+        map(|x| format!("{}: {}\n", x.0, x.1)).
+        collect()
+    ).unwrap_or(String::new())
+}
+
+fn import_params(post_data: &Value, multipart: bool) -> Option<String> {
+    let params = post_data.get("params").and_then(|x| x.as_array()).filter(|x| !x.is_empty())?;
+
+    Some(params.iter().map(|param| {
+        let name = str_field(param, "name");
+        let file_name = str_field(param, "fileName");
+        let file_path = str_field(param, "_filePath");
+
+        let mut line = if !multipart {
+            format!("{}={}", name, str_field(param, "value"))
+        } else if !file_path.is_empty() {
+            let default_name = Path::new(&file_path).file_name().map(|x| x.to_string_lossy().into_owned());
+
+            if file_name.is_empty() || Some(&file_name) == default_name.as_ref() {
+                format!("{}=@{}", name, file_path)
+            } else {
+                format!("{}=@{};filename={}", name, file_path, file_name)
+            }
+        } else if !file_name.is_empty() {
+            format!("{}=@{}", name, file_name)
+        } else {
+            format!("{}={}", name, str_field(param, "value"))
+        };
+
+        let content_type = str_field(param, "contentType");
+
+        if multipart && !content_type.is_empty() {
+            line += &format!(";type={}", content_type);
+        }
+
+        line
+    }).collect::<Vec<_>>().join("\n"))
+}
+
+fn import_request(har_request: &Value) -> SavedRequest {
+    let mut request = SavedRequest::default();
+    let method = str_field(har_request, "method");
+
+    request.set_method_name(if method.is_empty() { "GET" } else { &method });
+    request.url = str_field(har_request, "url");
+    request.name = format!("{} {}", request.method_name(), request.url);
+    request.body_type = ::BodyType::Empty as i32;
+
+    let post_data = har_request.get("postData").cloned().unwrap_or(Value::Null);
+    let mime_type = str_field(&post_data, "mimeType").to_lowercase();
+    let multipart = mime_type.starts_with("multipart/form-data");
+    let params = import_params(&post_data, multipart);
+    let body_file = str_field(&post_data, "_bodyFile");
+
+    // The browser's multipart boundary does not match the body sent on replay
+    let drop_content_type = multipart && params.is_some();
+
+    // Pseudo headers of HTTP/2 and a stale length would break the replay
+    request.req_headers = header_lines(har_request.get("headers"), &|name| {
+        name.starts_with(':') || name.eq_ignore_ascii_case("content-length") ||
+            (drop_content_type && name.eq_ignore_ascii_case("content-type"))
+    });
+
+    if !body_file.is_empty() {
+        request.body_file = body_file;
+        request.body_type = ::BodyType::Binary as i32;
+    } else if let Some(params) = params {
+        request.req_body = params;
+        request.body_type = if multipart { ::BodyType::Multipart as i32 } else { ::BodyType::Form as i32 };
+    } else if post_data.get("text").is_some() {
+        request.req_body = str_field(&post_data, "text");
+        request.body_type = actions::raw_body_type(&mime_type) as i32;
+    }
+
+    request
+}
+
+fn import_entry(entry: &Value) -> Result<HistoryEntry, String> {
+    let har_request = entry.get("request").ok_or("Entry without a request")?;
+    let response = entry.get("response").cloned().unwrap_or(Value::Null);
+    let content = response.get("content").cloned().unwrap_or(Value::Null);
+    let text = str_field(&content, "text");
+
+    let rs_body = if str_field(&content, "encoding") == "base64" {
+        base64::decode(text.trim()).map_err(|err| format!("Cannot decode response body - {}", err))?
+    } else {
+        text.into_bytes()
+    };
+
+    let rs_headers = header_lines(response.get("headers"), &|_| false);
+    let request = import_request(har_request);
+    let final_url = str_field(&response, "_finalUrl");
+    let body_size = num_field(&response, "bodySize");
+    let timings = entry.get("timings").cloned().unwrap_or(Value::Null);
+    let ssl = num_field(&timings, "ssl").max(0.0);
+
+    Ok(HistoryEntry {
+        id: None,
+        created_at: history::parse_timestamp(&str_field(entry, "startedDateTime")).unwrap_or(0),
+        status: response.get("status").and_then(|x| x.as_i64()).unwrap_or(0) as i32,
+        reason: str_field(&response, "statusText"),
+        version: str_field(&response, "httpVersion"),
+        final_url: if final_url.is_empty() { request.url.clone() } else { final_url },
+        wire_size: if body_size >= 0.0 { body_size as i64 } else { rs_body.len() as i64 },
+        content_encoding: header_value(&rs_headers, "content-encoding").unwrap_or(String::new()),
+        rs_headers,
+        rs_body,
+        request,
+        timings: Timings {
+            dns: ms_to_duration(num_field(&timings, "dns")),
+            connect: ms_to_duration(num_field(&timings, "connect") - ssl),
+            tls: ms_to_duration(ssl),
+            waiting: ms_to_duration(num_field(&timings, "wait")),
+            transfer: ms_to_duration(num_field(&timings, "receive")),
+        },
+        error: str_field(&response, "_error"),
+        sent: None,
+    })
+}
+
+// Broken entries are skipped with a warning, the rest of the archive is still imported
+pub fn parse_har(text: &str) -> Result<HarImport, String> {
+    let root: Value = serde_json::from_str(text).map_err(|err| format!("Not a JSON file - {}", err))?;
+    let entries = root.pointer("/log/entries").and_then(|x| x.as_array()).ok_or("Not a HAR file")?;
+    let mut result = HarImport::default();
+
+    for (i, entry) in entries.iter().enumerate() {
+        match import_entry(entry) {
+            Ok(x) => result.entries.push(x),
+            Err(err) => result.warnings.push(format!("Entry {} skipped - {}", i + 1, err))
+        };
+    }
+
+    Ok(result)
+}
+
+pub fn import_files(m_win: &::MainWindow) {
+    let files = gtk_ext::choose_files_to_open(&m_win.window, "Import HAR archives");
+
+    if files.is_empty() {
+        return;
+    }
+
+    let connection = config::connect_to_state();
+    let mut request_count = 0;
+    let mut errors = Vec::new();
+
+    for file in &files {
+        let parsed = fs::read_to_string(file).
+            map_err(|err| err.to_string()).
+            and_then(|text| parse_har(&text));
+
+        match parsed {
+            Ok(imported) => {
+                let folder = ImportedFolder {
+                    name: file.file_stem().map(|x| x.to_string_lossy().into_owned()).unwrap_or(String::from("HAR")),
+                    folders: Vec::new(),
+                    requests: imported.entries.into_iter().map(|x| x.request).collect(),
+                };
+
+                request_count += collections::store_imported(&connection, None, &folder);
+                errors.extend(imported.warnings.iter().map(|x| format!("{}: {}", file.display(), x)));
+            },
+            Err(err) => errors.push(format!("{}: {}", file.display(), err))
+        };
+    }
+
+    collections::refresh_tree(m_win);
+
+    let mut message = format!("Imported {} requests", request_count);
+
+    if !errors.is_empty() {
+        message += &format!("\n\n{}", errors.join("\n"));
+    }
+
+    gtk_ext::show_message(&message, &m_win.window);
+}
+
+pub fn export_history(m_win: &::MainWindow) {
+    let mut entries = history::list_entries(&config::connect_to_state());
+
+    if entries.is_empty() {
+        gtk_ext::show_message("There are no history entries to export", &m_win.window);
+        return;
+    }
+
+    entries.reverse();
+
+    if let Some(path) = gtk_ext::choose_file_to_save(&m_win.window, "Export history as HAR", "history.har") {
+        if let Err(err) = fs::write(&path, export_har(&entries)) {
+            gtk_ext::show_message(&format!("Cannot export history - {}", err), &m_win.window);
+        }
+    }
+}
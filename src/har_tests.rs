@@ -0,0 +1,139 @@
+use std::time::Duration;
+use serde_json;
+use serde_json::Value;
+use super::har::{parse_har, export_har};
+use super::history::HistoryEntry;
+use super::collections::SavedRequest;
+use super::timing::Timings;
+
+#[test]
+pub fn test_parse_har() {
+    let imported = parse_har(include_str!("test_data/har1.json")).unwrap();
+    let entries = &imported.entries;
+    assert_eq!(entries.len(), 2);
+    assert!(imported.warnings.is_empty());
+
+    let login = &entries[0];
+    assert_eq!(login.created_at, 1551777630);
+    assert_eq!(login.request.request_method, ::RequestMethod::Post as i32);
+    assert_eq!(login.request.url, "https://api.example.com/login");
+    assert_eq!(login.request.req_headers, "content-type: application/x-www-form-urlencoded\naccept: application/json\n");
+    assert_eq!(login.request.body_type, ::BodyType::Form as i32);
+    assert_eq!(login.request.req_body, "user=ann\npass=x y");
+    assert_eq!(login.status, 200);
+    assert_eq!(login.version, "http/2.0");
+    assert_eq!(login.rs_body, b"{\"token\":\"abc\"}");
+    assert_eq!(login.wire_size, 35);
+    assert_eq!(login.content_encoding, "gzip");
+    assert_eq!(login.final_url, "https://api.example.com/login");
+    assert_eq!(login.timings.connect, Duration::from_millis(30));
+    assert_eq!(login.timings.tls, Duration::from_millis(20));
+    assert_eq!(login.timings.waiting, Duration::from_millis(90));
+
+    let upload = &entries[1];
+    assert_eq!(upload.created_at, 1551777631);
+    assert_eq!(upload.request.req_headers, "");
+    assert_eq!(upload.request.body_type, ::BodyType::Multipart as i32);
+    assert_eq!(upload.request.req_body, "title=Report\nfile=@report.pdf;type=application/pdf");
+    assert_eq!(upload.reason, "Created");
+    assert_eq!(upload.rs_body, b"GIF89a");
+    assert_eq!(upload.wire_size, 6);
+    assert_eq!(upload.timings.dns, Duration::from_millis(0));
+
+    assert!(parse_har("{\"log\": {}}").is_err());
+
+    let partial = parse_har(r#"{"log": {"entries": [
+        {"request": {"method": "GET", "url": "http://a/"}, "response": {"content": {"text": "%%", "encoding": "base64"}}},
+        {"request": {"method": "GET", "url": "http://b/"}, "response": {"content": {"text": "b2s=", "encoding": "base64"}}}
+    ]}}"#).unwrap();
+    assert_eq!(partial.entries.len(), 1);
+    assert_eq!(partial.entries[0].rs_body, b"ok");
+    assert_eq!(partial.warnings.len(), 1);
+    assert!(partial.warnings[0].starts_with("Entry 1 skipped - Cannot decode response body"));
+}
+
+#[test]
+pub fn test_har_round_trip() {
+    let entry = HistoryEntry {
+        id: None,
+        created_at: 1546300799,
+        request: SavedRequest {
+            request_method: ::RequestMethod::Put as i32,
+            url: String::from("https://api.example.com/items/1?force=true"),
+            req_headers: String::from("content-type: application/json\nx-trace: 42\n"),
+            body_type: ::BodyType::Json as i32,
+            req_body: String::from("{\"name\": \"item\"}"),
+            ..Default::default()
+        },
+        status: 404,
+        reason: String::from("Not Found"),
+        version: String::from("HTTP/1.1"),
+        final_url: String::from("https://api.example.com/items/1/?force=true"),
+        rs_headers: String::from("content-type: application/octet-stream\n"),
+        rs_body: vec![0, 159, 146, 150],
+        wire_size: 4,
+        content_encoding: String::new(),
+        timings: Timings {
+            dns: Duration::from_millis(3),
+            connect: Duration::from_millis(12),
+            tls: Duration::from_millis(25),
+            waiting: Duration::from_millis(140),
+            transfer: Duration::from_millis(2),
+        },
+        error: String::new(),
+        sent: None,
+    };
+
+    let parsed = parse_har(&export_har(&[entry.clone()])).unwrap();
+    let copy = &parsed.entries[0];
+
+    assert_eq!(copy.created_at, entry.created_at);
+    assert_eq!(copy.request.request_method, entry.request.request_method);
+    assert_eq!(copy.request.url, entry.request.url);
+    assert_eq!(copy.request.req_headers, entry.request.req_headers);
+    assert_eq!(copy.request.body_type, entry.request.body_type);
+    assert_eq!(copy.request.req_body, entry.request.req_body);
+    assert_eq!(copy.status, entry.status);
+    assert_eq!(copy.reason, entry.reason);
+    assert_eq!(copy.version, entry.version);
+    assert_eq!(copy.final_url, entry.final_url);
+    assert_eq!(copy.rs_headers, entry.rs_headers);
+    assert_eq!(copy.rs_body, entry.rs_body);
+    assert_eq!(copy.wire_size, entry.wire_size);
+    assert_eq!(copy.timings.dns, entry.timings.dns);
+    assert_eq!(copy.timings.connect, entry.timings.connect);
+    assert_eq!(copy.timings.tls, entry.timings.tls);
+    assert_eq!(copy.timings.waiting, entry.timings.waiting);
+    assert_eq!(copy.timings.transfer, entry.timings.transfer);
+}
+
+#[test]
+pub fn test_har_form_round_trip() {
+    let mut entry = parse_har(include_str!("test_data/har1.json")).unwrap().entries.remove(0);
+    entry.request.req_body = String::from("q=a&b\nempty=");
+
+    let copy = parse_har(&export_har(&[entry.clone()])).unwrap().entries.remove(0);
+
+    assert_eq!(copy.request.body_type, ::BodyType::Form as i32);
+    assert_eq!(copy.request.req_body, entry.request.req_body);
+}
+
+#[test]
+pub fn test_har_exports_sent_request() {
+    let mut entry = parse_har(include_str!("test_data/har1.json")).unwrap().entries.remove(0);
+    entry.request.url = String::from("{{host}}/login?user={{user}}");
+    entry.request.req_body = String::from("user={{user}}\npass=x y");
+    entry.sent = Some(SavedRequest {
+        url: String::from("https://api.example.com/login?user=ann"),
+        req_body: String::from("user=ann\npass=x y"),
+        ..entry.request.clone()
+    });
+
+    let exported: Value = serde_json::from_str(&export_har(&[entry])).unwrap();
+    let request = &exported["log"]["entries"][0]["request"];
+
+    assert_eq!(request["url"], "https://api.example.com/login?user=ann");
+    assert_eq!(request["queryString"][0]["value"], "ann");
+    assert_eq!(request["bodySize"], "user=ann&pass=x+y".len() as i64);
+    assert_eq!(request["postData"]["params"][0]["value"], "ann");
+}
@@ -0,0 +1,472 @@
+use rusqlite::{Connection, Row};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use gtk;
+use gtk::prelude::*;
+use gtk_ext;
+use config;
+use actions;
+use charset;
+use collections::{self, SavedRequest};
+use timing::{self, Timings};
+use har;
+
+pub const NO_HISTORY_HEADER: &'static str = "X-AU-No-History";
+
+const ENTRY_COLUMNS: &'static str = "id, created_at, request_method, custom_method, url, req_headers, \
+    body_type, req_body, body_file, status, reason, version, final_url, rs_headers, rs_body, wire_size, \
+    content_encoding, dns_ms, connect_ms, tls_ms, waiting_ms, transfer_ms, error, sent_url, sent_headers, \
+    sent_body, sent_body_file";
+
+const LIST_COLUMNS: &'static str = "id, created_at, request_method, custom_method, url, status, error";
+
+// Only the newest entries are listed, older ones are still exported
+const LIST_LIMIT: i64 = 1000;
+
+#[derive(Debug, Clone)]
+pub struct HistoryEntry {
+    pub id: Option<i64>,
+    pub created_at: i64,
+    pub request: SavedRequest,
+    pub status: i32,
+    pub reason: String,
+    pub version: String,
+    pub final_url: String,
+    pub rs_headers: String,
+    pub rs_body: Vec<u8>,
+    pub wire_size: i64,
+    pub content_encoding: String,
+    pub timings: Timings,
+    // Set when the request failed before any response arrived
+    pub error: String,
+    // The request with its variables resolved, missing for entries recorded before it was kept
+    pub sent: Option<SavedRequest>,
+}
+
+// A row of the history list, the full entry is loaded when it is opened
+#[derive(Debug, Clone)]
+pub struct HistoryItem {
+    pub id: i64,
+    pub title: String,
+}
+
+#[allow(unused_must_use)]
+pub fn create_tables(connection: &Connection) {
+    connection.execute("CREATE TABLE history (
+        id INTEGER PRIMARY KEY,
+        created_at INTEGER NOT NULL,
+        request_method INTEGER NOT NULL,
+        custom_method TEXT NOT NULL,
+        url TEXT NOT NULL,
+        req_headers TEXT NOT NULL,
+        body_type INTEGER NOT NULL,
+        req_body TEXT NOT NULL,
+        body_file TEXT NOT NULL,
+        status INTEGER NOT NULL,
+        reason TEXT NOT NULL,
+        version TEXT NOT NULL,
+        final_url TEXT NOT NULL,
+        rs_headers TEXT NOT NULL,
+        rs_body BLOB NOT NULL,
+        wire_size INTEGER NOT NULL,
+        content_encoding TEXT NOT NULL,
+        dns_ms REAL NOT NULL,
+        connect_ms REAL NOT NULL,
+        tls_ms REAL NOT NULL,
+        waiting_ms REAL NOT NULL,
+        transfer_ms REAL NOT NULL,
+        error TEXT NOT NULL DEFAULT '',
+        sent_url TEXT NOT NULL DEFAULT '',
+        sent_headers TEXT NOT NULL DEFAULT '',
+        sent_body TEXT NOT NULL DEFAULT '',
+        sent_body_file TEXT NOT NULL DEFAULT ''
+        )", &[]);
+
+    // Databases created before failures were recorded lack the column, elsewhere this fails harmlessly
+    connection.execute("ALTER TABLE history ADD COLUMN error TEXT NOT NULL DEFAULT ''", &[]);
+
+    // Same for the resolved request
+    connection.execute("ALTER TABLE history ADD COLUMN sent_url TEXT NOT NULL DEFAULT ''", &[]);
+    connection.execute("ALTER TABLE history ADD COLUMN sent_headers TEXT NOT NULL DEFAULT ''", &[]);
+    connection.execute("ALTER TABLE history ADD COLUMN sent_body TEXT NOT NULL DEFAULT ''", &[]);
+    connection.execute("ALTER TABLE history ADD COLUMN sent_body_file TEXT NOT NULL DEFAULT ''", &[]);
+}
+
+pub fn now() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|x| x.as_secs() as i64).unwrap_or(0)
+}
+
+pub fn ms_to_duration(ms: f64) -> Duration {
+    Duration::from_micros((ms.max(0.0) * 1000.0) as u64)
+}
+
+// Civil date from days since epoch, see http://howardhinnant.github.io/date_algorithms.html
+pub fn format_timestamp(timestamp: i64) -> String {
+    let timestamp = timestamp.max(0);
+    let days = timestamp / 86400;
+    let seconds = timestamp % 86400;
+    let z = days + 719468;
+    let era = z / 146097;
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        year, month, day, seconds / 3600, seconds % 3600 / 60, seconds % 60)
+}
+
+// Accepts ISO 8601 date-times like 2019-03-05T10:20:30.123+02:00 as used by HAR files
+pub fn parse_timestamp(text: &str) -> Option<i64> {
+    let number = |range: ::std::ops::Range<usize>| text.get(range).and_then(|x| x.parse::<i64>().ok());
+    let (year, month, day) = (number(0..4)?, number(5..7)?, number(8..10)?);
+    let (hour, minute, second) = (number(11..13)?, number(14..16)?, number(17..19)?);
+
+    if month < 1 || month > 12 || day < 1 || day > 31 {
+        return None;
+    }
+
+    let rest = text[19..].trim_start_matches(|x: char| x == '.' || x.is_ascii_digit());
+    let offset = match rest.chars().next() {
+        None | Some('Z') | Some('z') => 0,
+        Some(sign) if sign == '+' || sign == '-' => {
+            let zone = rest[1..].replace(':', "");
+            let minutes = zone.get(0..2)?.parse::<i64>().ok()? * 60 + zone.get(2..4).unwrap_or("0").parse::<i64>().ok()?;
+            if sign == '+' { minutes * 60 } else { -minutes * 60 }
+        },
+        _ => return None
+    };
+
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let yoe = year - era * 400;
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146097 + doe - 719468;
+
+    Some(days * 86400 + hour * 3600 + minute * 60 + second - offset)
+}
+
+impl HistoryEntry {
+    pub fn new(request: &SavedRequest, resp: &::Response) -> Self {
+        HistoryEntry {
+            id: None,
+            created_at: now(),
+            request: request.clone(),
+            status: resp.status as i32,
+            reason: resp.reason.clone(),
+            version: resp.version.clone(),
+            final_url: resp.url.clone(),
+            rs_headers: actions::format_headers(&resp.headers),
+            rs_body: resp.body.clone(),
+            wire_size: resp.wire_size as i64,
+            content_encoding: resp.content_encoding.clone(),
+            timings: resp.timings.clone(),
+            error: String::new(),
+            sent: None,
+        }
+    }
+
+    pub fn failed(request: &SavedRequest, error: &str) -> Self {
+        HistoryEntry {
+            id: None,
+            created_at: now(),
+            request: request.clone(),
+            status: 0,
+            reason: String::new(),
+            version: String::new(),
+            final_url: request.url.clone(),
+            rs_headers: String::new(),
+            rs_body: Vec::new(),
+            wire_size: 0,
+            content_encoding: String::new(),
+            timings: Timings::default(),
+            error: String::from(error),
+            sent: None,
+        }
+    }
+
+    // What was actually sent, the stored request may still contain {{variables}}
+    pub fn sent_request(&self) -> &SavedRequest {
+        self.sent.as_ref().unwrap_or(&self.request)
+    }
+
+    pub fn title(&self) -> String {
+        format_title(self.created_at, self.status, &self.error, &self.request)
+    }
+
+    pub fn to_response(&self) -> ::Response {
+        let headers = actions::parse_headers(&self.rs_headers, &mut |_| {});
+        let mime = actions::detect_mime_type(&headers);
+        let encoding = charset::detect_encoding(&self.rs_body, &mime);
+
+        ::Response {
+            text: charset::decode_with(&self.rs_body, encoding),
+            body: self.rs_body.clone(),
+            extension: actions::conv_mime_type_to_extension(&mime),
+            mime_type: mime,
+            encoding,
+            force_text: false,
+            highlight: None,
+            headers,
+            status: self.status as u16,
+            reason: self.reason.clone(),
+            version: self.version.clone(),
+            url: self.final_url.clone(),
+            size: self.rs_body.len(),
+            wire_size: self.wire_size as usize,
+            content_encoding: self.content_encoding.clone(),
+            decode_error: None,
+            raw_encoded: false,
+            timings: self.timings.clone(),
+            assertions: Vec::new(),
+        }
+    }
+}
+
+fn format_title(created_at: i64, status: i32, error: &str, request: &SavedRequest) -> String {
+    let status = if error.is_empty() { status.to_string() } else { String::from("failed") };
+
+    format!("{}  {} {}  {}", format_timestamp(created_at), status, request.method_name(), request.url)
+}
+
+fn read_entry(row: &Row) -> HistoryEntry {
+    let request = SavedRequest {
+        request_method: row.get(2),
+        custom_method: row.get(3),
+        url: row.get(4),
+        req_headers: row.get(5),
+        body_type: row.get(6),
+        req_body: row.get(7),
+        body_file: row.get(8),
+        ..Default::default()
+    };
+    let sent_url: String = row.get(23);
+    let sent = if sent_url.is_empty() {
+        None
+    } else {
+        Some(SavedRequest {
+            url: sent_url,
+            req_headers: row.get(24),
+            req_body: row.get(25),
+            body_file: row.get(26),
+            ..request.clone()
+        })
+    };
+
+    HistoryEntry {
+        id: row.get(0),
+        created_at: row.get(1),
+        request,
+        status: row.get(9),
+        reason: row.get(10),
+        version: row.get(11),
+        final_url: row.get(12),
+        rs_headers: row.get(13),
+        rs_body: row.get(14),
+        wire_size: row.get(15),
+        content_encoding: row.get(16),
+        timings: Timings {
+            dns: ms_to_duration(row.get(17)),
+            connect: ms_to_duration(row.get(18)),
+            tls: ms_to_duration(row.get(19)),
+            waiting: ms_to_duration(row.get(20)),
+            transfer: ms_to_duration(row.get(21)),
+        },
+        error: row.get(22),
+        sent,
+    }
+}
+
+fn read_item(row: &Row) -> HistoryItem {
+    let request = SavedRequest {
+        request_method: row.get(2),
+        custom_method: row.get(3),
+        url: row.get(4),
+        ..Default::default()
+    };
+    let error: String = row.get(6);
+
+    HistoryItem {
+        id: row.get(0),
+        title: format_title(row.get(1), row.get(5), &error, &request),
+    }
+}
+
+pub fn insert_entry(connection: &Connection, entry: &HistoryEntry) -> Option<i64> {
+    let q = format!("INSERT INTO history ({}) VALUES (NULL, ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, \
+        ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24, ?25, ?26)", ENTRY_COLUMNS);
+    let sent = entry.sent.clone().unwrap_or(SavedRequest::default());
+
+    connection.execute(&q, &[
+        &entry.created_at, &entry.request.request_method, &entry.request.custom_method,
+        &entry.request.url, &entry.request.req_headers, &entry.request.body_type,
+        &entry.request.req_body, &entry.request.body_file, &entry.status, &entry.reason,
+        &entry.version, &entry.final_url, &entry.rs_headers, &entry.rs_body, &entry.wire_size,
+        &entry.content_encoding, &timing::duration_to_ms(entry.timings.dns),
+        &timing::duration_to_ms(entry.timings.connect), &timing::duration_to_ms(entry.timings.tls),
+        &timing::duration_to_ms(entry.timings.waiting), &timing::duration_to_ms(entry.timings.transfer),
+        &entry.error, &sent.url, &sent.req_headers, &sent.req_body, &sent.body_file
+    ]).ok().map(|_| connection.last_insert_rowid())
+}
+
+pub fn load_entry(connection: &Connection, id: i64) -> Option<HistoryEntry> {
+    let q = format!("SELECT {} FROM history WHERE id = ?1", ENTRY_COLUMNS);
+
+    connection.query_row(&q, &[&id], read_entry).ok()
+}
+
+pub fn list_entries(connection: &Connection) -> Vec<HistoryEntry> {
+    let q = format!("SELECT {} FROM history ORDER BY id DESC", ENTRY_COLUMNS);
+    let mut stmt = connection.prepare(&q).unwrap();
+    let entry_iter = stmt.query_map(&[], read_entry).unwrap();
+
+    entry_iter.filter_map(|x| x.ok()).collect()
+}
+
+pub fn list_items(connection: &Connection) -> Vec<HistoryItem> {
+    let q = format!("SELECT {} FROM history ORDER BY id DESC LIMIT ?1", LIST_COLUMNS);
+    let mut stmt = connection.prepare(&q).unwrap();
+    let item_iter = stmt.query_map(&[&LIST_LIMIT], read_item).unwrap();
+
+    item_iter.filter_map(|x| x.ok()).collect()
+}
+
+#[allow(unused_must_use)]
+pub fn prune(connection: &Connection, max_entries: i64, max_age_days: i64) {
+    if max_age_days > 0 {
+        connection.execute("DELETE FROM history WHERE created_at < ?1", &[&(now() - max_age_days * 86400)]);
+    }
+
+    if max_entries > 0 {
+        connection.execute("DELETE FROM history WHERE id NOT IN
+            (SELECT id FROM history ORDER BY id DESC LIMIT ?1)", &[&max_entries]);
+    }
+}
+
+#[allow(unused_must_use)]
+pub fn clear(connection: &Connection) {
+    connection.execute("DELETE FROM history", &[]);
+}
+
+fn get_limits(m_win: &::MainWindow) -> (i64, i64) {
+    let state = m_win.config.borrow();
+    (state.history_limit, state.history_days)
+}
+
+pub fn record(m_win: &::MainWindow, request: &SavedRequest, sent: &SavedRequest, resp: &::Response) {
+    let mut entry = HistoryEntry::new(request, resp);
+    entry.sent = Some(sent.clone());
+
+    insert_and_prune(m_win, &entry);
+}
+
+pub fn record_failure(m_win: &::MainWindow, request: &SavedRequest, sent: &SavedRequest, error: &str) {
+    let mut entry = HistoryEntry::failed(request, error);
+    entry.final_url = sent.url.clone();
+    entry.sent = Some(sent.clone());
+
+    insert_and_prune(m_win, &entry);
+}
+
+fn insert_and_prune(m_win: &::MainWindow, entry: &HistoryEntry) {
+    let connection = config::connect_to_state();
+    let (max_entries, max_age_days) = get_limits(m_win);
+
+    insert_entry(&connection, entry);
+    prune(&connection, max_entries, max_age_days);
+    refresh_list(m_win);
+}
+
+pub fn refresh_list(m_win: &::MainWindow) {
+    m_win.history_store.clear();
+
+    for item in list_items(&config::connect_to_state()) {
+        m_win.history_store.insert_with_values(None, &[0, 1], &[&item.title, &item.id]);
+    }
+}
+
+pub fn load_selected(m_win: &::MainWindow) {
+    let selected = m_win.history_view.get_selection().get_selected().
+        and_then(|(model, iter)| model.get_value(&iter, 1).get::<i64>());
+
+    let connection = config::connect_to_state();
+
+    if let Some(entry) = selected.and_then(|x| load_entry(&connection, x)) {
+        if collections::has_unsaved_edits(m_win, &connection) &&
+            !gtk_ext::confirm("Discard the unsaved changes in this tab?", &m_win.window) {
+            return;
+        }
+
+        let editor = m_win.editor();
+
+        entry.request.update_to_editor(&editor);
+        editor.set_saved_request(None);
+
+        if entry.error.is_empty() {
+            actions::show_response(&editor, entry.to_response());
+        } else {
+            actions::reset_response_view(&editor);
+            editor.set_rs_headers("");
+            editor.set_rs_body(&entry.error);
+        }
+    }
+}
+
+fn edit_limits(m_win: &::MainWindow) {
+    let (max_entries, max_age_days) = get_limits(m_win);
+    let values = gtk_ext::prompt_values(&m_win.window, "History limits (0 means unlimited)", &[
+        ("Max entries", &max_entries.to_string()),
+        ("Max age in days", &max_age_days.to_string()),
+    ]);
+
+    if let Some(values) = values {
+        let parsed = (values[0].trim().parse::<i64>(), values[1].trim().parse::<i64>());
+
+        match parsed {
+            (Ok(entries), Ok(days)) => {
+                {
+                    let mut state = m_win.config.borrow_mut();
+                    state.history_limit = entries.max(0);
+                    state.history_days = days.max(0);
+                }
+
+                prune(&config::connect_to_state(), entries, days);
+                refresh_list(m_win);
+            },
+            _ => gtk_ext::show_message("History limits must be whole numbers", &m_win.window)
+        };
+    }
+}
+
+pub fn bind_to_window(m_win: &::MainWindow) {
+    let column = gtk::TreeViewColumn::new();
+    let cell = gtk::CellRendererText::new();
+    column.pack_start(&cell, true);
+    column.add_attribute(&cell, "text", 0);
+    m_win.history_view.append_column(&column);
+    m_win.history_view.set_model(Some(&m_win.history_store));
+
+    // Like saved requests, an entry is opened by activating its row
+    m_win.history_view.connect_row_activated(gtk_clone!(m_win => move |_, _, _| {
+        load_selected(&m_win);
+    }));
+
+    let clear_btn: gtk::Button = gtk_ext::get_gtk_obj_by_id(&m_win.builder, "clearHistoryBtn");
+    let limits_btn: gtk::Button = gtk_ext::get_gtk_obj_by_id(&m_win.builder, "historyLimitsBtn");
+    let export_btn: gtk::Button = gtk_ext::get_gtk_obj_by_id(&m_win.builder, "exportHistoryBtn");
+
+    clear_btn.connect_clicked(gtk_clone!(m_win => move |_| {
+        if gtk_ext::confirm("Delete all history entries?", &m_win.window) {
+            clear(&config::connect_to_state());
+            refresh_list(&m_win);
+        }
+    }));
+
+    limits_btn.connect_clicked(gtk_clone!(m_win => move |_| edit_limits(&m_win)));
+    export_btn.connect_clicked(gtk_clone!(m_win => move |_| har::export_history(&m_win)));
+
+    refresh_list(m_win);
+}
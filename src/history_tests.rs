@@ -0,0 +1,17 @@
+use super::history::{format_timestamp, parse_timestamp};
+
+#[test]
+pub fn test_format_timestamp() {
+    assert_eq!(format_timestamp(0), "1970-01-01 00:00:00");
+    assert_eq!(format_timestamp(951782400), "2000-02-29 00:00:00");
+    assert_eq!(format_timestamp(1546300799), "2018-12-31 23:59:59");
+}
+
+#[test]
+pub fn test_parse_timestamp() {
+    assert_eq!(parse_timestamp("1970-01-01T00:00:00Z"), Some(0));
+    assert_eq!(parse_timestamp("2000-02-29T00:00:00.000Z"), Some(951782400));
+    assert_eq!(parse_timestamp("2019-01-01T01:59:59.123+02:00"), Some(1546300799));
+    assert_eq!(parse_timestamp("2018-12-31T18:59:59-0500"), Some(1546300799));
+    assert_eq!(parse_timestamp("yesterday"), None);
+}
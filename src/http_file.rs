@@ -0,0 +1,483 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use gtk;
+use gtk::prelude::*;
+use gtk_ext;
+use config;
+use actions;
+use environments;
+use curl;
+use collections::{self, SavedRequest};
+
+const SEPARATOR: &'static str = "###";
+const DEFAULT_EXTENSION: &'static str = ".http";
+
+// One request of a REST Client file. Everything around the request is kept
+// as written, so that saving only rewrites the requests that were edited.
+#[derive(Debug, Clone, Default)]
+pub struct HttpBlock {
+    pub separator: Option<String>,
+    pub leading: Vec<String>,
+    pub lines: Vec<String>,
+    pub trailing: Vec<String>,
+    pub request: Option<SavedRequest>,
+    pub version: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct HttpFile {
+    pub path: PathBuf,
+    pub newline: String,
+    pub blocks: Vec<HttpBlock>,
+}
+
+fn is_comment(line: &str) -> bool {
+    let line = line.trim_start();
+    line.starts_with('#') || line.starts_with("//")
+}
+
+fn parse_variable(line: &str) -> Option<(String, String)> {
+    let line = line.trim();
+
+    if !line.starts_with('@') {
+        return None;
+    }
+
+    line[1..].find('=').
+        map(|pos| (line[1..pos + 1].trim(), line[pos + 2..].trim())).
+        filter(|x| !x.0.is_empty() && !x.0.contains(char::is_whitespace)).
+        map(|(name, value)| (String::from(name), String::from(value)))
+}
+
+// A comment like "# @name login" names the request
+fn comment_name(line: &str) -> Option<String> {
+    let text = line.trim_start().trim_start_matches(|x: char| x == '#' || x == '/').trim();
+
+    if text.starts_with("@name ") {
+        Some(String::from(text[6..].trim()))
+    } else {
+        None
+    }
+}
+
+fn is_method(word: &str) -> bool {
+    !word.is_empty() && word.chars().all(|x| x.is_ascii_uppercase())
+}
+
+fn parse_request(lines: &[String], base_dir: &Path) -> (SavedRequest, Option<String>) {
+    let mut request = SavedRequest::default();
+    let mut version = None;
+    let mut words: Vec<&str> = lines[0].split_whitespace().collect();
+
+    if words.len() > 1 && words.last().map(|x| x.starts_with("HTTP/")).unwrap_or(false) {
+        version = words.pop().map(String::from);
+    }
+
+    if words.len() > 1 && is_method(words[0]) {
+        request.set_method_name(words[0]);
+        words.remove(0);
+    } else {
+        request.set_method_name("GET");
+    }
+
+    request.url = words.join(" ");
+
+    // Long query strings may continue on the next lines starting with ? or &
+    let mut rest = lines[1..].iter().peekable();
+
+    while let Some(line) = rest.peek().cloned().map(|x| x.trim()).filter(|x| x.starts_with('?') || x.starts_with('&')) {
+        request.url += line;
+        rest.next();
+    }
+
+    let mut headers = String::new();
+
+    while let Some(line) = rest.next() {
+        if line.trim().is_empty() {
+            break;
+        }
+
+        if !is_comment(line) {
+            headers += &format!("{}\n", line.trim());
+        }
+    }
+
+    let body: Vec<&str> = rest.map(|x| x.as_str()).collect();
+    let content_type = actions::parse_headers(&headers, &mut |_| {}).get("content-type").
+        and_then(|x| x.to_str().ok()).
+        map(String::from).
+        unwrap_or(String::new());
+
+    request.req_headers = headers;
+
+    if body.len() == 1 && body[0].trim_start().starts_with('<') {
+        let file = body[0].trim_start()[1..].trim();
+        request.body_file = base_dir.join(file).to_string_lossy().into_owned();
+        request.body_type = ::BodyType::Binary as i32;
+    } else if body.is_empty() {
+        request.body_type = ::BodyType::Empty as i32;
+    } else {
+        request.req_body = body.join("\n");
+        request.body_type = actions::raw_body_type(&content_type) as i32;
+    }
+
+    (request, version)
+}
+
+fn parse_block(separator: Option<String>, lines: Vec<String>, base_dir: &Path) -> HttpBlock {
+    let start = lines.iter().
+        position(|x| !x.trim().is_empty() && !is_comment(x) && parse_variable(x).is_none()).
+        unwrap_or(lines.len());
+    let end = lines.iter().rposition(|x| !x.trim().is_empty()).map(|x| x + 1).unwrap_or(0).max(start);
+
+    let mut block = HttpBlock {
+        separator,
+        leading: lines[..start].to_vec(),
+        lines: lines[start..end].to_vec(),
+        trailing: lines[end..].to_vec(),
+        ..Default::default()
+    };
+
+    if !block.lines.is_empty() {
+        let (mut request, version) = parse_request(&block.lines, base_dir);
+        let title = block.separator.as_ref().map(|x| String::from(x[SEPARATOR.len()..].trim())).unwrap_or(String::new());
+
+        request.name = block.leading.iter().filter_map(|x| comment_name(x)).last().
+            or(Some(title).filter(|x| !x.is_empty())).
+            unwrap_or(format!("{} {}", request.method_name(), request.url));
+
+        block.request = Some(request);
+        block.version = version;
+    }
+
+    block
+}
+
+pub fn parse(text: &str, path: &Path) -> HttpFile {
+    let base_dir = path.parent().unwrap_or(Path::new(""));
+    let mut file = HttpFile {
+        path: path.to_path_buf(),
+        newline: String::from(if text.contains("\r\n") { "\r\n" } else { "\n" }),
+        blocks: Vec::new(),
+    };
+
+    let mut separator = None;
+    let mut lines = Vec::new();
+
+    for line in text.lines() {
+        if line.trim_start().starts_with(SEPARATOR) {
+            file.blocks.push(parse_block(separator, lines, base_dir));
+            separator = Some(String::from(line));
+            lines = Vec::new();
+        } else {
+            lines.push(String::from(line));
+        }
+    }
+
+    file.blocks.push(parse_block(separator, lines, base_dir));
+    file
+}
+
+// File variables may refer to environment variables and to the ones defined above them
+pub fn variables(file: &HttpFile, base: &[(String, String)]) -> Vec<(String, String)> {
+    let mut result = base.to_vec();
+    let mut unresolved = Vec::new();
+
+    for block in &file.blocks {
+        for (name, value) in block.leading.iter().filter_map(|x| parse_variable(x)) {
+            let value = environments::substitute(&value, &result, &mut unresolved);
+            result.push((name, value));
+        }
+    }
+
+    result.split_off(base.len())
+}
+
+fn header_name(line: &str) -> String {
+    line.split(':').next().unwrap_or("").trim().to_lowercase()
+}
+
+// Comments between the headers of a block, with the header each one preceded
+fn header_comments(lines: &[String]) -> Vec<(Option<String>, String)> {
+    let mut result = Vec::new();
+    let mut pending = Vec::new();
+    let headers = lines.iter().skip(1).skip_while(|x| x.trim().starts_with('?') || x.trim().starts_with('&'));
+
+    for line in headers {
+        if line.trim().is_empty() {
+            break;
+        }
+
+        if is_comment(line) {
+            pending.push(line.clone());
+        } else {
+            let name = header_name(line);
+            result.extend(pending.drain(..).map(|x| (Some(name.clone()), x)));
+        }
+    }
+
+    result.extend(pending.into_iter().map(|x| (None, x)));
+    result
+}
+
+// Comments go back before their header, those whose header is gone end up after the last one
+fn restore_header_comments(lines: Vec<String>, comments: &[(Option<String>, String)]) -> Vec<String> {
+    if comments.is_empty() {
+        return lines;
+    }
+
+    let header_end = lines.iter().position(|x| x.is_empty()).unwrap_or(lines.len());
+    let mut used = vec![false; comments.len()];
+    let mut result = vec![lines[0].clone()];
+
+    for line in &lines[1..header_end] {
+        let name = header_name(line);
+
+        for (i, comment) in comments.iter().enumerate() {
+            if !used[i] && comment.0.as_ref() == Some(&name) {
+                result.push(comment.1.clone());
+                used[i] = true;
+            }
+        }
+
+        result.push(line.clone());
+    }
+
+    result.extend(comments.iter().zip(used).filter(|x| !x.1).map(|x| (x.0).1.clone()));
+    result.extend(lines[header_end..].iter().cloned());
+    result
+}
+
+pub fn render_request(request: &SavedRequest, version: Option<&str>, base_dir: &Path) -> Result<Vec<String>, String> {
+    let mut lines = vec![match version {
+        Some(version) => format!("{} {} {}", request.method_name(), request.url, version),
+        None => format!("{} {}", request.method_name(), request.url)
+    }];
+
+    let mut headers: Vec<String> = request.req_headers.lines().
+        map(|x| String::from(x.trim())).
+        filter(|x| !x.is_empty()).
+        collect();
+
+    let body = match config::WindowState::conv_to_body_type(request.body_type) {
+        ::BodyType::Empty => None,
+        ::BodyType::Binary => {
+            let path = Path::new(&request.body_file);
+            Some(format!("< {}", path.strip_prefix(base_dir).unwrap_or(path).display()))
+        },
+        ::BodyType::Multipart => return Err(String::from("Multipart bodies cannot be written to .http files")),
+        ::BodyType::Form => {
+            if !headers.iter().any(|x| x.to_lowercase().starts_with("content-type:")) {
+                headers.push(String::from("Content-Type: application/x-www-form-urlencoded"));
+            }
+
+            Some(actions::create_post_req_data(&request.req_body).iter().
+                map(|x| format!("{}={}", curl::percent_encode(x.0), curl::percent_encode(x.1))).
+                collect::<Vec<_>>().
+                join("&"))
+        },
+        _ => Some(request.req_body.clone()).filter(|x| !x.trim().is_empty())
+    };
+
+    lines.extend(headers);
+
+    if let Some(body) = body {
+        lines.push(String::new());
+        lines.extend(body.lines().map(String::from));
+    }
+
+    Ok(lines)
+}
+
+impl HttpFile {
+    fn base_dir(&self) -> &Path {
+        self.path.parent().unwrap_or(Path::new(""))
+    }
+
+    pub fn requests(&self) -> Vec<(usize, &SavedRequest)> {
+        self.blocks.iter().enumerate().filter_map(|(i, x)| x.request.as_ref().map(|y| (i, y))).collect()
+    }
+
+    // Replaces the request of a block, or appends a new block when index is None
+    pub fn set_request(&mut self, index: Option<usize>, request: &SavedRequest) -> Result<usize, String> {
+        let version = index.and_then(|x| self.blocks[x].version.clone());
+        let comments = index.map(|x| header_comments(&self.blocks[x].lines)).unwrap_or(Vec::new());
+        let lines = render_request(request, version.as_ref().map(|x| x.as_str()), self.base_dir())?;
+        let lines = restore_header_comments(lines, &comments);
+
+        let index = match index {
+            Some(x) => x,
+            None => {
+                // Keep a blank line between the previous request and the new separator
+                if let Some(previous) = self.blocks.last_mut() {
+                    if !previous.trailing.iter().any(|x| x.trim().is_empty()) {
+                        previous.trailing.push(String::new());
+                    }
+                }
+
+                self.blocks.push(HttpBlock {
+                    separator: Some(format!("{} {}", SEPARATOR, request.name).trim().to_string()),
+                    ..Default::default()
+                });
+
+                self.blocks.len() - 1
+            }
+        };
+
+        let block = &mut self.blocks[index];
+
+        block.lines = lines;
+        block.request = Some(SavedRequest { name: block.request.as_ref().map(|x| x.name.clone()).unwrap_or(request.name.clone()), ..request.clone() });
+
+        Ok(index)
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut lines: Vec<&str> = Vec::new();
+
+        for block in &self.blocks {
+            lines.extend(block.separator.iter().map(|x| x.as_str()));
+            lines.extend(block.leading.iter().chain(block.lines.iter()).chain(block.trailing.iter()).map(|x| x.as_str()));
+        }
+
+        lines.join(self.newline.as_str()) + self.newline.as_str()
+    }
+}
+
+pub fn active_variables(base: &[(String, String)]) -> Vec<(String, String)> {
+    ::HTTP_FILE.with(|file| file.borrow().as_ref().map(|x| variables(x, base)).unwrap_or(Vec::new()))
+}
+
+fn get_selected(m_win: &::MainWindow) -> Option<usize> {
+    m_win.http_file_view.get_selection().get_selected().
+        and_then(|(model, iter)| model.get_value(&iter, 1).get::<i64>()).
+        map(|x| x as usize)
+}
+
+fn refresh(m_win: &::MainWindow, selected: Option<usize>) {
+    let file_label: gtk::Label = gtk_ext::get_gtk_obj_by_id(&m_win.builder, "httpFileLbl");
+    let (path, requests) = ::HTTP_FILE.with(|file| match *file.borrow() {
+        Some(ref file) => (file.path.display().to_string(), file.requests().into_iter().map(|(i, x)| (i, x.name.clone())).collect()),
+        None => (String::new(), Vec::new())
+    });
+
+    file_label.set_text(&path);
+    m_win.http_file_store.clear();
+
+    for (i, name) in requests {
+        let iter = m_win.http_file_store.insert_with_values(None, &[0, 1], &[&name, &(i as i64)]);
+
+        if selected == Some(i) {
+            m_win.http_file_view.get_selection().select_iter(&iter);
+        }
+    }
+}
+
+pub fn block_request(index: usize) -> Option<SavedRequest> {
+    ::HTTP_FILE.with(|file| {
+        file.borrow().as_ref().and_then(|x| x.blocks.get(index)).and_then(|x| x.request.clone())
+    })
+}
+
+fn load_selected(m_win: &::MainWindow) {
+    let index = get_selected(m_win);
+
+    if let Some(request) = index.and_then(block_request) {
+        if collections::has_unsaved_edits(m_win, &config::connect_to_state()) &&
+            !gtk_ext::confirm("Discard the unsaved changes in this tab?", &m_win.window) {
+            return;
+        }
+
+        let editor = m_win.editor();
+
+        request.update_to_editor(&editor);
+        editor.set_http_block(index);
+    }
+}
+
+pub fn open(m_win: &::MainWindow, path: &Path) {
+    match fs::read_to_string(path) {
+        Ok(text) => {
+            ::HTTP_FILE.with(|file| *file.borrow_mut() = Some(parse(&text, path)));
+
+            // Blocks may have moved, so tabs no longer point into the file
+            for tab in m_win.tabs.borrow().items.iter().filter(|x| x.editor.get_http_block().is_some()) {
+                tab.editor.set_http_block(None);
+            }
+
+            refresh(m_win, None);
+        },
+        Err(err) => gtk_ext::show_message(&format!("Cannot open {} - {}", path.display(), err), &m_win.window)
+    };
+}
+
+fn choose_and_open(m_win: &::MainWindow) {
+    if let Some(path) = gtk_ext::choose_files_to_open(&m_win.window, "Open .http file").into_iter().next() {
+        open(m_win, &path);
+    }
+}
+
+fn save_current(m_win: &::MainWindow) {
+    let has_file = ::HTTP_FILE.with(|file| file.borrow().is_some());
+
+    if !has_file {
+        let path = gtk_ext::choose_file_to_save(&m_win.window, "Save request to .http file", &format!("requests{}", DEFAULT_EXTENSION));
+
+        match path {
+            Some(path) => ::HTTP_FILE.with(|file| {
+                *file.borrow_mut() = Some(HttpFile { path, newline: String::from("\n"), blocks: Vec::new() })
+            }),
+            None => return
+        };
+    }
+
+    let editor = m_win.editor();
+    let mut request = SavedRequest::default();
+    request.update_from_editor(&editor);
+    request.name = format!("{} {}", request.method_name(), request.url);
+
+    // Requests that did not come from the file are added to it
+    let target = editor.get_http_block();
+    let result = ::HTTP_FILE.with(|file| {
+        let mut file = file.borrow_mut();
+        let file = file.as_mut().expect("HTTP file must be open");
+        let index = file.set_request(target, &request)?;
+
+        fs::write(&file.path, file.to_text()).
+            map(|_| index).
+            map_err(|err| format!("Cannot save {} - {}", file.path.display(), err))
+    });
+
+    match result {
+        Ok(index) => {
+            editor.set_http_block(Some(index));
+            refresh(m_win, Some(index));
+        },
+        Err(err) => gtk_ext::show_message(&err, &m_win.window)
+    };
+}
+
+pub fn bind_to_window(m_win: &::MainWindow) {
+    let column = gtk::TreeViewColumn::new();
+    let cell = gtk::CellRendererText::new();
+    column.pack_start(&cell, true);
+    column.add_attribute(&cell, "text", 0);
+    m_win.http_file_view.append_column(&column);
+    m_win.http_file_view.set_model(Some(&m_win.http_file_store));
+
+    // Selecting a block, also when saving reselects it, leaves the tab alone
+    m_win.http_file_view.connect_row_activated(gtk_clone!(m_win => move |_, _, _| {
+        load_selected(&m_win);
+    }));
+
+    let open_btn: gtk::Button = gtk_ext::get_gtk_obj_by_id(&m_win.builder, "openHttpFileBtn");
+    let save_btn: gtk::Button = gtk_ext::get_gtk_obj_by_id(&m_win.builder, "saveHttpFileBtn");
+    let reload_btn: gtk::Button = gtk_ext::get_gtk_obj_by_id(&m_win.builder, "reloadHttpFileBtn");
+
+    open_btn.connect_clicked(gtk_clone!(m_win => move |_| choose_and_open(&m_win)));
+    save_btn.connect_clicked(gtk_clone!(m_win => move |_| save_current(&m_win)));
+    reload_btn.connect_clicked(gtk_clone!(m_win => move |_| {
+        let path = ::HTTP_FILE.with(|file| file.borrow().as_ref().map(|x| x.path.clone()));
+        path.map(|x| open(&m_win, &x));
+    }));
+}
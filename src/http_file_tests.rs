@@ -0,0 +1,70 @@
+use std::path::Path;
+use super::http_file::{parse, variables};
+use super::collections::SavedRequest;
+
+#[test]
+pub fn test_parse_http_file() {
+    let file = parse(include_str!("test_data/requests1.http"), Path::new("/work/requests1.http"));
+    let requests = file.requests();
+
+    assert_eq!(requests.iter().map(|x| x.0).collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+
+    let list = requests[0].1;
+    assert_eq!(list.name, "List users");
+    assert_eq!(list.request_method, ::RequestMethod::Get as i32);
+    assert_eq!(list.url, "{{users}}?page=2&size=10");
+    assert_eq!(list.req_headers, "Accept: application/json\nAuthorization: Bearer {{token}}\n");
+    assert_eq!(list.body_type, ::BodyType::Empty as i32);
+
+    let create = requests[1].1;
+    assert_eq!(create.name, "createUser");
+    assert_eq!(create.body_type, ::BodyType::Json as i32);
+    assert_eq!(create.req_body, "{\n  \"name\": \"Ann\"\n}");
+
+    let upload = requests[2].1;
+    assert_eq!(upload.name, "PUT {{users}}/1/avatar");
+    assert_eq!(upload.body_type, ::BodyType::Binary as i32);
+    assert_eq!(upload.body_file, "/work/./avatar.png");
+    assert_eq!(file.blocks[3].version, Some(String::from("HTTP/1.1")));
+
+    let health = requests[3].1;
+    assert_eq!(health.request_method, ::RequestMethod::Get as i32);
+    assert_eq!(health.url, "https://example.com/health");
+
+    let base = vec![(String::from("apiToken"), String::from("secret"))];
+    assert_eq!(variables(&file, &base), vec![
+        (String::from("host"), String::from("https://api.example.com")),
+        (String::from("users"), String::from("https://api.example.com/users")),
+        (String::from("token"), String::from("secret")),
+    ]);
+}
+
+#[test]
+pub fn test_save_http_file() {
+    let text = include_str!("test_data/requests1.http");
+    let mut file = parse(text, Path::new("/work/requests1.http"));
+
+    assert_eq!(file.to_text(), text);
+
+    let mut edited = file.blocks[2].request.clone().unwrap();
+    edited.req_body = String::from("{\"name\": \"Bob\"}");
+    file.set_request(Some(2), &edited).unwrap();
+
+    let added = SavedRequest {
+        name: String::from("Login"),
+        request_method: ::RequestMethod::Post as i32,
+        url: String::from("{{host}}/login"),
+        body_type: ::BodyType::Form as i32,
+        req_body: String::from("user=ann\npass=a b"),
+        ..Default::default()
+    };
+    assert_eq!(file.set_request(None, &added).unwrap(), 5);
+
+    let multipart = SavedRequest { body_type: ::BodyType::Multipart as i32, ..added.clone() };
+    assert!(file.set_request(None, &multipart).is_err());
+
+    let expected = text.replace("{\n  \"name\": \"Ann\"\n}", "{\"name\": \"Bob\"}") +
+        "\n### Login\nPOST {{host}}/login\nContent-Type: application/x-www-form-urlencoded\n\nuser=ann&pass=a%20b\n";
+    assert_eq!(file.to_text(), expected);
+    assert_eq!(file.blocks[2].request.as_ref().unwrap().name, "createUser");
+}
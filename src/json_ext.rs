@@ -0,0 +1,24 @@
+use serde_json::Value;
+
+// Shared by the importers, missing or mistyped fields read as empty
+
+pub fn str_field(value: &Value, key: &str) -> String {
+    value.get(key).and_then(|x| x.as_str()).map(String::from).unwrap_or(String::new())
+}
+
+pub fn scalar_to_string(value: &Value) -> String {
+    match *value {
+        Value::String(ref x) => x.clone(),
+        Value::Null => String::new(),
+        ref x => x.to_string()
+    }
+}
+
+// Like str_field, but numbers and booleans are taken as their text
+pub fn scalar_field(value: &Value, key: &str) -> String {
+    value.get(key).map(scalar_to_string).unwrap_or(String::new())
+}
+
+pub fn json_object(fields: Vec<(&str, Value)>) -> Value {
+    Value::Object(fields.into_iter().map(|(key, value)| (String::from(key), value)).collect())
+}
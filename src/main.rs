@@ -8,248 +8,276 @@ extern crate dirs;
 extern crate glib;
 extern crate mime;
 extern crate rusqlite;
-extern crate quick_xml;
-extern crate html5ever;
+extern crate cairo;
+extern crate encoding_rs;
+extern crate gdk_pixbuf;
+extern crate base64;
+extern crate serde_yaml;
+extern crate auweb_core;
 
 use gio::prelude::*;
 use gtk::prelude::*;
-use gtk::{Builder, Button, Entry, ApplicationWindow, Cast, StyleContextExt, ComboBoxText, MenuItemExt, SettingsExt};
-use std::sync::mpsc::{channel, Receiver};
+use gtk::{Builder, ApplicationWindow, Cast, StyleContextExt, ComboBoxText, MenuItemExt, SettingsExt};
+use std::sync::mpsc::{channel, TryRecvError};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::cell::RefCell;
+use std::rc::Rc;
+use reqwest::header::HeaderMap;
 
 use std::env::args;
-use mime::{Mime};
 
 
-use gtk_ext::{TextWidget};
-use sourceview::{StyleSchemeManagerExt, BufferExt, LanguageManagerExt};
+use sourceview::{StyleSchemeManagerExt};
+
+pub use auweb_core::{Request, Response, RequestMethod, BodyType};
+use auweb_core::{timing, charset};
 
 mod config;
 #[macro_use] mod gtk_ext;
+mod json_ext;
 mod actions;
-mod xml;
-mod html;
+mod collections;
+mod history;
+mod tabs;
+mod editor;
+mod environments;
+mod curl;
+mod snippets;
+mod postman;
+mod har;
+mod openapi;
+mod http_file;
+mod cli;
+mod scripts;
 
 #[derive(Clone)]
 pub struct MainWindow {
     pub builder: Builder,
     pub window: ApplicationWindow,
-    pub perform_btn: Button,
-    pub url_inp: Entry,
-    pub resp_mtx: sourceview::View,
-    pub headers_mtx: sourceview::View,
-    pub method_sel: ComboBoxText,
-    pub req_mtx: sourceview::View,
-    pub resp_headers_mtx: sourceview::View,
     pub lang_manager: sourceview::LanguageManager,
+    pub style_scheme: Option<sourceview::StyleScheme>,
+    pub collections_store: gtk::TreeStore,
+    pub collections_view: gtk::TreeView,
+    pub collections_search_inp: gtk::SearchEntry,
+    pub history_store: gtk::ListStore,
+    pub history_view: gtk::TreeView,
+    pub request_tabs: gtk::Notebook,
+    pub environment_sel: ComboBoxText,
+    pub environments_store: gtk::ListStore,
+    pub environments_view: gtk::TreeView,
+    pub environment_vars_mtx: gtk::TextView,
+    pub http_file_store: gtk::ListStore,
+    pub http_file_view: gtk::TreeView,
+    pub script_console_mtx: gtk::TextView,
+    pub tabs: Rc<RefCell<tabs::Tabs>>,
+    pub config: Rc<RefCell<config::WindowState>>,
 }
 
-pub struct Response
-{
-    pub text: String,
-    pub mime_type: Mime,
-    pub extension: &'static str,
-    pub highlight: Option<String>,
-    pub headers: reqwest::header::HeaderMap,
-}
-
-pub enum RequestMethod {
-    GetWithUri = 1,
-    PostWithForm = 2,
-    PostRaw = 3,
-}
-
-impl<'a> From<&'a mut reqwest::Response> for Response {
-    fn from(x: &'a mut reqwest::Response) -> Self {
-        let response_text: String = x.text().unwrap_or(String::from(""));
-        let mime: Mime = actions::detect_mime_type(x.headers());
-        let extension: &'static str = actions::conv_mime_type_to_extension(&mime);
-
-        Response {
-            text: response_text, 
-            mime_type: mime, 
-            extension,
-            highlight: None,
-            headers: x.headers().clone(),
-        }
-    }
-}
-
-impl Response {
-    fn with_highlight_override(self, highlight: Option<String>) -> Self {
-        Response {
-            text: self.text,
-            mime_type: self.mime_type,
-            extension: self.extension,
-            highlight,
-            headers: self.headers,
-        }
-    }
-}
+// How often a running request is checked for its result
+const RESULT_POLL_MS: u32 = 50;
 
 impl MainWindow {
-    fn apply_css(window: &ApplicationWindow) {
+    pub fn apply_css(container: &gtk::Container) {
         let css = gtk::CssProvider::new();
         match css.load_from_data(include_str!("main.css").as_bytes()) {
             Ok(_) => (),
             Err(x) => panic!(x)
         };
 
-        let cont: &gtk::Container = window.upcast_ref();
-        gtk_ext::traverse_gtk_container(cont, &|x| {
+        gtk_ext::traverse_gtk_container(container, &|x| {
             x.get_style_context().map(|y| y.add_provider(&css, 600));
         });
     }
 
-    fn get_request_method(&self) -> RequestMethod {
-        config::WindowState::conv_to_req_method(MainWindow::get_sel_int_id(&self.method_sel, 1))
+    // The editors of the tab being shown
+    pub fn editor(&self) -> editor::RequestEditor {
+        tabs::current_editor(self)
     }
 
-    fn get_sel_int_id(sel: &gtk::ComboBoxText, def: i32) -> i32 {
-        sel.get_active_id().unwrap_or("".to_owned()).parse::<i32>().unwrap_or(def)
-    }
+    // Every tab gets its own widgets, laid out like the last tab the window was closed with
+    pub fn create_editor(&self) -> editor::RequestEditor {
+        let editor = editor::RequestEditor::new(&self.lang_manager, self.style_scheme.as_ref());
 
-    fn set_request_method(&self, method: RequestMethod) {
-        self.method_sel.set_active_id((method as i32).to_string().as_str());
-    }
+        {
+            let state = self.config.borrow();
+            editor.set_paned_top_left(state.top_left_offset);
+            editor.set_paned_top_right(state.top_right_offset);
+            editor.set_vertical_offset(state.vertical_offset);
+        }
 
-    pub fn set_vertical_offset(&self, x: i32) {
-        let paned_vertical: gtk::Paned = self.builder.get_object("panedParent").expect("panedParent not found");
-        gtk::PanedExt::set_position(&paned_vertical, x);
+        bind_to_editor(self, &editor);
+        editor
     }
 
-    pub fn get_vertical_offset(&self) -> i32 {
-        let paned_vertical: gtk::Paned = self.builder.get_object("panedParent").expect("panedParent not found");
-        gtk::PanedExt::get_position(&paned_vertical)
+    // Script variables win over the .http file ones, which win over the environment
+    fn get_variables(&self) -> Vec<(String, String)> {
+        let mut variables = environments::active_variables(self.config.borrow().active_environment);
+        let file_variables = http_file::active_variables(&variables);
+        variables.extend(file_variables);
+        variables.extend(scripts::session_variables());
+        variables
     }
 
-    pub fn set_paned_top_left(&self, x: i32) {
-        let paned_top_left: gtk::Paned = self.builder.get_object("panedTopLeft").expect("panedTopLeft not found");
-        gtk::PanedExt::set_position(&paned_top_left, x);
+    // Placeholders are substituted from the active environment, unknown ones fail the request
+    fn get_request(&self, editor: &editor::RequestEditor) -> Result<Request, String> {
+        editor.get_request(&self.get_variables(), &self.window)
     }
 
-    pub fn get_paned_top_left(&self) -> i32 {
-        let paned_top_left: gtk::Paned = self.builder.get_object("panedTopLeft").expect("panedTopLeft not found");
-        gtk::PanedExt::get_position(&paned_top_left)
+    pub fn set_sidebar_offset(&self, x: i32) {
+        let paned_sidebar: gtk::Paned = gtk_ext::get_gtk_obj_by_id(&self.builder, "panedSidebar");
+        gtk::PanedExt::set_position(&paned_sidebar, x);
     }
 
-    pub fn set_paned_top_right(&self, x: i32) {
-        let paned_top_right: gtk::Paned = self.builder.get_object("panedTopRight").expect("panedTopRight not found");
-        gtk::PanedExt::set_position(&paned_top_right, x);
+    pub fn get_sidebar_offset(&self) -> i32 {
+        let paned_sidebar: gtk::Paned = gtk_ext::get_gtk_obj_by_id(&self.builder, "panedSidebar");
+        gtk::PanedExt::get_position(&paned_sidebar)
     }
 
-    pub fn get_paned_top_right(&self) -> i32 {
-        let paned_top_right: gtk::Paned = self.builder.get_object("panedTopRight").expect("panedTopRight not found");
-        gtk::PanedExt::get_position(&paned_top_right)
+    pub fn set_window_size(&self, w: i32, h: i32) {
+        let mut alloc: gdk::Rectangle = gtk::WidgetExt::get_allocation(&self.window);
+        alloc.width = w;
+        alloc.height = h;
+        self.window.set_allocation(&alloc);
     }
 
-    pub fn get_req_headers(&self) -> String {
-        self.headers_mtx.get_all_text()
-    }
+    fn quit(&self) -> Inhibit {
+        tabs::store(&self);
 
-    pub fn get_req_body(&self) -> String {
-        self.req_mtx.get_all_text()
-    }
+        {
+            let mut state = self.config.borrow_mut();
+            state.update_from_window(&self);
+            state.write_to_db(&config::connect_to_state());
+        }
 
-    pub fn get_rs_headers(&self) -> String {
-        self.resp_headers_mtx.get_all_text()
+        self.window.destroy();
+        Inhibit(false)
     }
 
-    pub fn get_rs_body(&self) -> String {
-        self.resp_mtx.get_all_text()
-    }
+    fn perform(&self, editor: &editor::RequestEditor) {
+        let mut request = match self.get_request(editor) {
+            Ok(x) => x,
+            Err(err) => {
+                gtk_ext::show_message(&err, &self.window);
+                return;
+            }
+        };
 
-    pub fn set_req_headers(&self, x: &str) {
-        self.headers_mtx.replace_all_text(x);
-    }
+        let mut snapshot = collections::SavedRequest::default();
+        snapshot.update_from_editor(editor);
 
-    pub fn set_req_body(&self, x: &str) {
-        self.req_mtx.replace_all_text(x);
-    }
+        let url_before = request.url.clone();
+        let body_before = request.body.clone();
+        let headers_before = actions::format_headers(&request.headers);
 
-    pub fn set_rs_headers(&self, x: &str) {
-        self.resp_headers_mtx.replace_all_text(x);
-    }
+        if let Err(err) = scripts::run_pre_request(self, &snapshot.pre_script, &mut request, &self.get_variables()) {
+            gtk_ext::show_message(&err, &self.window);
+            return;
+        }
 
-    pub fn set_rs_body(&self, x: &str) {
-        self.resp_mtx.replace_all_text(x);
-    }
+        // History keeps the placeholders, except where the script changed what is sent
+        if request.url != url_before {
+            snapshot.url = request.url.clone();
+        }
 
-    pub fn get_url(&self) -> String {
-        self.url_inp.get_all_text()
-    }
+        if request.body != body_before {
+            snapshot.req_body = request.body.clone();
+        }
 
-    pub fn set_url(&self, x: &str) {
-        self.url_inp.replace_all_text(x);
-    }
+        let headers_after = actions::format_headers(&request.headers);
+        if headers_after != headers_before {
+            snapshot.req_headers = headers_after;
+        }
 
-    pub fn set_window_size(&self, w: i32, h: i32) {
-        let mut alloc: gdk::Rectangle = gtk::WidgetExt::get_allocation(&self.window);
-        alloc.width = w;
-        alloc.height = h;
-        self.window.set_allocation(&alloc);
-    }
+        // Kept for exports, which need the values instead of the placeholders
+        let sent_headers: HeaderMap = request.headers.iter().
+            filter(|x| !actions::is_client_header(x.0)).
+            map(|(name, value)| (name.clone(), value.clone())).
+            collect();
+        let sent = collections::SavedRequest {
+            url: request.url.clone(),
+            req_headers: actions::format_headers(&sent_headers),
+            req_body: request.body.clone(),
+            body_file: request.body_file.clone(),
+            ..snapshot.clone()
+        };
 
-    fn quit(&self) -> Inhibit {
-        CONFIG.with(|conf| {
-            let mut state = conf.borrow_mut();
-            state.update_from_window(&self);
-            state.write_to_db(&config::connect_to_state());
-        });
+        editor.set_request_running(true);
 
-        self.window.destroy();
-        Inhibit(false)
+        let highlight_override = request.headers.
+            get("X-AU-Syntax").
+            map(|x| x.as_bytes()).
+            and_then(|y| std::str::from_utf8(y).ok()).
+            map(|x| String::from(x.trim()));
+
+        let assertions = snapshot.assertions.clone();
+        let record_history = !request.headers.contains_key(history::NO_HISTORY_HEADER);
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let (tx, rx) = channel();
+
+        editor.state.borrow_mut().running = Some(cancelled.clone());
+
+        let active_request = ActiveRequest {
+            cancelled: cancelled.clone(),
+            snapshot,
+            sent,
+            record_history,
+        };
+
+        std::thread::spawn(move|| actions::http_worker(
+            request,
+            highlight_override,
+            assertions,
+            cancelled,
+            tx
+        ));
+
+        // The worker drops its sender without a result once it notices the cancellation
+        let m_win = self.clone();
+        let editor = editor.clone();
+
+        gtk::timeout_add(RESULT_POLL_MS, move || match rx.try_recv() {
+            Ok(result) => {
+                receive(&m_win, &editor, &active_request, result);
+                glib::Continue(false)
+            },
+            Err(TryRecvError::Empty) => glib::Continue(true),
+            Err(TryRecvError::Disconnected) => glib::Continue(false)
+        });
     }
 
     fn new(glade: &str, application: &gtk::Application) -> MainWindow {
         let builder = Builder::new_from_string(glade);
 
         let window: ApplicationWindow = builder.get_object("window1").expect("Couldn't get window1");
-        let perform_btn: Button = builder.get_object("performBtn").expect("performBtn not found");
-        let url_inp: Entry = builder.get_object("urlInp").expect("urlInp not found");
-        let resp_mtx: sourceview::View = builder.get_object("respMtx").expect("respMtx not found");
-        let req_mtx: sourceview::View = builder.get_object("reqMtx").expect("reqMtx not found");
-        let headers_mtx: sourceview::View = builder.get_object("headersMtx").expect("headersMtx not found");
-        let resp_headers_mtx: sourceview::View = builder.get_object("respHeadersMtx").expect("respHeadersMtx not found");
-        let method_sel: ComboBoxText = builder.get_object("methodSel").expect("methodSel not found");
-        let search_bar: gtk::SearchBar = builder.get_object("searchBar").expect("searchBar not found");
-        let search_inp: gtk::SearchEntry = builder.get_object("searchInp").expect("searchInp not found");
         let find_acm: gtk::ImageMenuItem = builder.get_object("findAcm").expect("findAcm not found");
         let quit_acm: gtk::ImageMenuItem = gtk_ext::get_gtk_obj_by_id(&builder, "quitAcm");
-
-        MainWindow::apply_css(&window);
+        let save_body_acm: gtk::MenuItem = gtk_ext::get_gtk_obj_by_id(&builder, "saveBodyAcm");
+        let reinterpret_acm: gtk::MenuItem = gtk_ext::get_gtk_obj_by_id(&builder, "reinterpretAcm");
+        let collections_view: gtk::TreeView = gtk_ext::get_gtk_obj_by_id(&builder, "collectionsView");
+        let collections_search_inp: gtk::SearchEntry = gtk_ext::get_gtk_obj_by_id(&builder, "collectionsSearchInp");
+        let collections_store = gtk::TreeStore::new(&[gtk::Type::String, gtk::Type::I32, gtk::Type::I64, gtk::Type::String]);
+        let history_view: gtk::TreeView = gtk_ext::get_gtk_obj_by_id(&builder, "historyView");
+        let history_store = gtk::ListStore::new(&[gtk::Type::String, gtk::Type::I64]);
+        let request_tabs: gtk::Notebook = gtk_ext::get_gtk_obj_by_id(&builder, "requestTabs");
+        let new_tab_acm: gtk::MenuItem = gtk_ext::get_gtk_obj_by_id(&builder, "newTabAcm");
+        let close_tab_acm: gtk::MenuItem = gtk_ext::get_gtk_obj_by_id(&builder, "closeTabAcm");
+        let paste_curl_acm: gtk::MenuItem = gtk_ext::get_gtk_obj_by_id(&builder, "pasteCurlAcm");
+        let copy_as_acm: gtk::MenuItem = gtk_ext::get_gtk_obj_by_id(&builder, "copyAsAcm");
+        let import_postman_acm: gtk::MenuItem = gtk_ext::get_gtk_obj_by_id(&builder, "importPostmanAcm");
+        let import_har_acm: gtk::MenuItem = gtk_ext::get_gtk_obj_by_id(&builder, "importHarAcm");
+        let import_open_api_acm: gtk::MenuItem = gtk_ext::get_gtk_obj_by_id(&builder, "importOpenApiAcm");
+        let environment_sel: ComboBoxText = gtk_ext::get_gtk_obj_by_id(&builder, "environmentSel");
+        let environments_view: gtk::TreeView = gtk_ext::get_gtk_obj_by_id(&builder, "environmentsView");
+        let environment_vars_mtx: gtk::TextView = gtk_ext::get_gtk_obj_by_id(&builder, "environmentVarsMtx");
+        let environments_store = gtk::ListStore::new(&[gtk::Type::String, gtk::Type::I64]);
+        let http_file_view: gtk::TreeView = gtk_ext::get_gtk_obj_by_id(&builder, "httpFileView");
+        let http_file_store = gtk::ListStore::new(&[gtk::Type::String, gtk::Type::I64]);
+        let script_console_mtx: gtk::TextView = gtk_ext::get_gtk_obj_by_id(&builder, "scriptConsoleMtx");
+
+        MainWindow::apply_css(window.upcast_ref());
         window.set_application(application);
 
-        find_acm.connect_activate(gtk_clone!(search_bar => move |_| {
-            search_bar.set_search_mode(!search_bar.get_search_mode());
-        }));
-        
-        search_inp.connect_activate(move |search_inp| {
-            search_inp.emit_next_match();
-        });
-
-        search_inp.connect_next_match(gtk_clone!(resp_mtx => move |search_inp| {
-            let pattern = search_inp.upcast_ref::<gtk::Entry>().get_all_text();
-            let buffer = resp_mtx.get_buffer().unwrap();
-            let cursor = buffer.get_insert().unwrap();
-            let mut cursor_iter = buffer.get_iter_at_mark(&cursor);
-
-            if cursor_iter == buffer.get_end_iter() {
-                buffer.place_cursor(&buffer.get_start_iter());
-                cursor_iter = buffer.get_iter_at_mark(&cursor);
-            }
-
-            let found = cursor_iter.forward_search(&pattern, gtk::TextSearchFlags::CASE_INSENSITIVE, None);
-
-            match found {
-                Some(pair) => {
-                    buffer.select_range(&pair.1, &pair.0);
-                    resp_mtx.scroll_mark_onscreen(&cursor);
-                },
-                None => buffer.place_cursor(&buffer.get_end_iter())
-            };
-        }));
-
         let style_manager = sourceview::StyleSchemeManager::new();
         let lang_manager = sourceview::LanguageManager::new();
 
@@ -263,40 +291,104 @@ impl MainWindow {
             None => ()
         };
 
-        style_manager
+        let style_scheme = style_manager
             .get_scheme("tomorrownighteighties")
-            .or(style_manager.get_scheme("Classic"))
-            .map(|theme| {
-                gtk_ext::apply_to_src_buf(&resp_mtx, &|x| x.set_style_scheme(&theme));
-                gtk_ext::apply_to_src_buf(&headers_mtx, &|x| x.set_style_scheme(&theme));
-                gtk_ext::apply_to_src_buf(&req_mtx, &|x| x.set_style_scheme(&theme));
-                gtk_ext::apply_to_src_buf(&resp_headers_mtx, &|x| x.set_style_scheme(&theme));
-            });
-
-        lang_manager.
-            guess_language(Some("headers.ini"), None).
-            map(|lang| {
-                gtk_ext::apply_to_src_buf(&headers_mtx, &|x| x.set_language(&lang));
-                gtk_ext::apply_to_src_buf(&resp_headers_mtx, &|x| x.set_language(&lang));
-            });
+            .or(style_manager.get_scheme("Classic"));
 
         let result = MainWindow {
             builder,
             window,
-            perform_btn,
-            url_inp,
-            resp_mtx,
-            headers_mtx,
-            method_sel,
-            req_mtx,
-            resp_headers_mtx,
             lang_manager,
+            style_scheme,
+            collections_store,
+            collections_view,
+            collections_search_inp,
+            history_store,
+            history_view,
+            request_tabs,
+            environment_sel,
+            environments_store,
+            environments_view,
+            environment_vars_mtx,
+            http_file_store,
+            http_file_view,
+            script_console_mtx,
+            tabs: Rc::new(RefCell::new(tabs::Tabs::default())),
+            config: Rc::new(RefCell::new(config::WindowState::read_from_db(&config::connect_to_state()))),
         };
 
+        find_acm.connect_activate(gtk_clone!(result => move |_| {
+            let search_bar = result.editor().search_bar;
+            search_bar.set_search_mode(!search_bar.get_search_mode());
+        }));
+
         quit_acm.connect_activate(gtk_clone!(result => move |_| {
             result.window.destroy();
         }));
 
+        new_tab_acm.connect_activate(gtk_clone!(result => move |_| {
+            tabs::new_tab(&result);
+        }));
+
+        close_tab_acm.connect_activate(gtk_clone!(result => move |_| {
+            tabs::close_current(&result);
+        }));
+
+        paste_curl_acm.connect_activate(gtk_clone!(result => move |_| {
+            curl::paste_command(&result);
+        }));
+
+        import_postman_acm.connect_activate(gtk_clone!(result => move |_| {
+            postman::import_files(&result);
+        }));
+
+        import_har_acm.connect_activate(gtk_clone!(result => move |_| {
+            har::import_files(&result);
+        }));
+
+        import_open_api_acm.connect_activate(gtk_clone!(result => move |_| {
+            openapi::import_files(&result);
+        }));
+
+        save_body_acm.connect_activate(gtk_clone!(result => move |_| {
+            actions::save_response_body(&result);
+        }));
+
+        let reinterpret_menu = gtk::Menu::new();
+        let auto_detect_item = gtk::MenuItem::new_with_label("Auto-detect");
+
+        auto_detect_item.connect_activate(gtk_clone!(result => move |_| {
+            actions::reinterpret_response(&result.editor(), None);
+        }));
+        reinterpret_menu.append(&auto_detect_item);
+
+        for label in charset::REINTERPRET_ENCODINGS.iter() {
+            let item = gtk::MenuItem::new_with_label(label);
+            let encoding = encoding_rs::Encoding::for_label(label.as_bytes());
+
+            item.connect_activate(gtk_clone!(result => move |_| {
+                actions::reinterpret_response(&result.editor(), encoding);
+            }));
+            reinterpret_menu.append(&item);
+        }
+
+        reinterpret_menu.show_all();
+        reinterpret_acm.set_submenu(Some(&reinterpret_menu));
+
+        let copy_as_menu = gtk::Menu::new();
+
+        for &(label, target) in snippets::SNIPPET_TARGETS.iter() {
+            let item = gtk::MenuItem::new_with_label(label);
+
+            item.connect_activate(gtk_clone!(result => move |_| {
+                snippets::copy_as(&result, target);
+            }));
+            copy_as_menu.append(&item);
+        }
+
+        copy_as_menu.show_all();
+        copy_as_acm.set_submenu(Some(&copy_as_menu));
+
         result
     }
 }
@@ -311,66 +403,84 @@ pub fn build_ui(application: &gtk::Application) {
         m_win.quit()
     }));
 
-    let (tx, rx) = channel();
-    GLOBAL.with(|global| {
-        *global.borrow_mut() = Some((m_win.clone(), rx))
-    });
+    m_win.config.borrow().update_to_window(&m_win);
 
-    m_win.perform_btn.connect_clicked(gtk_clone!(m_win => move |_| {
-        m_win.perform_btn.set_sensitive(false);
-        let headers = actions::parse_headers(&m_win.headers_mtx.get_all_text(),
-            &mut |x| gtk_ext::show_message(x, &m_win.window));
+    tabs::bind_to_window(&m_win);
+    collections::bind_to_window(&m_win);
+    history::bind_to_window(&m_win);
+    environments::bind_to_window(&m_win);
+    http_file::bind_to_window(&m_win);
+    scripts::bind_to_window(&m_win);
 
-        let highlight_override = headers.
-            get("X-AU-Syntax").
-            map(|x| x.as_bytes()).
-            and_then(|y| std::str::from_utf8(y).ok()).
-            map(|x| String::from(x.trim()));
+    m_win.window.show_all();
+}
 
-        let request_method = m_win.get_request_method();
-        let url = m_win.url_inp.get_all_text();
-        let req = m_win.req_mtx.get_all_text();
-        let thread_tx = tx.clone();
+// Wires a new tab to the window, its own widgets are already connected by the editor
+pub fn bind_to_editor(m_win: &MainWindow, editor: &editor::RequestEditor) {
+    editor.perform_btn.connect_clicked(gtk_clone!(m_win, editor => move |_| {
+        m_win.perform(&editor);
+    }));
 
-        std::thread::spawn(move|| actions::http_worker(
-            request_method, 
-            &url, 
-            req, 
-            highlight_override, 
-            headers, 
-            thread_tx
-        ));
+    editor.cancel_btn.connect_clicked(gtk_clone!(editor => move |_| {
+        editor.cancel();
     }));
 
-    CONFIG.with(|conf| {
-        conf.borrow().update_to_window(&m_win);
-    });
+    curl::bind_to_editor(m_win, editor);
+}
 
-    m_win.window.show_all();
+pub struct ActiveRequest {
+    pub cancelled: Arc<AtomicBool>,
+    pub snapshot: collections::SavedRequest,
+    pub sent: collections::SavedRequest,
+    pub record_history: bool,
+}
+
+impl ActiveRequest {
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
 }
 
 thread_local!(
-    static GLOBAL: RefCell<Option<(MainWindow, Receiver<Result<Response, String>>)>> = RefCell::new(None);
-    static CONFIG: RefCell<config::WindowState> = RefCell::new(
-        config::WindowState::read_from_db(&config::connect_to_state()));
+    static HTTP_FILE: RefCell<Option<http_file::HttpFile>> = RefCell::new(None);
+    static SCRIPT_VARIABLES: RefCell<Vec<(String, String)>> = RefCell::new(Vec::new());
 );
 
-pub fn receive() -> glib::Continue {
-    GLOBAL.with(|global| {
-        if let Some((ref m_win, ref rx)) = *global.borrow() {
-            if let Ok(result) = rx.try_recv() {
-                match result {
-                    Ok(resp) => actions::output_to_sourceview(&m_win, &resp),
-                    Err(err) => gtk_ext::show_message(&err, &m_win.window),
-                };
-                m_win.perform_btn.set_sensitive(true);
+// Late results of cancelled requests are dropped
+fn receive(m_win: &MainWindow, editor: &editor::RequestEditor, active_request: &ActiveRequest, result: Result<Response, String>) {
+    if active_request.is_cancelled() {
+        return;
+    }
+
+    editor.state.borrow_mut().running = None;
+    editor.set_request_running(false);
+
+    match result {
+        Ok(resp) => {
+            let script = &active_request.snapshot.post_script;
+            scripts::run_post_response(&m_win, script, &resp, &m_win.get_variables());
+
+            if active_request.record_history {
+                history::record(&m_win, &active_request.snapshot, &active_request.sent, &resp);
             }
-        }
-    });
-    glib::Continue(false)
+            actions::show_response(editor, resp);
+        },
+        Err(err) => {
+            if active_request.record_history {
+                history::record_failure(&m_win, &active_request.snapshot, &active_request.sent, &err);
+            }
+            gtk_ext::show_message(&err, &m_win.window);
+        },
+    };
 }
 
 pub fn main() {
+    let arguments: Vec<String> = args().collect();
+
+    if arguments.get(1).map(|x| x == cli::RUN_COMMAND).unwrap_or(false) {
+        std::process::exit(cli::run(&arguments[2..]));
+    }
+
     let application = gtk::Application::new("com.automatl.web_api_client", gio::ApplicationFlags::empty())
         .expect("Initialization failed...");
 
@@ -379,8 +489,37 @@ pub fn main() {
     });
     
     application.connect_activate(|_| {});
-    application.run(&args().collect::<Vec<_>>());
+    application.run(&arguments);
 }
 
 #[cfg(test)]
-mod actions_tests;
\ No newline at end of file
+mod collections_tests;
+#[cfg(test)]
+mod history_tests;
+
+#[cfg(test)]
+mod tabs_tests;
+
+#[cfg(test)]
+mod environments_tests;
+
+#[cfg(test)]
+mod curl_tests;
+
+#[cfg(test)]
+mod snippets_tests;
+
+#[cfg(test)]
+mod postman_tests;
+
+#[cfg(test)]
+mod har_tests;
+
+#[cfg(test)]
+mod openapi_tests;
+
+#[cfg(test)]
+mod http_file_tests;
+
+#[cfg(test)]
+mod cli_tests;
@@ -0,0 +1,528 @@
+use serde_json;
+use serde_json::Value;
+use serde_yaml;
+use std::fs;
+use gtk_ext;
+use json_ext::{str_field, scalar_to_string};
+use config;
+use actions;
+use collections::{self, ImportedFolder, SavedRequest};
+use environments::{self, Environment};
+
+const METHODS: [&'static str; 8] = ["get", "put", "post", "delete", "options", "head", "patch", "trace"];
+
+// Recursive schemas are cut off at this depth when building examples
+const MAX_SCHEMA_DEPTH: usize = 8;
+
+#[derive(Debug, Clone, Default)]
+pub struct SpecImport {
+    pub folder: ImportedFolder,
+    pub environment: Environment,
+    pub warnings: Vec<String>,
+}
+
+struct Spec<'a> {
+    root: &'a Value,
+    swagger2: bool,
+    warnings: Vec<String>,
+    variables: Vec<String>,
+}
+
+fn to_placeholders(path: &str) -> String {
+    path.replace('{', "{{").replace('}', "}}")
+}
+
+impl<'a> Spec<'a> {
+    fn warn(&mut self, message: String) {
+        if !self.warnings.contains(&message) {
+            self.warnings.push(message);
+        }
+    }
+
+    fn add_variable(&mut self, name: &str) {
+        if !self.variables.iter().any(|x| x == name) {
+            self.variables.push(String::from(name));
+        }
+    }
+
+    // Only references into the same document are followed
+    fn resolve(&mut self, value: &'a Value) -> Option<&'a Value> {
+        let mut current = value;
+
+        for _ in 0..MAX_SCHEMA_DEPTH {
+            let reference = match current.get("$ref").and_then(|x| x.as_str()) {
+                Some(x) => x,
+                None => return Some(current)
+            };
+
+            current = match self.root.pointer(reference.trim_start_matches('#')) {
+                Some(x) if reference.starts_with('#') => x,
+                _ => {
+                    self.warn(format!("Reference {} could not be resolved", reference));
+                    return None;
+                }
+            };
+        }
+
+        None
+    }
+
+    fn example_value(&mut self, schema: &'a Value, depth: usize) -> Value {
+        let schema = match self.resolve(schema) {
+            Some(x) if depth < MAX_SCHEMA_DEPTH => x,
+            _ => return Value::Null
+        };
+
+        if let Some(example) = schema.get("example").or(schema.get("default")) {
+            return example.clone();
+        }
+
+        if let Some(first) = schema.get("enum").and_then(|x| x.as_array()).and_then(|x| x.first()) {
+            return first.clone();
+        }
+
+        if let Some(parts) = schema.get("allOf").and_then(|x| x.as_array()) {
+            let mut merged = serde_json::Map::new();
+
+            for part in parts {
+                if let Value::Object(fields) = self.example_value(part, depth + 1) {
+                    merged.extend(fields);
+                }
+            }
+
+            return Value::Object(merged);
+        }
+
+        for key in &["oneOf", "anyOf"] {
+            if let Some(first) = schema.get(*key).and_then(|x| x.as_array()).and_then(|x| x.first()) {
+                return self.example_value(first, depth + 1);
+            }
+        }
+
+        // OpenAPI 3.1 allows a list of types like ["string", "null"]
+        let schema_type = match schema.get("type") {
+            Some(&Value::Array(ref types)) => types.iter().
+                filter_map(|x| x.as_str()).
+                find(|x| *x != "null").
+                unwrap_or("null"),
+            Some(x) => x.as_str().unwrap_or(""),
+            None if schema.get("properties").is_some() => "object",
+            None if schema.get("items").is_some() => "array",
+            None => ""
+        };
+
+        match schema_type {
+            "object" => {
+                let mut fields = serde_json::Map::new();
+
+                if let Some(properties) = schema.get("properties").and_then(|x| x.as_object()) {
+                    for (name, property) in properties {
+                        fields.insert(name.clone(), self.example_value(property, depth + 1));
+                    }
+                } else if let Some(additional) = schema.get("additionalProperties").filter(|x| x.is_object()) {
+                    fields.insert(String::from("key"), self.example_value(additional, depth + 1));
+                }
+
+                Value::Object(fields)
+            },
+            "array" => match schema.get("items") {
+                Some(items) => Value::Array(vec![self.example_value(items, depth + 1)]),
+                None => Value::Array(Vec::new())
+            },
+            "integer" => Value::from(0),
+            "number" => Value::from(0.0),
+            "boolean" => Value::from(false),
+            "string" => Value::from(match str_field(schema, "format").as_str() {
+                "date-time" => "2019-01-01T00:00:00Z",
+                "date" => "2019-01-01",
+                "time" => "00:00:00",
+                "email" => "user@example.com",
+                "uuid" => "00000000-0000-0000-0000-000000000000",
+                "uri" | "url" => "https://example.com",
+                "ipv4" => "127.0.0.1",
+                "ipv6" => "::1",
+                _ => "string"
+            }),
+            _ => Value::Null
+        }
+    }
+
+    // Operation parameters override path level ones with the same name and location
+    fn parameters(&mut self, path_item: &'a Value, operation: &'a Value) -> Vec<&'a Value> {
+        let mut result: Vec<&'a Value> = Vec::new();
+        let lists = [path_item.get("parameters"), operation.get("parameters")];
+
+        for list in lists.iter().filter_map(|x| x.and_then(|y| y.as_array())) {
+            for parameter in list.iter().filter_map(|x| self.resolve(x)) {
+                let key = (str_field(parameter, "name"), str_field(parameter, "in"));
+                result.retain(|x| (str_field(x, "name"), str_field(x, "in")) != key);
+                result.push(parameter);
+            }
+        }
+
+        result
+    }
+
+    fn security_schemes(&self) -> Option<&'a Value> {
+        if self.swagger2 {
+            self.root.get("securityDefinitions")
+        } else {
+            self.root.pointer("/components/securitySchemes")
+        }
+    }
+
+    // Credentials become placeholders named after the security scheme
+    fn apply_security(&mut self, operation: &'a Value, request: &mut SavedRequest, headers: &mut Vec<String>,
+        query: &mut Vec<String>) {
+
+        let requirements = operation.get("security").or(self.root.get("security")).and_then(|x| x.as_array());
+        let requirement = match requirements.and_then(|x| x.first()).and_then(|x| x.as_object()) {
+            Some(x) => x,
+            None => return
+        };
+
+        for name in requirement.keys() {
+            let scheme = match self.security_schemes().and_then(|x| x.get(name.as_str())).and_then(|x| self.resolve(x)) {
+                Some(x) => x,
+                None => {
+                    self.warn(format!("{}: security scheme {} is not defined", request.name, name));
+                    continue;
+                }
+            };
+
+            let placeholder = format!("{{{{{}}}}}", name);
+            let scheme_type = str_field(scheme, "type");
+            let http_scheme = str_field(scheme, "scheme").to_lowercase();
+
+            match scheme_type.as_str() {
+                "apiKey" => match str_field(scheme, "in").as_str() {
+                    "query" => query.push(format!("{}={}", str_field(scheme, "name"), placeholder)),
+                    "cookie" => headers.push(format!("Cookie: {}={}", str_field(scheme, "name"), placeholder)),
+                    _ => headers.push(format!("{}: {}", str_field(scheme, "name"), placeholder))
+                },
+                "basic" => headers.push(format!("Authorization: Basic {}", placeholder)),
+                "http" if http_scheme == "basic" => headers.push(format!("Authorization: Basic {}", placeholder)),
+                "http" if http_scheme == "bearer" => headers.push(format!("Authorization: Bearer {}", placeholder)),
+                "http" => headers.push(format!("Authorization: {} {}", str_field(scheme, "scheme"), placeholder)),
+                "oauth2" | "openIdConnect" => headers.push(format!("Authorization: Bearer {}", placeholder)),
+                x => {
+                    self.warn(format!("{}: {} security is not supported", request.name, x));
+                    continue;
+                }
+            };
+
+            self.add_variable(name);
+        }
+    }
+
+    fn form_fields(&mut self, schema: &'a Value, multipart: bool) -> String {
+        let schema = match self.resolve(schema) {
+            Some(x) => x,
+            None => return String::new()
+        };
+
+        let properties = match schema.get("properties").and_then(|x| x.as_object()) {
+            Some(x) => x,
+            None => return String::new()
+        };
+
+        properties.iter().map(|(name, property)| {
+            let property = self.resolve(property).unwrap_or(property);
+            let format = str_field(property, "format");
+
+            if multipart && (format == "binary" || format == "base64" || str_field(property, "type") == "file") {
+                format!("{}=@{{{{{}}}}}", name, name)
+            } else {
+                format!("{}={}", name, scalar_to_string(&self.example_value(property, 0)))
+            }
+        }).collect::<Vec<_>>().join("\n")
+    }
+
+    fn apply_body(&mut self, request: &mut SavedRequest, headers: &mut Vec<String>, media_type: &str,
+        media: &'a Value) {
+
+        let lower = media_type.to_lowercase();
+
+        let body_type = if lower.starts_with("application/x-www-form-urlencoded") {
+            request.req_body = media.get("schema").map(|x| self.form_fields(x, false)).unwrap_or(String::new());
+            ::BodyType::Form
+        } else if lower.starts_with("multipart/form-data") {
+            request.req_body = media.get("schema").map(|x| self.form_fields(x, true)).unwrap_or(String::new());
+            ::BodyType::Multipart
+        } else {
+            let example = media.get("example").cloned().
+                or_else(|| media.get("examples").and_then(|x| x.as_object()).
+                    and_then(|x| x.values().next()).
+                    and_then(|x| self.resolve(x)).
+                    and_then(|x| x.get("value")).
+                    cloned()).
+                or_else(|| media.get("schema").map(|x| self.example_value(x, 0))).
+                unwrap_or(Value::Null);
+
+            let body_type = actions::raw_body_type(&lower);
+
+            request.req_body = match (&body_type, example) {
+                (_, Value::Null) => String::new(),
+                (&::BodyType::Json, x) => serde_json::to_string_pretty(&x).unwrap_or(String::new()),
+                (_, x) => scalar_to_string(&x)
+            };
+
+            body_type
+        };
+
+        if actions::body_type_content_type(&body_type).map(|x| x != lower).unwrap_or(false) {
+            headers.push(format!("Content-Type: {}", media_type));
+        }
+
+        request.body_type = body_type as i32;
+    }
+
+    fn request_body(&mut self, request: &mut SavedRequest, headers: &mut Vec<String>, operation: &'a Value,
+        parameters: &[&'a Value]) {
+
+        if self.swagger2 {
+            let consumes = operation.get("consumes").or(self.root.get("consumes")).
+                and_then(|x| x.as_array()).
+                and_then(|x| x.first()).
+                and_then(|x| x.as_str()).
+                unwrap_or("application/json");
+
+            if let Some(body) = parameters.iter().cloned().find(|x| str_field(x, "in") == "body") {
+                self.apply_body(request, headers, consumes, body);
+                return;
+            }
+
+            let form: Vec<&'a Value> = parameters.iter().filter(|x| str_field(x, "in") == "formData").cloned().collect();
+
+            if !form.is_empty() {
+                let multipart = consumes.starts_with("multipart/") || form.iter().any(|x| str_field(x, "type") == "file");
+
+                request.req_body = form.into_iter().map(|x| {
+                    let name = str_field(x, "name");
+
+                    if str_field(x, "type") == "file" {
+                        format!("{}=@{{{{{}}}}}", name, name)
+                    } else {
+                        format!("{}={}", name, scalar_to_string(&self.example_value(x, 0)))
+                    }
+                }).collect::<Vec<_>>().join("\n");
+                request.body_type = if multipart { ::BodyType::Multipart as i32 } else { ::BodyType::Form as i32 };
+            }
+
+            return;
+        }
+
+        let content = operation.get("requestBody").
+            and_then(|x| self.resolve(x)).
+            and_then(|x| x.get("content")).
+            and_then(|x| x.as_object());
+
+        let content = match content {
+            Some(x) if !x.is_empty() => x,
+            _ => return
+        };
+
+        // JSON is the most useful starting point, then forms, then whatever is listed first
+        let media = content.iter().find(|x| x.0.to_lowercase().contains("json")).
+            or(content.iter().find(|x| x.0.starts_with("application/x-www-form-urlencoded"))).
+            or(content.iter().find(|x| x.0.starts_with("multipart/form-data"))).
+            or(content.iter().next());
+
+        if let Some((media_type, media)) = media {
+            self.apply_body(request, headers, media_type, media);
+        }
+    }
+
+    fn read_operation(&mut self, path: &str, path_item: &'a Value, method: &str, operation: &'a Value) -> SavedRequest {
+        let mut request = SavedRequest::default();
+        let mut headers = Vec::new();
+        let mut query = Vec::new();
+
+        request.name = [str_field(operation, "summary"), str_field(operation, "operationId")].iter().
+            map(|x| String::from(x.trim())).
+            find(|x| !x.is_empty()).
+            unwrap_or(format!("{} {}", method.to_uppercase(), path));
+
+        let mut tags: Vec<String> = operation.get("tags").and_then(|x| x.as_array()).
+            map(|x| x.iter().filter_map(|y| y.as_str()).map(String::from).collect()).
+            unwrap_or(Vec::new());
+
+        if operation.get("deprecated").and_then(|x| x.as_bool()).unwrap_or(false) {
+            tags.push(String::from("deprecated"));
+        }
+
+        request.tags = tags.join(", ");
+        request.set_method_name(method);
+        request.body_type = ::BodyType::Empty as i32;
+
+        let parameters = self.parameters(path_item, operation);
+
+        for parameter in &parameters {
+            let name = str_field(parameter, "name");
+            let required = parameter.get("required").and_then(|x| x.as_bool()).unwrap_or(false);
+
+            match str_field(parameter, "in").as_str() {
+                "path" => (),
+                "query" if required => query.push(format!("{}={{{{{}}}}}", name, name)),
+                "header" if required => headers.push(format!("{}: {{{{{}}}}}", name, name)),
+                "cookie" if required => headers.push(format!("Cookie: {}={{{{{}}}}}", name, name)),
+                _ => continue
+            };
+
+            self.add_variable(&name);
+        }
+
+        self.apply_security(operation, &mut request, &mut headers, &mut query);
+        self.request_body(&mut request, &mut headers, operation, &parameters);
+
+        request.url = format!("{{{{baseUrl}}}}{}", to_placeholders(path));
+
+        if !query.is_empty() {
+            request.url += &format!("?{}", query.join("&"));
+        }
+
+        request.req_headers = headers.iter().map(|x| format!("{}\n", x)).collect();
+        request
+    }
+
+    fn base_urls(&mut self) -> Vec<String> {
+        let urls = if self.swagger2 {
+            let host = str_field(self.root, "host");
+            let base_path = str_field(self.root, "basePath");
+            let schemes: Vec<String> = self.root.get("schemes").and_then(|x| x.as_array()).
+                map(|x| x.iter().filter_map(|y| y.as_str()).map(String::from).collect()).
+                unwrap_or(vec![String::from("https")]);
+
+            if host.is_empty() {
+                vec![base_path]
+            } else {
+                schemes.iter().map(|x| format!("{}://{}{}", x, host, base_path)).collect()
+            }
+        } else {
+            self.root.get("servers").and_then(|x| x.as_array()).map(|servers| servers.iter().map(|server| {
+                let mut url = str_field(server, "url");
+
+                if let Some(variables) = server.get("variables").and_then(|x| x.as_object()) {
+                    for (name, variable) in variables {
+                        url = url.replace(&format!("{{{}}}", name), &scalar_to_string(variable.get("default").unwrap_or(&Value::Null)));
+                    }
+                }
+
+                url
+            }).collect()).unwrap_or(Vec::new())
+        };
+
+        let urls: Vec<String> = urls.into_iter().map(|x| String::from(x.trim_end_matches('/'))).collect();
+
+        for url in urls.iter().filter(|x| !x.contains("://")) {
+            self.warn(format!("Server URL \"{}\" is relative, set baseUrl to the full address", url));
+        }
+
+        urls
+    }
+}
+
+pub fn parse_document(text: &str) -> Result<Value, String> {
+    serde_json::from_str(text).or_else(|_| serde_yaml::from_str::<Value>(text)).
+        map_err(|err| format!("Neither JSON nor YAML - {}", err))
+}
+
+pub fn parse_spec(text: &str) -> Result<SpecImport, String> {
+    let root = parse_document(text)?;
+    let swagger2 = str_field(&root, "swagger").starts_with("2.");
+
+    if !swagger2 && !str_field(&root, "openapi").starts_with("3.") {
+        return Err(String::from("Neither an OpenAPI 3 nor a Swagger 2 document"));
+    }
+
+    let mut spec = Spec { root: &root, swagger2, warnings: Vec::new(), variables: Vec::new() };
+    let title = root.pointer("/info/title").map(scalar_to_string).filter(|x| !x.trim().is_empty()).
+        unwrap_or(String::from("OpenAPI"));
+    let mut folder = ImportedFolder { name: title.clone(), ..Default::default() };
+
+    let paths = root.get("paths").and_then(|x| x.as_object()).ok_or("The document has no paths")?;
+
+    for (path, path_item) in paths {
+        let path_item = match spec.resolve(path_item) {
+            Some(x) => x,
+            None => continue
+        };
+
+        for method in METHODS.iter() {
+            let operation = match path_item.get(*method) {
+                Some(x) => x,
+                None => continue
+            };
+
+            let request = spec.read_operation(path, path_item, method, operation);
+            let tag = operation.pointer("/tags/0").and_then(|x| x.as_str()).map(String::from);
+
+            match tag {
+                Some(tag) => {
+                    let position = folder.folders.iter().position(|x| x.name == tag).unwrap_or_else(|| {
+                        folder.folders.push(ImportedFolder { name: tag.clone(), ..Default::default() });
+                        folder.folders.len() - 1
+                    });
+
+                    folder.folders[position].requests.push(request);
+                },
+                None => folder.requests.push(request)
+            };
+        }
+    }
+
+    // The first server is active, the others are left commented out to switch by hand
+    let mut variables = String::new();
+
+    for (i, url) in spec.base_urls().iter().enumerate() {
+        variables += &format!("{}baseUrl={}\n", if i == 0 { "" } else { "# " }, url);
+    }
+
+    for name in &spec.variables {
+        variables += &format!("# {}=\n", name);
+    }
+
+    Ok(SpecImport {
+        folder,
+        environment: Environment { id: None, name: title, variables },
+        warnings: spec.warnings,
+    })
+}
+
+pub fn import_files(m_win: &::MainWindow) {
+    let files = gtk_ext::choose_files_to_open(&m_win.window, "Import OpenAPI or Swagger documents");
+
+    if files.is_empty() {
+        return;
+    }
+
+    let connection = config::connect_to_state();
+    let mut request_count = 0;
+    let mut warnings = Vec::new();
+
+    for file in &files {
+        let parsed = fs::read_to_string(file).
+            map_err(|err| err.to_string()).
+            and_then(|text| parse_spec(&text));
+
+        match parsed {
+            Ok(spec) => {
+                request_count += collections::store_imported(&connection, None, &spec.folder);
+                environments::save_environment(&connection, &spec.environment);
+                warnings.extend(spec.warnings.into_iter().map(|x| format!("{}: {}", spec.folder.name, x)));
+            },
+            Err(err) => warnings.push(format!("{}: {}", file.display(), err))
+        };
+    }
+
+    collections::refresh_tree(m_win);
+    environments::refresh(m_win);
+
+    let mut message = format!("Imported {} requests", request_count);
+
+    if !warnings.is_empty() {
+        message += &format!("\n\nNot translated:\n{}", warnings.join("\n"));
+    }
+
+    gtk_ext::show_message(&message, &m_win.window);
+}
@@ -0,0 +1,63 @@
+use super::openapi::parse_spec;
+
+#[test]
+pub fn test_parse_openapi3() {
+    let spec = parse_spec(include_str!("test_data/openapi1.yaml")).unwrap();
+    let folder = &spec.folder;
+
+    assert_eq!(folder.name, "Pet store");
+    assert_eq!(folder.folders.iter().map(|x| x.name.as_str()).collect::<Vec<_>>(), vec!["pets", "photos"]);
+
+    let list = &folder.folders[0].requests[0];
+    assert_eq!(list.name, "List pets");
+    assert_eq!(list.request_method, ::RequestMethod::Get as i32);
+    assert_eq!(list.url, "{{baseUrl}}/pets?limit={{limit}}");
+    assert_eq!(list.req_headers, "X-Request-Id: {{X-Request-Id}}\nX-API-Key: {{apiKey}}\n");
+    assert_eq!(list.body_type, ::BodyType::Empty as i32);
+
+    let create = &folder.folders[0].requests[1];
+    assert_eq!(create.name, "createPet");
+    assert_eq!(create.req_headers, "Authorization: Bearer {{bearerAuth}}\n");
+    assert_eq!(create.body_type, ::BodyType::Json as i32);
+    assert_eq!(create.req_body, "{\n  \"name\": \"string\",\n  \"tag\": \"cat\",\n  \"born\": \"2019-01-01\",\n  \"owner\": null\n}");
+
+    let photo = &folder.folders[1].requests[0];
+    assert_eq!(photo.name, "PUT /pets/{petId}/photo");
+    assert_eq!(photo.tags, "photos, pets, deprecated");
+    assert_eq!(photo.url, "{{baseUrl}}/pets/{{petId}}/photo");
+    assert_eq!(photo.req_headers, "");
+    assert_eq!(photo.body_type, ::BodyType::Multipart as i32);
+    assert_eq!(photo.req_body, "caption=Sleeping\nfile=@{{file}}");
+
+    let health = &folder.requests[0];
+    assert_eq!(health.request_method, ::RequestMethod::Head as i32);
+    assert_eq!(health.url, "{{baseUrl}}/health");
+
+    assert_eq!(spec.environment.name, "Pet store");
+    assert_eq!(spec.environment.variables,
+        "baseUrl=https://eu.example.com/v1\n# baseUrl=http://localhost:8080/v1\n# limit=\n# X-Request-Id=\n# apiKey=\n\
+        # bearerAuth=\n# petId=\n");
+    assert_eq!(spec.warnings, vec!["Reference common.yaml#/Owner could not be resolved"]);
+}
+
+#[test]
+pub fn test_parse_swagger2() {
+    let spec = parse_spec(include_str!("test_data/swagger1.json")).unwrap();
+
+    let update = &spec.folder.requests[0];
+    assert_eq!(update.request_method, ::RequestMethod::Patch as i32);
+    assert_eq!(update.url, "{{baseUrl}}/orders/{{id}}?access_token={{token}}");
+    assert_eq!(update.body_type, ::BodyType::Json as i32);
+    assert_eq!(update.req_body, "{\n  \"quantity\": 2,\n  \"items\": [\n    \"string\"\n  ]\n}");
+
+    let login = &spec.folder.requests[1];
+    assert_eq!(login.req_headers, "Authorization: Basic {{basicAuth}}\n");
+    assert_eq!(login.body_type, ::BodyType::Form as i32);
+    assert_eq!(login.req_body, "user=string\nremember=true");
+
+    assert_eq!(spec.environment.variables,
+        "baseUrl=https://orders.example.com/api\n# baseUrl=http://orders.example.com/api\n# id=\n# token=\n# basicAuth=\n");
+    assert!(spec.warnings.is_empty());
+    assert!(parse_spec("openapi: 3.0.0\ninfo: {title: x}\n").is_err());
+    assert!(parse_spec("{\"foo\": [").is_err());
+}
@@ -0,0 +1,394 @@
+use serde_json;
+use serde_json::Value;
+use base64;
+use std::fs;
+use gtk_ext;
+use json_ext::{scalar_field, scalar_to_string};
+use config;
+use actions;
+use collections::{self, ImportedFolder, SavedRequest};
+use environments::{self, Environment};
+
+#[derive(Debug, Clone, Default)]
+pub struct PostmanImport {
+    pub collections: Vec<ImportedFolder>,
+    pub environments: Vec<Environment>,
+    pub warnings: Vec<String>,
+}
+
+fn is_disabled(value: &Value) -> bool {
+    value.get("disabled").and_then(|x| x.as_bool()).unwrap_or(false) ||
+        !value.get("enabled").and_then(|x| x.as_bool()).unwrap_or(true)
+}
+
+// Postman lists like headers or variables are arrays of {key, value, disabled}
+fn key_values(value: Option<&Value>) -> Vec<(String, String, bool)> {
+    value.and_then(|x| x.as_array()).
+        map(|items| items.iter().
+            map(|x| (scalar_field(x, "key"), scalar_field(x, "value"), !is_disabled(x))).
+            filter(|x| !x.0.is_empty()).
+            collect()).
+        unwrap_or(Vec::new())
+}
+
+fn variables_text(variables: &[(String, String, bool)]) -> String {
+    variables.iter().
+        map(|x| format!("{}{}={}\n", if x.2 { "" } else { "# " }, x.0, x.1)).
+        collect()
+}
+
+// v2.1 stores auth parameters as [{key, value}], v2.0 as a plain object
+fn auth_param(auth: &Value, kind: &str, key: &str) -> String {
+    match auth.get(kind) {
+        Some(&Value::Array(ref items)) => items.iter().
+            find(|x| x.get("key").and_then(|y| y.as_str()) == Some(key)).
+            map(|x| scalar_field(x, "value")).
+            unwrap_or(String::new()),
+        Some(x) => scalar_field(x, key),
+        None => String::new()
+    }
+}
+
+// Only whole path segments are variables, so :id leaves :identifier alone
+pub fn replace_path_variable(url: &str, key: &str, value: &str) -> String {
+    let pattern = format!("/:{}", key);
+    let mut result = String::new();
+    let mut rest = url;
+
+    while let Some(pos) = rest.find(&pattern) {
+        let end = pos + pattern.len();
+        let whole_segment = rest[end..].chars().next().
+            map(|x| x == '/' || x == '?' || x == '#').
+            unwrap_or(true);
+
+        result += &rest[..pos + 1];
+        result += if whole_segment { value } else { &rest[pos + 1..end] };
+        rest = &rest[end..];
+    }
+
+    result + rest
+}
+
+fn read_url(url: Option<&Value>) -> String {
+    let url = match url {
+        Some(&Value::String(ref x)) => return x.clone(),
+        Some(x) => x,
+        None => return String::new()
+    };
+
+    let mut raw = scalar_field(url, "raw");
+
+    if raw.is_empty() {
+        let protocol = scalar_field(url, "protocol");
+        let host = url.get("host").and_then(|x| x.as_array()).
+            map(|x| x.iter().map(scalar_to_string).collect::<Vec<_>>().join(".")).
+            unwrap_or_else(|| scalar_field(url, "host"));
+        let path = url.get("path").and_then(|x| x.as_array()).
+            map(|x| x.iter().map(scalar_to_string).collect::<Vec<_>>().join("/")).
+            unwrap_or_else(|| scalar_field(url, "path"));
+        let query = key_values(url.get("query")).iter().
+            filter(|x| x.2).
+            map(|x| format!("{}={}", x.0, x.1)).
+            collect::<Vec<_>>().
+            join("&");
+
+        if !protocol.is_empty() {
+            raw += &format!("{}://", protocol);
+        }
+
+        raw += &host;
+
+        if !path.is_empty() {
+            raw += &format!("/{}", path.trim_start_matches('/'));
+        }
+
+        if !query.is_empty() {
+            raw += &format!("?{}", query);
+        }
+    }
+
+    // Path variables like :id have no equivalent, so they become {{id}} unless a value is given
+    for (key, value, _) in key_values(url.get("variable")) {
+        let replacement = if value.is_empty() { format!("{{{{{}}}}}", key) } else { value };
+        raw = replace_path_variable(&raw, &key, &replacement);
+    }
+
+    raw
+}
+
+fn apply_auth(auth: &Value, request: &mut SavedRequest, headers: &mut Vec<String>, location: &str,
+    warnings: &mut Vec<String>) {
+
+    match scalar_field(auth, "type").as_str() {
+        "noauth" | "" => (),
+        "basic" => {
+            let credentials = format!("{}:{}", auth_param(auth, "basic", "username"), auth_param(auth, "basic", "password"));
+
+            if credentials.contains("{{") {
+                warnings.push(format!("{}: basic auth credentials use variables and were encoded literally", location));
+            }
+
+            headers.push(format!("Authorization: Basic {}", base64::encode(credentials.as_bytes())));
+        },
+        "bearer" => headers.push(format!("Authorization: Bearer {}", auth_param(auth, "bearer", "token"))),
+        "apikey" => {
+            let key = auth_param(auth, "apikey", "key");
+            let value = auth_param(auth, "apikey", "value");
+
+            if auth_param(auth, "apikey", "in") == "query" {
+                request.url += if request.url.contains('?') { "&" } else { "?" };
+                request.url += &format!("{}={}", key, value);
+            } else {
+                headers.push(format!("{}: {}", key, value));
+            }
+        },
+        x => warnings.push(format!("{}: {} auth is not supported", location, x))
+    };
+}
+
+fn apply_body(body: &Value, request: &mut SavedRequest, headers: &[String], location: &str,
+    warnings: &mut Vec<String>) {
+
+    let content_type = headers.iter().
+        filter_map(|x| x.find(':').map(|pos| (x[..pos].trim(), x[pos + 1..].trim()))).
+        find(|x| x.0.eq_ignore_ascii_case("Content-Type")).
+        map(|x| String::from(x.1));
+
+    let body_type = match scalar_field(body, "mode").as_str() {
+        "raw" => {
+            request.req_body = scalar_field(body, "raw");
+            let language = body.pointer("/options/raw/language").map(scalar_to_string).unwrap_or(String::new());
+
+            match content_type {
+                Some(ref x) => actions::raw_body_type(x),
+                None => actions::raw_body_type(&language)
+            }
+        },
+        "urlencoded" => {
+            request.req_body = key_values(body.get("urlencoded")).iter().
+                filter(|x| x.2).
+                map(|x| format!("{}={}\n", x.0, x.1)).
+                collect();
+            ::BodyType::Form
+        },
+        "formdata" => {
+            let fields = body.get("formdata").and_then(|x| x.as_array()).cloned().unwrap_or(Vec::new());
+            let mut lines = Vec::new();
+
+            for field in fields.iter().filter(|x| !is_disabled(x)) {
+                let key = scalar_field(field, "key");
+                let mut line = if scalar_field(field, "type") == "file" {
+                    let src = match field.get("src") {
+                        Some(&Value::Array(ref items)) => {
+                            if items.len() > 1 {
+                                warnings.push(format!("{}: only the first file of form field {} was kept", location, key));
+                            }
+
+                            items.first().map(scalar_to_string).unwrap_or(String::new())
+                        },
+                        Some(x) => scalar_to_string(x),
+                        None => String::new()
+                    };
+
+                    format!("{}=@{}", key, src)
+                } else {
+                    format!("{}={}", key, scalar_field(field, "value"))
+                };
+
+                let field_type = scalar_field(field, "contentType");
+
+                if !field_type.is_empty() {
+                    line += &format!(";type={}", field_type);
+                }
+
+                lines.push(line);
+            }
+
+            request.req_body = lines.join("\n");
+            ::BodyType::Multipart
+        },
+        "file" => {
+            request.body_file = body.get("file").map(|x| scalar_field(x, "src")).unwrap_or(String::new());
+            ::BodyType::Binary
+        },
+        "graphql" => {
+            let graphql = body.get("graphql").cloned().unwrap_or(Value::Null);
+            let variables = graphql.get("variables").map(scalar_to_string).unwrap_or(String::new());
+            let variables = serde_json::from_str::<Value>(&variables).unwrap_or(Value::Null);
+            let mut payload = serde_json::Map::new();
+            payload.insert(String::from("query"), Value::String(scalar_field(&graphql, "query")));
+            payload.insert(String::from("variables"), variables);
+
+            request.req_body = serde_json::to_string_pretty(&Value::Object(payload)).unwrap_or(String::new());
+            ::BodyType::Json
+        },
+        "" => ::BodyType::Empty,
+        x => {
+            warnings.push(format!("{}: {} body is not supported", location, x));
+            ::BodyType::Empty
+        }
+    };
+
+    request.body_type = body_type as i32;
+}
+
+fn has_scripts(item: &Value) -> bool {
+    item.get("event").and_then(|x| x.as_array()).map(|events| events.iter().any(|event| {
+        match event.pointer("/script/exec") {
+            Some(&Value::Array(ref lines)) => lines.iter().any(|x| !scalar_to_string(x).trim().is_empty()),
+            Some(x) => !scalar_to_string(x).trim().is_empty(),
+            None => false
+        }
+    })).unwrap_or(false)
+}
+
+fn read_request(item: &Value, inherited_auth: &Value, location: &str, warnings: &mut Vec<String>) -> SavedRequest {
+    let mut request = SavedRequest::default();
+    let mut headers: Vec<String> = Vec::new();
+    request.name = scalar_field(item, "name");
+
+    let source = item.get("request").cloned().unwrap_or(Value::Null);
+
+    if let Value::String(ref url) = source {
+        request.set_method_name("GET");
+        request.url = url.clone();
+        request.body_type = ::BodyType::Empty as i32;
+        return request;
+    }
+
+    let method = scalar_field(&source, "method");
+    request.set_method_name(if method.is_empty() { "GET" } else { &method });
+    request.url = read_url(source.get("url"));
+
+    match source.get("header") {
+        Some(&Value::String(ref x)) => headers.extend(x.lines().filter(|y| !y.trim().is_empty()).map(String::from)),
+        x => headers.extend(key_values(x).into_iter().filter(|y| y.2).map(|y| format!("{}: {}", y.0, y.1)))
+    };
+
+    let auth = match source.get("auth") {
+        Some(x) if scalar_field(x, "type") != "inherit" => x.clone(),
+        _ => inherited_auth.clone()
+    };
+
+    apply_auth(&auth, &mut request, &mut headers, location, warnings);
+    apply_body(source.get("body").unwrap_or(&Value::Null), &mut request, &headers, location, warnings);
+
+    request.req_headers = headers.iter().map(|x| format!("{}\n", x)).collect();
+    request
+}
+
+fn read_items(items: &Value, folder: &mut ImportedFolder, inherited_auth: &Value, path: &str,
+    warnings: &mut Vec<String>) {
+
+    for item in items.as_array().map(|x| x.as_slice()).unwrap_or(&[]) {
+        let name = scalar_field(item, "name");
+        let location = if path.is_empty() { name.clone() } else { format!("{} / {}", path, name) };
+
+        if has_scripts(item) {
+            warnings.push(format!("{}: pre-request and test scripts were not imported", location));
+        }
+
+        match item.get("item") {
+            Some(children) => {
+                let auth = item.get("auth").cloned().unwrap_or(inherited_auth.clone());
+                let mut child = ImportedFolder { name, ..Default::default() };
+
+                read_items(children, &mut child, &auth, &location, warnings);
+                folder.folders.push(child);
+            },
+            None => folder.requests.push(read_request(item, inherited_auth, &location, warnings))
+        };
+    }
+}
+
+pub fn parse_collection(root: &Value, result: &mut PostmanImport) {
+    let name = root.pointer("/info/name").map(scalar_to_string).unwrap_or(String::from("Postman collection"));
+    let auth = root.get("auth").cloned().unwrap_or(Value::Null);
+    let mut folder = ImportedFolder { name: name.clone(), ..Default::default() };
+
+    if has_scripts(root) {
+        result.warnings.push(format!("{}: collection scripts were not imported", name));
+    }
+
+    read_items(root.get("item").unwrap_or(&Value::Null), &mut folder, &auth, &name, &mut result.warnings);
+
+    let variables = key_values(root.get("variable"));
+
+    if !variables.is_empty() {
+        result.environments.push(Environment {
+            id: None,
+            name: format!("{} variables", name),
+            variables: variables_text(&variables),
+        });
+    }
+
+    result.collections.push(folder);
+}
+
+pub fn parse_environment(root: &Value, result: &mut PostmanImport) {
+    result.environments.push(Environment {
+        id: None,
+        name: root.get("name").map(scalar_to_string).unwrap_or(String::from("Postman environment")),
+        variables: variables_text(&key_values(root.get("values"))),
+    });
+}
+
+pub fn parse(text: &str, result: &mut PostmanImport) -> Result<(), String> {
+    let root: Value = serde_json::from_str(text).map_err(|err| format!("Not a JSON file - {}", err))?;
+
+    if root.get("item").is_some() {
+        let schema = root.pointer("/info/schema").map(scalar_to_string).unwrap_or(String::new());
+
+        if !schema.is_empty() && !schema.contains("v2.0") && !schema.contains("v2.1") {
+            result.warnings.push(format!("Unknown collection schema {}, imported as v2.1", schema));
+        }
+
+        parse_collection(&root, result);
+        Ok(())
+    } else if root.get("values").is_some() {
+        parse_environment(&root, result);
+        Ok(())
+    } else {
+        Err(String::from("Neither a Postman v2 collection nor an environment"))
+    }
+}
+
+pub fn import_files(m_win: &::MainWindow) {
+    let files = gtk_ext::choose_files_to_open(&m_win.window, "Import Postman collections and environments");
+
+    if files.is_empty() {
+        return;
+    }
+
+    let mut result = PostmanImport::default();
+
+    for file in &files {
+        let parsed = fs::read_to_string(file).
+            map_err(|err| err.to_string()).
+            and_then(|text| parse(&text, &mut result));
+
+        if let Err(err) = parsed {
+            result.warnings.push(format!("{}: {}", file.display(), err));
+        }
+    }
+
+    let connection = config::connect_to_state();
+    let request_count: usize = result.collections.iter().
+        map(|x| collections::store_imported(&connection, None, x)).
+        sum();
+
+    for environment in &result.environments {
+        environments::save_environment(&connection, environment);
+    }
+
+    collections::refresh_tree(m_win);
+    environments::refresh(m_win);
+
+    let mut message = format!("Imported {} requests and {} environments", request_count, result.environments.len());
+
+    if !result.warnings.is_empty() {
+        message += &format!("\n\nNot translated:\n{}", result.warnings.join("\n"));
+    }
+
+    gtk_ext::show_message(&message, &m_win.window);
+}
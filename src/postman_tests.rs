@@ -0,0 +1,60 @@
+use super::postman::{parse, replace_path_variable, PostmanImport};
+
+#[test]
+pub fn test_parse_collection() {
+    let mut result = PostmanImport::default();
+    parse(include_str!("test_data/postman1.json"), &mut result).unwrap();
+
+    let root = &result.collections[0];
+    assert_eq!(root.name, "Partner API");
+    assert_eq!(root.folders[0].name, "Users");
+
+    let get_user = &root.folders[0].requests[0];
+    assert_eq!(get_user.request_method, ::RequestMethod::Get as i32);
+    assert_eq!(get_user.url, "{{base}}/users/{{id}}?verbose=true");
+    assert_eq!(get_user.req_headers, "Accept: application/json\nAuthorization: Bearer {{token}}\n");
+    assert_eq!(get_user.body_type, ::BodyType::Empty as i32);
+
+    let create_user = &root.folders[0].requests[1];
+    assert_eq!(create_user.body_type, ::BodyType::Json as i32);
+    assert_eq!(create_user.req_body, "{\"name\": \"Ann\"}");
+    assert_eq!(create_user.req_headers, "Authorization: Basic YWRtaW46cGFzcw==\n");
+
+    let login = &root.requests[0];
+    assert_eq!(login.url, "https://auth.example.com/login");
+    assert_eq!(login.body_type, ::BodyType::Form as i32);
+    assert_eq!(login.req_body, "user=ann\n");
+    assert_eq!(login.req_headers, "");
+
+    let upload = &root.requests[1];
+    assert_eq!(upload.body_type, ::BodyType::Multipart as i32);
+    assert_eq!(upload.req_body, "title=Report\nfile=@/tmp/report.pdf;type=application/pdf");
+
+    assert_eq!(result.environments[0].name, "Partner API variables");
+    assert_eq!(result.environments[0].variables, "base=https://api.example.com\ntoken=secret\n");
+    assert_eq!(result.warnings, vec![
+        "Partner API / Users / Create user: pre-request and test scripts were not imported",
+        "Partner API / Upload: oauth2 auth is not supported",
+    ]);
+}
+
+#[test]
+pub fn test_parse_environment() {
+    let mut result = PostmanImport::default();
+    parse(include_str!("test_data/postman_env1.json"), &mut result).unwrap();
+
+    assert!(result.collections.is_empty());
+    assert_eq!(result.environments[0].name, "Staging");
+    assert_eq!(result.environments[0].variables, "base=https://staging.example.com\n# token=abc\n");
+    assert!(parse("{\"foo\": 1}", &mut result).is_err());
+    assert!(parse("not json", &mut result).is_err());
+}
+
+#[test]
+pub fn test_replace_path_variable() {
+    assert_eq!(replace_path_variable("{{base}}/users/:id", "id", "{{id}}"), "{{base}}/users/{{id}}");
+    assert_eq!(replace_path_variable("/users/:id/posts/:idx?id=:id#:id", "id", "7"), "/users/7/posts/:idx?id=:id#:id");
+    assert_eq!(replace_path_variable("/users/:id?x=1", "id", "7"), "/users/7?x=1");
+    assert_eq!(replace_path_variable("/users/:id#top", "id", "7"), "/users/7#top");
+    assert_eq!(replace_path_variable("/users/:identifier", "id", "7"), "/users/:identifier");
+}
@@ -0,0 +1,83 @@
+use gtk;
+use gtk::prelude::*;
+use gtk_ext;
+use gtk_ext::{TextWidget};
+use auweb_core::scripting::{self, ScriptResult};
+
+// Variables set by scripts live until the application exits, later ones replace earlier ones
+pub fn session_variables() -> Vec<(String, String)> {
+    ::SCRIPT_VARIABLES.with(|vars| vars.borrow().clone())
+}
+
+fn remember_variables(changed: &[(String, String)]) {
+    ::SCRIPT_VARIABLES.with(|vars| {
+        let mut vars = vars.borrow_mut();
+
+        for variable in changed {
+            vars.retain(|x| x.0 != variable.0);
+            vars.push(variable.clone());
+        }
+    });
+}
+
+fn write_to_console(m_win: &::MainWindow, title: &str, result: &ScriptResult) {
+    let mut text = format!("{}\n", title);
+
+    for line in &result.log {
+        text += &format!("  {}\n", line);
+    }
+
+    for variable in &result.variables {
+        text += &format!("  vars.{} = {}\n", variable.0, variable.1);
+    }
+
+    if let Some(ref err) = result.error {
+        text += &format!("  {}\n", err);
+    }
+
+    m_win.script_console_mtx.append_text(&text);
+
+    if let Some(buffer) = m_win.script_console_mtx.get_buffer() {
+        m_win.script_console_mtx.scroll_to_iter(&mut buffer.get_end_iter(), 0.0, false, 0.0, 1.0);
+    }
+}
+
+// An error means the request must not be sent
+pub fn run_pre_request(m_win: &::MainWindow, script: &str, request: &mut ::Request, variables: &[(String, String)]) -> Result<(), String> {
+    if script.trim().is_empty() {
+        return Ok(());
+    }
+
+    let result = scripting::run_pre_request(script, request, variables);
+    write_to_console(m_win, &format!("Pre-request {}", request.url), &result);
+    remember_variables(&result.variables);
+
+    match result.error {
+        Some(err) => Err(err),
+        None => Ok(())
+    }
+}
+
+// The response is shown either way, so errors only go to the console
+pub fn run_post_response(m_win: &::MainWindow, script: &str, resp: &::Response, variables: &[(String, String)]) {
+    if script.trim().is_empty() {
+        return;
+    }
+
+    let result = scripting::run_post_response(script, resp, variables);
+    write_to_console(m_win, &format!("Post-response {} {}", resp.status, resp.url), &result);
+    remember_variables(&result.variables);
+}
+
+pub fn bind_to_window(m_win: &::MainWindow) {
+    let clear_btn: gtk::Button = gtk_ext::get_gtk_obj_by_id(&m_win.builder, "clearConsoleBtn");
+    let clear_vars_btn: gtk::Button = gtk_ext::get_gtk_obj_by_id(&m_win.builder, "clearScriptVarsBtn");
+
+    clear_btn.connect_clicked(gtk_clone!(m_win => move |_| {
+        m_win.script_console_mtx.clear_all_text();
+    }));
+
+    clear_vars_btn.connect_clicked(move |_| {
+        ::SCRIPT_VARIABLES.with(|vars| vars.borrow_mut().clear());
+    });
+}
@@ -0,0 +1,452 @@
+use reqwest::header::{CONTENT_TYPE};
+use serde_json;
+use std::path::Path;
+use gdk;
+use gtk;
+use gtk_ext;
+use actions::{self, MultipartField};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SnippetTarget {
+    Curl,
+    Httpie,
+    Python,
+    Fetch,
+    Reqwest,
+}
+
+pub const SNIPPET_TARGETS: [(&'static str, SnippetTarget); 5] = [
+    ("curl", SnippetTarget::Curl),
+    ("HTTPie", SnippetTarget::Httpie),
+    ("Python requests", SnippetTarget::Python),
+    ("JavaScript fetch", SnippetTarget::Fetch),
+    ("Rust reqwest", SnippetTarget::Reqwest),
+];
+
+enum SnippetBody {
+    Empty,
+    Text(String),
+    Form(Vec<(String, String)>),
+    File(String),
+    Multipart(Vec<MultipartField>),
+}
+
+struct SnippetRequest {
+    method: String,
+    url: String,
+    headers: Vec<(String, String)>,
+    body: SnippetBody,
+}
+
+// Collects what http_worker would send, without the X-AU-* headers that only steer this client
+fn prepare(request: &::Request) -> Result<SnippetRequest, String> {
+    let method = actions::conv_to_http_method(&request.method, &request.custom_method)?;
+    let mut headers: Vec<(String, String)> = request.headers.iter().
+        filter(|x| !actions::is_client_header(x.0)).
+        map(|x| (String::from(x.0.as_str()), String::from(x.1.to_str().unwrap_or("")))).
+        collect();
+
+    let body = match request.body_type {
+        ::BodyType::Empty => SnippetBody::Empty,
+        ::BodyType::Form => SnippetBody::Form(actions::create_post_req_data(&request.body).iter().
+            filter(|x| !x.0.is_empty() || !x.1.is_empty()).
+            map(|x| (String::from(x.0), String::from(x.1))).
+            collect()),
+        ::BodyType::Binary => SnippetBody::File(request.body_file.clone()),
+        ::BodyType::Multipart => SnippetBody::Multipart(actions::create_multipart_fields(&request.body)?),
+        _ => SnippetBody::Text(request.body.clone())
+    };
+
+    let needs_content_type = match body {
+        SnippetBody::Text(_) | SnippetBody::File(_) => !request.headers.contains_key(CONTENT_TYPE),
+        _ => false
+    };
+
+    if needs_content_type {
+        actions::body_type_content_type(&request.body_type).
+            map(|x| headers.push((String::from(CONTENT_TYPE.as_str()), String::from(x))));
+    }
+
+    Ok(SnippetRequest {
+        method: String::from(method.as_str()),
+        url: request.url.clone(),
+        headers,
+        body,
+    })
+}
+
+pub fn shell_quote(text: &str) -> String {
+    let is_plain = !text.is_empty() && text.chars().all(|x| x.is_ascii_alphanumeric() || "_-./:=@%+,".contains(x));
+
+    if is_plain {
+        String::from(text)
+    } else {
+        format!("'{}'", text.replace("'", "'\\''"))
+    }
+}
+
+// JSON string literals are valid in both Python and JavaScript
+fn quote(text: &str) -> String {
+    serde_json::to_string(text).unwrap_or(String::from("\"\""))
+}
+
+fn rust_quote(text: &str) -> String {
+    format!("{:?}", text)
+}
+
+fn file_name_of(field: &MultipartField) -> String {
+    field.file_name.clone().unwrap_or_else(|| {
+        field.file_path.as_ref().
+            and_then(|x| Path::new(x).file_name()).
+            and_then(|x| x.to_str()).
+            map(String::from).
+            unwrap_or(String::new())
+    })
+}
+
+fn curl_quote(text: &str) -> String {
+    format!("\"{}\"", text.replace("\\", "\\\\").replace("\"", "\\\""))
+}
+
+// Inside -F a ; or , ends the file name, unless it is double quoted
+fn curl_form_word(text: &str) -> String {
+    if text.contains(|x| x == ';' || x == ',' || x == '"') {
+        curl_quote(text)
+    } else {
+        String::from(text)
+    }
+}
+
+// Text parts go through --form-string, which sends a leading < or @ and any ;type= literally
+fn curl_form_option(field: &MultipartField) -> String {
+    if field.file_path.is_none() && field.mime_type.is_none() {
+        return format!("--form-string {}", shell_quote(&format!("{}={}", field.name, field.value)));
+    }
+
+    let mut spec = match field.file_path {
+        Some(ref path) => format!("{}=@{}", field.name, curl_form_word(path)),
+        // --form-string can't carry a type, a quoted -F value is taken literally too
+        None => format!("{}={}", field.name, curl_quote(&field.value))
+    };
+
+    field.file_name.as_ref().map(|x| spec += &format!(";filename={}", curl_form_word(x)));
+    field.mime_type.as_ref().map(|x| spec += &format!(";type={}", x));
+
+    format!("-F {}", shell_quote(&spec))
+}
+
+fn to_curl(request: &SnippetRequest) -> String {
+    let mut command = vec![String::from("curl")];
+    let mut options: Vec<String> = Vec::new();
+    let has_body = match request.body { SnippetBody::Empty => false, _ => true };
+
+    if request.method == "HEAD" {
+        command.push(String::from("--head"));
+    } else if request.method != "GET" || has_body {
+        command.push(format!("-X {}", shell_quote(&request.method)));
+    }
+
+    command.push(shell_quote(&request.url));
+
+    for header in &request.headers {
+        options.push(format!("-H {}", shell_quote(&format!("{}: {}", header.0, header.1))));
+    }
+
+    match request.body {
+        SnippetBody::Empty => (),
+        SnippetBody::Text(ref text) => options.push(format!("--data-raw {}", shell_quote(text))),
+        SnippetBody::Form(ref pairs) => {
+            for pair in pairs {
+                options.push(format!("--data-urlencode {}", shell_quote(&format!("{}={}", pair.0, pair.1))));
+            }
+        },
+        SnippetBody::File(ref path) => options.push(format!("--data-binary {}", shell_quote(&format!("@{}", path)))),
+        SnippetBody::Multipart(ref fields) => {
+            for field in fields {
+                options.push(curl_form_option(field));
+            }
+        }
+    };
+
+    let mut lines = vec![command.join(" ")];
+    lines.extend(options);
+
+    lines.join(" \\\n  ")
+}
+
+// HTTPie splits items at the first unescaped separator, so these are backslash escaped
+fn httpie_escape(text: &str) -> String {
+    let mut escaped = String::new();
+
+    for x in text.chars() {
+        if "\\=:@;".contains(x) {
+            escaped.push('\\');
+        }
+
+        escaped.push(x);
+    }
+
+    escaped
+}
+
+fn to_httpie(request: &SnippetRequest) -> String {
+    let mut command = vec![String::from("http")];
+    let mut items: Vec<String> = Vec::new();
+    let mut input = String::new();
+
+    match request.body {
+        SnippetBody::Text(ref text) => command.push(format!("--raw {}", shell_quote(text))),
+        SnippetBody::Form(_) => command.push(String::from("--form")),
+        SnippetBody::Multipart(_) => command.push(String::from("--multipart")),
+        _ => ()
+    };
+
+    command.push(shell_quote(&request.method));
+    command.push(shell_quote(&request.url));
+
+    for header in &request.headers {
+        let item = if header.1.is_empty() {
+            format!("{};", httpie_escape(&header.0))
+        } else {
+            format!("{}:{}", httpie_escape(&header.0), httpie_escape(&header.1))
+        };
+
+        items.push(shell_quote(&item));
+    }
+
+    match request.body {
+        SnippetBody::Form(ref pairs) => {
+            for pair in pairs {
+                items.push(shell_quote(&format!("{}={}", httpie_escape(&pair.0), httpie_escape(&pair.1))));
+            }
+        },
+        SnippetBody::Multipart(ref fields) => {
+            for field in fields {
+                let item = match field.file_path {
+                    Some(ref path) => format!("{}@{}", httpie_escape(&field.name), httpie_escape(path)),
+                    None => format!("{}={}", httpie_escape(&field.name), httpie_escape(&field.value))
+                };
+
+                let item = match field.mime_type {
+                    Some(ref mime) if field.file_path.is_some() => format!("{};type={}", item, mime),
+                    _ => item
+                };
+
+                items.push(shell_quote(&item));
+            }
+        },
+        SnippetBody::File(ref path) => input = format!(" < {}", shell_quote(path)),
+        _ => ()
+    };
+
+    let mut lines = vec![command.join(" ")];
+    lines.extend(items);
+
+    lines.join(" \\\n  ") + input.as_str()
+}
+
+fn to_python(request: &SnippetRequest) -> String {
+    let mut lines = vec![String::from("import requests"), String::new(), format!("url = {}", quote(&request.url))];
+    let mut arguments = vec![quote(&request.method), String::from("url")];
+
+    if !request.headers.is_empty() {
+        lines.push(String::from("headers = {"));
+
+        for header in &request.headers {
+            lines.push(format!("    {}: {},", quote(&header.0), quote(&header.1)));
+        }
+
+        lines.push(String::from("}"));
+        arguments.push(String::from("headers=headers"));
+    }
+
+    match request.body {
+        SnippetBody::Empty => (),
+        SnippetBody::Text(ref text) => {
+            lines.push(format!("data = {}", quote(text)));
+            arguments.push(String::from("data=data.encode(\"utf-8\")"));
+        },
+        SnippetBody::Form(ref pairs) => {
+            lines.push(String::from("data = ["));
+
+            for pair in pairs {
+                lines.push(format!("    ({}, {}),", quote(&pair.0), quote(&pair.1)));
+            }
+
+            lines.push(String::from("]"));
+            arguments.push(String::from("data=data"));
+        },
+        SnippetBody::File(ref path) => {
+            lines.push(format!("data = open({}, \"rb\")", quote(path)));
+            arguments.push(String::from("data=data"));
+        },
+        SnippetBody::Multipart(ref fields) => {
+            lines.push(String::from("files = ["));
+
+            for field in fields {
+                let mut parts = match field.file_path {
+                    Some(ref path) => vec![quote(&file_name_of(field)), format!("open({}, \"rb\")", quote(path))],
+                    None => vec![String::from("None"), quote(&field.value)]
+                };
+
+                field.mime_type.as_ref().map(|x| parts.push(quote(x)));
+                lines.push(format!("    ({}, ({})),", quote(&field.name), parts.join(", ")));
+            }
+
+            lines.push(String::from("]"));
+            arguments.push(String::from("files=files"));
+        }
+    };
+
+    lines.push(String::new());
+    lines.push(format!("response = requests.request({})", arguments.join(", ")));
+    lines.push(String::new());
+    lines.push(String::from("print(response.status_code)"));
+    lines.push(String::from("print(response.text)"));
+
+    lines.join("\n")
+}
+
+fn to_fetch(request: &SnippetRequest) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    let mut options = vec![format!("  method: {}", quote(&request.method))];
+    let uses_files = match request.body {
+        SnippetBody::File(_) => true,
+        SnippetBody::Multipart(ref fields) => fields.iter().any(|x| x.file_path.is_some()),
+        _ => false
+    };
+
+    if uses_files {
+        lines.push(String::from("const fs = require(\"fs\");"));
+        lines.push(String::new());
+    }
+
+    if !request.headers.is_empty() {
+        let headers = request.headers.iter().
+            map(|x| format!("    {}: {}", quote(&x.0), quote(&x.1))).
+            collect::<Vec<_>>();
+
+        options.push(format!("  headers: {{\n{}\n  }}", headers.join(",\n")));
+    }
+
+    match request.body {
+        SnippetBody::Empty => (),
+        SnippetBody::Text(ref text) => options.push(format!("  body: {}", quote(text))),
+        SnippetBody::Form(ref pairs) => {
+            let pairs = pairs.iter().
+                map(|x| format!("    [{}, {}]", quote(&x.0), quote(&x.1))).
+                collect::<Vec<_>>();
+
+            options.push(format!("  body: new URLSearchParams([\n{}\n  ])", pairs.join(",\n")));
+        },
+        SnippetBody::File(ref path) => options.push(format!("  body: fs.readFileSync({})", quote(path))),
+        SnippetBody::Multipart(ref fields) => {
+            lines.push(String::from("const form = new FormData();"));
+
+            for field in fields {
+                let type_option = field.mime_type.as_ref().
+                    map(|x| format!(", {{ type: {} }}", quote(x))).
+                    unwrap_or(String::new());
+
+                let line = match field.file_path {
+                    Some(ref path) => format!("form.append({}, new Blob([fs.readFileSync({})]{}), {});",
+                        quote(&field.name), quote(path), type_option, quote(&file_name_of(field))),
+                    None if field.mime_type.is_some() => format!("form.append({}, new Blob([{}]{}));",
+                        quote(&field.name), quote(&field.value), type_option),
+                    None => format!("form.append({}, {});", quote(&field.name), quote(&field.value))
+                };
+
+                lines.push(line);
+            }
+
+            lines.push(String::new());
+            options.push(String::from("  body: form"));
+        }
+    };
+
+    lines.push(format!("const response = await fetch({}, {{\n{}\n}});", quote(&request.url), options.join(",\n")));
+    lines.push(String::new());
+    lines.push(String::from("console.log(response.status);"));
+    lines.push(String::from("console.log(await response.text());"));
+
+    lines.join("\n")
+}
+
+fn to_reqwest(request: &SnippetRequest) -> String {
+    let standard_methods = ["GET", "POST", "PUT", "PATCH", "DELETE", "HEAD", "OPTIONS", "TRACE"];
+    let method = if standard_methods.contains(&request.method.as_str()) {
+        format!("reqwest::Method::{}", request.method)
+    } else {
+        format!("reqwest::Method::from_bytes(b{})?", rust_quote(&request.method))
+    };
+
+    let mut lines = vec![
+        String::from("fn main() -> Result<(), Box<dyn std::error::Error>> {"),
+        String::from("    let client = reqwest::blocking::Client::new();"),
+    ];
+    let mut calls = vec![format!("    let response = client.request({}, {})", method, rust_quote(&request.url))];
+
+    for header in &request.headers {
+        calls.push(format!("        .header({}, {})", rust_quote(&header.0), rust_quote(&header.1)));
+    }
+
+    match request.body {
+        SnippetBody::Empty => (),
+        SnippetBody::Text(ref text) => calls.push(format!("        .body({})", rust_quote(text))),
+        SnippetBody::Form(ref pairs) => {
+            let pairs = pairs.iter().
+                map(|x| format!("({}, {})", rust_quote(&x.0), rust_quote(&x.1))).
+                collect::<Vec<_>>();
+
+            calls.push(format!("        .form(&[{}])", pairs.join(", ")));
+        },
+        SnippetBody::File(ref path) => calls.push(format!("        .body(std::fs::read({})?)", rust_quote(path))),
+        SnippetBody::Multipart(ref fields) => {
+            lines.push(String::from("    let form = reqwest::blocking::multipart::Form::new()"));
+
+            for field in fields {
+                let mut part = match field.file_path {
+                    Some(ref path) => format!("reqwest::blocking::multipart::Part::file({})?.file_name({})",
+                        rust_quote(path), rust_quote(&file_name_of(field))),
+                    None => format!("reqwest::blocking::multipart::Part::text({})", rust_quote(&field.value))
+                };
+
+                field.mime_type.as_ref().map(|x| part += &format!(".mime_str({})?", rust_quote(x)));
+                lines.push(format!("        .part({}, {})", rust_quote(&field.name), part));
+            }
+
+            lines.last_mut().map(|x| x.push(';'));
+            lines.push(String::new());
+            calls.push(String::from("        .multipart(form)"));
+        }
+    };
+
+    calls.push(String::from("        .send()?;"));
+    lines.extend(calls);
+    lines.push(String::new());
+    lines.push(String::from("    println!(\"{}\", response.status());"));
+    lines.push(String::from("    println!(\"{}\", response.text()?);"));
+    lines.push(String::from("    Ok(())"));
+    lines.push(String::from("}"));
+
+    lines.join("\n")
+}
+
+pub fn generate(target: SnippetTarget, request: &::Request) -> Result<String, String> {
+    let request = prepare(request)?;
+
+    Ok(match target {
+        SnippetTarget::Curl => to_curl(&request),
+        SnippetTarget::Httpie => to_httpie(&request),
+        SnippetTarget::Python => to_python(&request),
+        SnippetTarget::Fetch => to_fetch(&request),
+        SnippetTarget::Reqwest => to_reqwest(&request),
+    })
+}
+
+pub fn copy_as(m_win: &::MainWindow, target: SnippetTarget) {
+    match m_win.get_request(&m_win.editor()).and_then(|x| generate(target, &x)) {
+        Ok(snippet) => gtk::Clipboard::get(&gdk::SELECTION_CLIPBOARD).set_text(&snippet),
+        Err(err) => gtk_ext::show_message(&err, &m_win.window)
+    };
+}
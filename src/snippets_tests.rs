@@ -0,0 +1,93 @@
+use reqwest::header::{HeaderMap, HeaderValue, CONTENT_TYPE, AUTHORIZATION};
+use super::snippets::{generate, shell_quote, SnippetTarget};
+
+fn json_request() -> ::Request {
+    let mut headers = HeaderMap::new();
+    headers.insert(AUTHORIZATION, HeaderValue::from_static("Bearer it's"));
+    headers.insert("X-AU-Syntax", HeaderValue::from_static("json"));
+
+    ::Request {
+        method: ::RequestMethod::Post,
+        custom_method: String::new(),
+        url: String::from("https://example.com/items?a=1&b=2"),
+        headers,
+        body_type: ::BodyType::Json,
+        body: String::from("{\"name\": \"O'Brien\"}"),
+        body_file: String::new(),
+        decompress: true,
+    }
+}
+
+#[test]
+pub fn test_shell_quote() {
+    assert_eq!(shell_quote("https://example.com/a"), "https://example.com/a");
+    assert_eq!(shell_quote("a b"), "'a b'");
+    assert_eq!(shell_quote("it's"), "'it'\\''s'");
+    assert_eq!(shell_quote(""), "''");
+}
+
+#[test]
+pub fn test_generate_json_post() {
+    let request = json_request();
+
+    assert_eq!(generate(SnippetTarget::Curl, &request).unwrap(), include_str!("test_data/snippet_curl").trim_end());
+    assert_eq!(generate(SnippetTarget::Httpie, &request).unwrap(), include_str!("test_data/snippet_httpie").trim_end());
+    assert_eq!(generate(SnippetTarget::Python, &request).unwrap(), include_str!("test_data/snippet_python").trim_end());
+    assert_eq!(generate(SnippetTarget::Fetch, &request).unwrap(), include_str!("test_data/snippet_fetch").trim_end());
+    assert_eq!(generate(SnippetTarget::Reqwest, &request).unwrap(), include_str!("test_data/snippet_reqwest").trim_end());
+}
+
+#[test]
+pub fn test_generate_form_and_multipart() {
+    let mut request = json_request();
+    request.headers = HeaderMap::new();
+    request.body_type = ::BodyType::Form;
+    request.body = String::from("q=a b\nlang=en");
+
+    assert_eq!(generate(SnippetTarget::Curl, &request).unwrap(),
+        "curl -X POST 'https://example.com/items?a=1&b=2' \\\n  --data-urlencode 'q=a b' \\\n  --data-urlencode lang=en");
+
+    request.body_type = ::BodyType::Multipart;
+    request.body = String::from("title=Cat\nphoto=@/tmp/cat.png;type=image/png");
+
+    assert_eq!(generate(SnippetTarget::Python, &request).unwrap(), "import requests\n\n\
+        url = \"https://example.com/items?a=1&b=2\"\n\
+        files = [\n    (\"title\", (None, \"Cat\")),\n    (\"photo\", (\"cat.png\", open(\"/tmp/cat.png\", \"rb\"), \"image/png\")),\n]\n\n\
+        response = requests.request(\"POST\", url, files=files)\n\n\
+        print(response.status_code)\nprint(response.text)");
+
+    request.method = ::RequestMethod::Custom;
+    request.custom_method = String::new();
+    request.headers.insert(CONTENT_TYPE, HeaderValue::from_static("text/plain"));
+
+    assert!(generate(SnippetTarget::Curl, &request).is_err());
+}
+
+#[test]
+pub fn test_curl_form_parts_are_literal() {
+    let mut request = json_request();
+    request.headers = HeaderMap::new();
+    request.body_type = ::BodyType::Multipart;
+    request.body = String::from("note=<literal\ndoc=@/tmp/a,b.txt;filename=x\"y.txt\npart={\"a\":1};type=application/json");
+
+    assert_eq!(generate(SnippetTarget::Curl, &request).unwrap(),
+        "curl -X POST 'https://example.com/items?a=1&b=2' \\\n  \
+        --form-string 'note=<literal' \\\n  \
+        -F 'doc=@\"/tmp/a,b.txt\";filename=\"x\\\"y.txt\"' \\\n  \
+        -F 'part=\"{\\\"a\\\":1}\";type=application/json'");
+}
+
+#[test]
+pub fn test_httpie_escapes_separators() {
+    let mut request = json_request();
+    request.headers = HeaderMap::new();
+    request.headers.insert("X-Empty", HeaderValue::from_static(""));
+    request.body_type = ::BodyType::Form;
+    request.body = String::from("a:b=@home\nq==x");
+
+    assert_eq!(generate(SnippetTarget::Httpie, &request).unwrap(),
+        "http --form POST 'https://example.com/items?a=1&b=2' \\\n  \
+        'x-empty;' \\\n  \
+        'a\\:b=\\@home' \\\n  \
+        'q=\\=x'");
+}
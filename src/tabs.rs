@@ -0,0 +1,420 @@
+use rusqlite::{Connection, Row};
+use gtk;
+use gtk::prelude::*;
+use gtk_ext;
+use config;
+use actions;
+use timing::{self, Timings};
+use collections::SavedRequest;
+use history::{self, HistoryEntry};
+use editor::RequestEditor;
+
+const TAB_COLUMNS: &'static str = "request_method, custom_method, url, req_headers, body_type, req_body, \
+    body_file, decompress, saved_request, rs_headers, rs_body, current_extension, current_mime, assertions, \
+    pre_script, post_script";
+
+const RESPONSE_COLUMNS: &'static str = "highlight, status, reason, version, final_url, rs_headers, rs_body, \
+    wire_size, content_encoding, dns_ms, connect_ms, tls_ms, waiting_ms, transfer_ms";
+
+const MAX_TITLE_LEN: usize = 32;
+
+// The response is kept the way history keeps it, its text is decoded again when the tab is restored
+#[derive(Debug, Clone, Default)]
+pub struct TabState {
+    pub request: SavedRequest,
+    pub saved_request: Option<i64>,
+    pub rs_headers: String,
+    pub rs_body: String,
+    pub current_extension: Option<String>,
+    pub current_mime: Option<String>,
+    pub response: Option<HistoryEntry>,
+    pub highlight: Option<String>,
+}
+
+pub struct Tab {
+    pub key: u64,
+    pub editor: RequestEditor,
+    pub label: gtk::Label,
+}
+
+#[derive(Default)]
+pub struct Tabs {
+    pub items: Vec<Tab>,
+    next_key: u64,
+}
+
+#[allow(unused_must_use)]
+pub fn create_tables(connection: &Connection) {
+    connection.execute("CREATE TABLE tabs (
+        id INTEGER PRIMARY KEY,
+        position INTEGER NOT NULL,
+        request_method INTEGER NOT NULL,
+        custom_method TEXT NOT NULL,
+        url TEXT NOT NULL,
+        req_headers TEXT NOT NULL,
+        body_type INTEGER NOT NULL,
+        req_body TEXT NOT NULL,
+        body_file TEXT NOT NULL,
+        decompress INTEGER NOT NULL,
+        saved_request INTEGER NULL,
+        rs_headers TEXT NOT NULL,
+        rs_body TEXT NOT NULL,
+        current_extension TEXT NULL,
+        current_mime TEXT NULL,
+        assertions TEXT NOT NULL DEFAULT '',
+        pre_script TEXT NOT NULL DEFAULT '',
+        post_script TEXT NOT NULL DEFAULT ''
+        )", &[]);
+
+    // Tabs stored before checks existed lack the column, elsewhere this fails harmlessly
+    connection.execute("ALTER TABLE tabs ADD COLUMN assertions TEXT NOT NULL DEFAULT ''", &[]);
+    connection.execute("ALTER TABLE tabs ADD COLUMN pre_script TEXT NOT NULL DEFAULT ''", &[]);
+    connection.execute("ALTER TABLE tabs ADD COLUMN post_script TEXT NOT NULL DEFAULT ''", &[]);
+
+    connection.execute("CREATE TABLE tab_responses (
+        position INTEGER PRIMARY KEY,
+        highlight TEXT NULL,
+        status INTEGER NOT NULL,
+        reason TEXT NOT NULL,
+        version TEXT NOT NULL,
+        final_url TEXT NOT NULL,
+        rs_headers TEXT NOT NULL,
+        rs_body BLOB NOT NULL,
+        wire_size INTEGER NOT NULL,
+        content_encoding TEXT NOT NULL,
+        dns_ms REAL NOT NULL,
+        connect_ms REAL NOT NULL,
+        tls_ms REAL NOT NULL,
+        waiting_ms REAL NOT NULL,
+        transfer_ms REAL NOT NULL
+        )", &[]);
+}
+
+pub fn tab_title(method: &str, url: &str) -> String {
+    let url = url.trim();
+    let location = url.find("://").map(|x| &url[x + 3..]).unwrap_or(url);
+
+    if location.is_empty() {
+        return String::from("New request");
+    }
+
+    let title = format!("{} {}", method, location);
+
+    if title.chars().count() > MAX_TITLE_LEN {
+        title.chars().take(MAX_TITLE_LEN - 3).collect::<String>() + "..."
+    } else {
+        title
+    }
+}
+
+impl TabState {
+    pub fn blank() -> Self {
+        TabState {
+            request: SavedRequest {
+                request_method: ::RequestMethod::Get as i32,
+                body_type: ::BodyType::Empty as i32,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    // Before tabs existed the single request lived in the window state
+    pub fn legacy(conf: &config::WindowState) -> Self {
+        TabState {
+            request: SavedRequest {
+                request_method: conf.request_method,
+                custom_method: conf.custom_method.clone(),
+                url: conf.current_url.clone(),
+                req_headers: conf.req_headers.clone(),
+                body_type: conf.body_type,
+                req_body: conf.req_body.clone(),
+                body_file: conf.body_file.clone(),
+                decompress: conf.decompress,
+                ..Default::default()
+            },
+            saved_request: conf.current_saved_request,
+            rs_headers: conf.rs_headers.clone(),
+            rs_body: conf.rs_body.clone(),
+            current_extension: conf.current_extension.clone(),
+            current_mime: conf.current_mime.clone(),
+            ..Default::default()
+        }
+    }
+
+    pub fn title(&self) -> String {
+        tab_title(&self.request.method_name(), &self.request.url)
+    }
+
+    pub fn update_from_editor(&mut self, editor: &RequestEditor) {
+        self.request.update_from_editor(editor);
+        self.rs_headers = editor.get_rs_headers();
+        self.rs_body = editor.get_rs_body();
+
+        let state = editor.state.borrow();
+        self.saved_request = state.saved_request;
+        self.current_extension = state.current_extension.clone();
+        self.current_mime = state.current_mime.clone();
+        self.response = state.response.as_ref().map(|x| HistoryEntry::new(&self.request, x));
+        self.highlight = state.response.as_ref().and_then(|x| x.highlight.clone());
+    }
+
+    pub fn update_to_editor(&self, editor: &RequestEditor) {
+        self.request.update_to_editor(editor);
+        editor.set_saved_request(self.saved_request);
+
+        match self.response {
+            Some(ref entry) => {
+                let resp = entry.to_response().
+                    with_highlight_override(self.highlight.clone()).
+                    with_assertions(&self.request.assertions);
+
+                actions::show_response(editor, resp);
+            },
+            // Tabs stored before responses were kept only have the text that was shown
+            None => {
+                actions::reset_response_view(editor);
+                editor.set_rs_headers(&self.rs_headers);
+                editor.set_rs_body(&self.rs_body);
+
+                {
+                    let mut state = editor.state.borrow_mut();
+                    state.current_extension = self.current_extension.clone();
+                    state.current_mime = self.current_mime.clone();
+                }
+
+                actions::update_resp_body_highlighting(editor);
+            }
+        };
+    }
+}
+
+fn read_tab_state(row: &Row) -> TabState {
+    TabState {
+        request: SavedRequest {
+            request_method: row.get(0),
+            custom_method: row.get(1),
+            url: row.get(2),
+            req_headers: row.get(3),
+            body_type: row.get(4),
+            req_body: row.get(5),
+            body_file: row.get(6),
+            assertions: row.get(13),
+            pre_script: row.get(14),
+            post_script: row.get(15),
+            decompress: row.get::<_, i32>(7) != 0,
+            ..Default::default()
+        },
+        saved_request: row.get(8),
+        rs_headers: row.get(9),
+        rs_body: row.get(10),
+        current_extension: row.get(11),
+        current_mime: row.get(12),
+        response: None,
+        highlight: None,
+    }
+}
+
+fn read_tab_response(row: &Row) -> (Option<String>, HistoryEntry) {
+    (row.get(0), HistoryEntry {
+        id: None,
+        created_at: 0,
+        request: SavedRequest::default(),
+        status: row.get(1),
+        reason: row.get(2),
+        version: row.get(3),
+        final_url: row.get(4),
+        rs_headers: row.get(5),
+        rs_body: row.get(6),
+        wire_size: row.get(7),
+        content_encoding: row.get(8),
+        timings: Timings {
+            dns: history::ms_to_duration(row.get(9)),
+            connect: history::ms_to_duration(row.get(10)),
+            tls: history::ms_to_duration(row.get(11)),
+            waiting: history::ms_to_duration(row.get(12)),
+            transfer: history::ms_to_duration(row.get(13)),
+        },
+        error: String::new(),
+        sent: None,
+    })
+}
+
+// Positions are written without gaps, so a tab finds its response by its index
+pub fn list_tab_states(connection: &Connection) -> Vec<TabState> {
+    let q = format!("SELECT {} FROM tabs ORDER BY position", TAB_COLUMNS);
+    let mut stmt = connection.prepare(&q).unwrap();
+    let tab_iter = stmt.query_map(&[], read_tab_state).unwrap();
+    let mut states: Vec<TabState> = tab_iter.filter_map(|x| x.ok()).collect();
+
+    let q = format!("SELECT {} FROM tab_responses WHERE position = ?1", RESPONSE_COLUMNS);
+
+    for (position, state) in states.iter_mut().enumerate() {
+        if let Ok((highlight, entry)) = connection.query_row(&q, &[&(position as i32)], read_tab_response) {
+            state.highlight = highlight;
+            state.response = Some(entry);
+        }
+    }
+
+    states
+}
+
+#[allow(unused_must_use)]
+pub fn write_tab_states(connection: &Connection, states: &[TabState]) {
+    let q = format!("INSERT INTO tabs (position, {}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, \
+        ?12, ?13, ?14, ?15, ?16, ?17)", TAB_COLUMNS);
+    let response_q = format!("INSERT INTO tab_responses (position, {}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, \
+        ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)", RESPONSE_COLUMNS);
+
+    connection.execute("DELETE FROM tabs", &[]);
+    connection.execute("DELETE FROM tab_responses", &[]);
+
+    for (position, state) in states.iter().enumerate() {
+        connection.execute(&q, &[
+            &(position as i32), &state.request.request_method, &state.request.custom_method,
+            &state.request.url, &state.request.req_headers, &state.request.body_type,
+            &state.request.req_body, &state.request.body_file, &(state.request.decompress as i32),
+            &state.saved_request, &state.rs_headers, &state.rs_body, &state.current_extension,
+            &state.current_mime, &state.request.assertions, &state.request.pre_script,
+            &state.request.post_script
+        ]);
+
+        if let Some(ref entry) = state.response {
+            connection.execute(&response_q, &[
+                &(position as i32), &state.highlight, &entry.status, &entry.reason, &entry.version,
+                &entry.final_url, &entry.rs_headers, &entry.rs_body, &entry.wire_size, &entry.content_encoding,
+                &timing::duration_to_ms(entry.timings.dns), &timing::duration_to_ms(entry.timings.connect),
+                &timing::duration_to_ms(entry.timings.tls), &timing::duration_to_ms(entry.timings.waiting),
+                &timing::duration_to_ms(entry.timings.transfer)
+            ]);
+        }
+    }
+}
+
+fn current_index(m_win: &::MainWindow) -> usize {
+    m_win.request_tabs.get_current_page().unwrap_or(0) as usize
+}
+
+// Notebook pages and tabs are kept in the same order
+pub fn current_editor(m_win: &::MainWindow) -> RequestEditor {
+    let tabs = m_win.tabs.borrow();
+    let index = current_index(m_win).min(tabs.items.len() - 1);
+
+    tabs.items[index].editor.clone()
+}
+
+fn update_label(m_win: &::MainWindow, key: u64) {
+    let tabs = m_win.tabs.borrow();
+
+    if let Some(tab) = tabs.items.iter().find(|x| x.key == key) {
+        let mut request = SavedRequest::default();
+        request.update_from_editor(&tab.editor);
+
+        tab.label.set_text(&tab_title(&request.method_name(), &request.url));
+    }
+}
+
+fn add_page(m_win: &::MainWindow, state: &TabState) -> u32 {
+    let editor = m_win.create_editor();
+    let header = gtk::Box::new(gtk::Orientation::Horizontal, 4);
+    let label = gtk::Label::new(Some(state.title().as_str()));
+    let close_btn = gtk::Button::new_from_icon_name("window-close-symbolic", gtk::IconSize::Menu.into());
+
+    close_btn.set_relief(gtk::ReliefStyle::None);
+    close_btn.set_focus_on_click(false);
+    header.pack_start(&label, true, true, 0);
+    header.pack_start(&close_btn, false, false, 0);
+    header.show_all();
+
+    state.update_to_editor(&editor);
+
+    let key = {
+        let mut tabs = m_win.tabs.borrow_mut();
+        tabs.next_key += 1;
+        let key = tabs.next_key;
+
+        tabs.items.push(Tab {
+            key,
+            editor: editor.clone(),
+            label,
+        });
+
+        key
+    };
+
+    close_btn.connect_clicked(gtk_clone!(m_win => move |_| close_tab(&m_win, key)));
+    editor.url_inp.connect_changed(gtk_clone!(m_win => move |_| update_label(&m_win, key)));
+    editor.method_sel.connect_changed(gtk_clone!(m_win => move |_| update_label(&m_win, key)));
+    editor.custom_method_inp.connect_changed(gtk_clone!(m_win => move |_| update_label(&m_win, key)));
+
+    m_win.request_tabs.append_page(&editor.page, Some(&header))
+}
+
+pub fn new_tab(m_win: &::MainWindow) {
+    let index = add_page(m_win, &TabState::blank());
+    m_win.request_tabs.set_current_page(Some(index));
+}
+
+pub fn close_current(m_win: &::MainWindow) {
+    let key = m_win.tabs.borrow().items.get(current_index(m_win)).map(|x| x.key);
+    key.map(|x| close_tab(m_win, x));
+}
+
+pub fn close_tab(m_win: &::MainWindow, key: u64) {
+    let found = {
+        let tabs = m_win.tabs.borrow();
+        tabs.items.iter().position(|x| x.key == key).map(|x| (x, tabs.items[x].editor.clone(), tabs.items.len()))
+    };
+
+    let (index, editor, count) = match found {
+        Some(x) => x,
+        None => return
+    };
+
+    editor.cancel();
+
+    // The last tab is never removed, it is emptied instead
+    if count == 1 {
+        TabState::blank().update_to_editor(&editor);
+        return;
+    }
+
+    m_win.tabs.borrow_mut().items.remove(index);
+    m_win.request_tabs.remove_page(Some(index as u32));
+}
+
+pub fn store(m_win: &::MainWindow) {
+    let states = m_win.tabs.borrow().items.iter().map(|x| {
+        let mut state = TabState::blank();
+        state.update_from_editor(&x.editor);
+        state
+    }).collect::<Vec<_>>();
+
+    write_tab_states(&config::connect_to_state(), &states);
+    m_win.config.borrow_mut().active_tab = current_index(m_win) as i32;
+}
+
+pub fn bind_to_window(m_win: &::MainWindow) {
+    let actions_box: gtk::Box = gtk_ext::get_gtk_obj_by_id(&m_win.builder, "tabActionsBox");
+    let new_tab_btn = gtk::Button::new_from_icon_name("list-add-symbolic", gtk::IconSize::Menu.into());
+    new_tab_btn.set_relief(gtk::ReliefStyle::None);
+    new_tab_btn.set_tooltip_text("New tab");
+    new_tab_btn.connect_clicked(gtk_clone!(m_win => move |_| new_tab(&m_win)));
+    new_tab_btn.show();
+    actions_box.pack_start(&new_tab_btn, false, false, 0);
+
+    let mut states = list_tab_states(&config::connect_to_state());
+
+    if states.is_empty() {
+        states.push(TabState::legacy(&m_win.config.borrow()));
+    }
+
+    let active = m_win.config.borrow().active_tab.max(0) as usize;
+    let active = if active < states.len() { active } else { 0 };
+
+    for state in &states {
+        add_page(m_win, state);
+    }
+
+    m_win.request_tabs.set_current_page(Some(active as u32));
+}
@@ -0,0 +1,10 @@
+use super::tabs::tab_title;
+
+#[test]
+pub fn test_tab_title() {
+    assert_eq!(tab_title("GET", ""), "New request");
+    assert_eq!(tab_title("GET", "  https://  "), "New request");
+    assert_eq!(tab_title("POST", "https://example.com/users"), "POST example.com/users");
+    assert_eq!(tab_title("GET", "localhost:8080"), "GET localhost:8080");
+    assert_eq!(tab_title("DELETE", "http://api.example.com/v1/organizations/42"), "DELETE api.example.com/v1/org...");
+}